@@ -0,0 +1,182 @@
+// Headless renderer: runs one mode from a JSON job spec without the webview.
+// Uses the same native host and datamosh code as the app, with ffmpeg resolved the same way.
+use std::{
+  io::Read,
+  path::Path,
+  process::{Command, ExitCode, Stdio},
+  sync::atomic::AtomicBool
+};
+
+use bitrot_core::checkpoint::checkpoint_dir;
+use bitrot_core::error::BitrotError;
+use bitrot_core::events::{JobEvent, JobEventPayload, JobStage, LogLevel};
+use bitrot_core::ffmpeg::resolve_ffmpeg_program;
+use bitrot_core::job::{JobRunner, JobSpec};
+use bitrot_core::progress::{JobReporter, ProgressSnapshot};
+use bitrot_core::provenance::RenderProvenance;
+
+const USAGE: &str = "Usage: bitrot-cli [--resume | --replay] [--events] <job.json | ->\n\
+Reads a job spec ({\"mode\", \"config\", \"input\", \"output\", \"encoding\", ...}) from a file or stdin.\n\
+--resume continues an interrupted render of that job from its last finished segment.\n\
+--replay takes a render's <output>.bitrot.json sidecar instead and renders it again.\n\
+--events prints job events (the app's `job-event` schema) as JSON lines on stdout.";
+
+const JOB_ID: &str = "cli";
+
+enum Start<'a> {
+  Fresh,
+  Resume,
+  Replay(&'a Path)
+}
+
+// Progress goes to stderr so stdout stays free for callers that pipe it.
+struct StderrReporter {
+  tag: &'static str
+}
+
+impl JobReporter for StderrReporter {
+  fn log(&self, message: &str) {
+    eprintln!("[{}] {message}", self.tag);
+  }
+
+  fn progress(&self, progress: &ProgressSnapshot) {
+    let total = progress
+      .total_frames
+      .map(|total| total.to_string())
+      .unwrap_or_else(|| "?".into());
+    let fps = progress.fps.unwrap_or(0.0);
+    let eta = progress
+      .eta_seconds
+      .map(|eta| format!("{eta:.1}s"))
+      .unwrap_or_else(|| "?".into());
+    eprintln!(
+      "[{}] frame {}/{total} ({:.1}%) {fps:.1} fps eta {eta}",
+      self.tag, progress.frame, progress.percent
+    );
+  }
+}
+
+// One JSON job event per line, for scripts that follow renders like the app does.
+struct JsonEventReporter {
+  tag: &'static str
+}
+
+impl JsonEventReporter {
+  fn emit(&self, payload: JobEventPayload) {
+    if let Ok(line) = serde_json::to_string(&JobEvent::new(JOB_ID, self.tag, payload)) {
+      println!("{line}");
+    }
+  }
+}
+
+impl JobReporter for JsonEventReporter {
+  fn log(&self, message: &str) {
+    self.log_at(LogLevel::Info, message);
+  }
+
+  fn log_at(&self, level: LogLevel, message: &str) {
+    self.emit(JobEventPayload::log(level, message));
+  }
+
+  fn progress(&self, progress: &ProgressSnapshot) {
+    self.emit(JobEventPayload::Progress(progress.clone()));
+  }
+
+  fn stage(&self, stage: JobStage) {
+    self.emit(JobEventPayload::Stage { stage });
+  }
+}
+
+fn ffmpeg_command(program: &str) -> Command {
+  let mut command = resolve_ffmpeg_program(program).std_command();
+  command.stdin(Stdio::null());
+  command
+}
+
+fn run_job(spec: &JobSpec, start: Start, events: bool) -> Result<(), BitrotError> {
+  let ffmpeg = || -> Result<Command, BitrotError> { Ok(ffmpeg_command("ffmpeg")) };
+  let ffprobe = || -> Result<Command, BitrotError> { Ok(ffmpeg_command("ffprobe")) };
+  let cancel_flag = AtomicBool::new(false);
+  let tag = spec.mode.tag();
+  let json_reporter = JsonEventReporter { tag };
+  let stderr_reporter = StderrReporter { tag };
+  let reporter: &dyn JobReporter = if events {
+    &json_reporter
+  } else {
+    &stderr_reporter
+  };
+  let runner = JobRunner {
+    ffmpeg: &ffmpeg,
+    ffprobe: &ffprobe,
+    cancel_flag: &cancel_flag,
+    reporter
+  };
+  if events {
+    json_reporter.emit(JobEventPayload::Started);
+  }
+  let result = match start {
+    Start::Fresh => runner.run(JOB_ID, spec),
+    Start::Resume => runner.resume(JOB_ID, &checkpoint_dir(&spec.output, tag)),
+    Start::Replay(sidecar) => runner.replay(JOB_ID, sidecar)
+  };
+  if events {
+    json_reporter.emit(JobEventPayload::from_result(&result));
+  }
+  result
+}
+
+fn read_spec(source: &str) -> Result<JobSpec, BitrotError> {
+  let raw = if source == "-" {
+    let mut raw = String::new();
+    std::io::stdin()
+      .read_to_string(&mut raw)
+      .map_err(|error| BitrotError::io("Failed to read job spec from stdin", error))?;
+    raw
+  } else {
+    std::fs::read_to_string(source)
+      .map_err(|error| BitrotError::io(&format!("Failed to read job spec {source}"), error))?
+  };
+  serde_json::from_str(&raw)
+    .map_err(|error| BitrotError::invalid(format!("Invalid job spec: {error}")))
+}
+
+fn main() -> ExitCode {
+  let mut args: Vec<String> = std::env::args().skip(1).collect();
+  let mut take_flag = |flag: &str| {
+    let position = args.iter().position(|arg| arg == flag);
+    position.map(|index| args.remove(index)).is_some()
+  };
+  let resume = take_flag("--resume");
+  let replay = take_flag("--replay");
+  let events = take_flag("--events");
+  let [source] = args.as_slice() else {
+    eprintln!("{USAGE}");
+    return ExitCode::from(2);
+  };
+  if resume && replay {
+    eprintln!("--resume and --replay can't be combined.\n{USAGE}");
+    return ExitCode::from(2);
+  }
+  if source == "-h" || source == "--help" {
+    eprintln!("{USAGE}");
+    return ExitCode::SUCCESS;
+  }
+  let result = if replay {
+    let sidecar = Path::new(source);
+    RenderProvenance::load(sidecar)
+      .and_then(|provenance| run_job(&provenance.spec, Start::Replay(sidecar), events))
+  } else {
+    let start = if resume { Start::Resume } else { Start::Fresh };
+    read_spec(source).and_then(|spec| run_job(&spec, start, events))
+  };
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(error) => {
+      eprintln!("error [{}]: {error}", error.code.as_str());
+      if let Some(tail) = &error.stderr_tail {
+        eprintln!("{tail}");
+      }
+      ExitCode::FAILURE
+    }
+  }
+}
@@ -0,0 +1,505 @@
+// Animated GIF and WebP outputs. Native renders for these formats are encoded into a
+// lossless intermediate first; the builders here turn that file into the final loop.
+// Audio never applies, so they stand apart from the encode and mux builders.
+
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BitrotError, ErrorCode};
+use crate::pipeline::{cleanup_file, run_ffmpeg_output, FfmpegLauncher};
+use crate::progress::JobReporter;
+
+// Each scene palette adds a palettegen/paletteuse pair to one filter graph.
+const MAX_SCENE_PALETTES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimatedFormat {
+  Gif,
+  Webp
+}
+
+impl AnimatedFormat {
+  // Maps a `NativeEncoding.format` value; video containers return None.
+  pub fn from_container(container: &str) -> Option<Self> {
+    match container {
+      "gif" => Some(Self::Gif),
+      "webp" => Some(Self::Webp),
+      _ => None
+    }
+  }
+}
+
+// How GIF palettes are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PaletteMode {
+  // One palette from every frame of the clip.
+  #[default]
+  Global,
+  // A fresh palette after each detected scene cut.
+  PerScene
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Dither {
+  Bayer,
+  Heckbert,
+  FloydSteinberg,
+  Sierra2,
+  #[default]
+  Sierra2_4a,
+  None
+}
+
+impl Dither {
+  fn filter_name(self) -> &'static str {
+    match self {
+      Self::Bayer => "bayer",
+      Self::Heckbert => "heckbert",
+      Self::FloydSteinberg => "floyd_steinberg",
+      Self::Sierra2 => "sierra2",
+      Self::Sierra2_4a => "sierra2_4a",
+      Self::None => "none"
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnimationOptions {
+  #[serde(default)]
+  pub palette: PaletteMode,
+  #[serde(default)]
+  pub dither: Dither,
+  // 0..5; only used by bayer dithering, lower is more visible.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub bayer_scale: Option<u8>,
+  // Scene change score (0..100) that starts a new palette.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub scene_threshold: Option<f64>,
+  // Frames above this rate are dropped; the source rate is kept when lower.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_fps: Option<f64>,
+  // Downscales (never upscales) to fit, keeping the aspect ratio.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_width: Option<u32>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_height: Option<u32>,
+  // 0 loops forever.
+  #[serde(default)]
+  pub loop_count: u32,
+  // WebP only: lossy quality 0..100 (default 75), or lossless.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub quality: Option<u32>,
+  #[serde(default)]
+  pub lossless: bool
+}
+
+impl AnimationOptions {
+  pub fn validate(&self, format: AnimatedFormat) -> Result<(), BitrotError> {
+    if self.max_fps.is_some_and(|fps| fps.is_nan() || fps <= 0.0) {
+      return Err(BitrotError::invalid("maxFps must be above 0."));
+    }
+    if self.max_width == Some(0) || self.max_height == Some(0) {
+      return Err(BitrotError::invalid("maxWidth and maxHeight must be above 0."));
+    }
+    if self.bayer_scale.is_some_and(|scale| scale > 5) {
+      return Err(BitrotError::invalid("bayerScale must be between 0 and 5."));
+    }
+    if self
+      .scene_threshold
+      .is_some_and(|threshold| !(0.0..=100.0).contains(&threshold))
+    {
+      return Err(BitrotError::invalid("sceneThreshold must be between 0 and 100."));
+    }
+    if self.quality.is_some_and(|quality| quality > 100) {
+      return Err(BitrotError::invalid("WebP quality must be between 0 and 100."));
+    }
+    if format == AnimatedFormat::Webp && self.palette == PaletteMode::PerScene {
+      return Err(BitrotError::invalid("Per-scene palettes only apply to GIF output."));
+    }
+    Ok(())
+  }
+
+  // fps and scale caps ahead of the palette filters; None when the source already fits.
+  fn caps_filter(&self, source_fps: f64) -> Option<String> {
+    let mut filters = Vec::new();
+    if let Some(max_fps) = self.max_fps.filter(|max_fps| *max_fps < source_fps) {
+      filters.push(format!("fps={max_fps:.3}"));
+    }
+    let scale = match (self.max_width, self.max_height) {
+      (Some(width), Some(height)) => Some(format!(
+        "scale='min(iw,{width})':'min(ih,{height})':force_original_aspect_ratio=decrease"
+      )),
+      (Some(width), None) => Some(format!("scale='min(iw,{width})':-1")),
+      (None, Some(height)) => Some(format!("scale=-1:'min(ih,{height})'")),
+      (None, None) => None
+    };
+    if let Some(scale) = scale {
+      filters.push(format!("{scale}:flags=lanczos"));
+    }
+    (!filters.is_empty()).then(|| filters.join(","))
+  }
+
+  fn paletteuse_filter(&self) -> String {
+    let mut filter = format!("paletteuse=dither={}", self.dither.filter_name());
+    if self.dither == Dither::Bayer {
+      filter.push_str(&format!(":bayer_scale={}", self.bayer_scale.unwrap_or(2)));
+    }
+    filter
+  }
+}
+
+fn base_args(source: &Path) -> Vec<String> {
+  vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-i".into(),
+    source.to_string_lossy().into_owned()
+  ]
+}
+
+// GIF pass 1 for global palettes: one 256-colour palette image from the whole clip.
+pub fn build_palette_args(
+  source: &Path,
+  palette_path: &Path,
+  options: &AnimationOptions,
+  source_fps: f64
+) -> Vec<String> {
+  let mut filter = options
+    .caps_filter(source_fps)
+    .map(|caps| format!("{caps},"))
+    .unwrap_or_default();
+  filter.push_str("palettegen=stats_mode=full");
+  let mut args = base_args(source);
+  args.extend([
+    "-vf".into(),
+    filter,
+    "-frames:v".into(),
+    "1".into(),
+    palette_path.to_string_lossy().into_owned()
+  ]);
+  args
+}
+
+// GIF pass 1 for per-scene palettes: logs scene cuts, read back by `parse_scene_cuts`.
+pub fn build_scene_detect_args(
+  source: &Path,
+  options: &AnimationOptions,
+  source_fps: f64
+) -> Vec<String> {
+  let threshold = options.scene_threshold.unwrap_or(10.0);
+  let mut filter = options
+    .caps_filter(source_fps)
+    .map(|caps| format!("{caps},"))
+    .unwrap_or_default();
+  filter.push_str(&format!("scdet=threshold={threshold}"));
+  let mut args = base_args(source);
+  // scdet reports cuts at info level.
+  args[3] = "info".into();
+  args.extend(["-vf".into(), filter, "-an".into(), "-f".into(), "null".into(), "-".into()]);
+  args
+}
+
+// Cut times from scdet's "lavfi.scd.time: <seconds>" log lines, in order.
+pub fn parse_scene_cuts(log: &str) -> Vec<f64> {
+  let mut cuts: Vec<f64> = log
+    .lines()
+    .filter_map(|line| line.split("lavfi.scd.time").nth(1))
+    .filter_map(|rest| {
+      rest
+        .trim_start_matches([':', '=', ' '])
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .next()
+        .and_then(|value| value.parse::<f64>().ok())
+    })
+    .filter(|time| *time > 0.0)
+    .collect();
+  cuts.sort_by(f64::total_cmp);
+  cuts.dedup();
+  cuts
+}
+
+// Where GIF pass 2 gets its colours.
+pub enum GifPalette<'a> {
+  Global(&'a Path),
+  // Scene cut times; each scene gets its own palette.
+  Scenes(&'a [f64])
+}
+
+// GIF pass 2: maps the clip onto its palette(s) with the chosen dither.
+pub fn build_gif_args(
+  source: &Path,
+  palette: GifPalette,
+  output_path: &Path,
+  options: &AnimationOptions,
+  source_fps: f64
+) -> Vec<String> {
+  let caps = options.caps_filter(source_fps);
+  let paletteuse = options.paletteuse_filter();
+  let mut args = base_args(source);
+  let graph = match palette {
+    GifPalette::Global(palette_path) => {
+      args.extend(["-i".into(), palette_path.to_string_lossy().into_owned()]);
+      match caps {
+        Some(caps) => format!("[0:v]{caps}[x];[x][1:v]{paletteuse}"),
+        None => format!("[0:v][1:v]{paletteuse}")
+      }
+    }
+    GifPalette::Scenes(cuts) => {
+      let cuts = &cuts[..cuts.len().min(MAX_SCENE_PALETTES - 1)];
+      let count = cuts.len() + 1;
+      let mut graph = String::from("[0:v]");
+      if let Some(caps) = caps {
+        graph.push_str(&caps);
+        graph.push(',');
+      }
+      graph.push_str(&format!("split={count}"));
+      for index in 0..count {
+        graph.push_str(&format!("[s{index}]"));
+      }
+      for index in 0..count {
+        let mut trim = String::from("trim");
+        let mut bounds = Vec::new();
+        if index > 0 {
+          bounds.push(format!("start={}", cuts[index - 1]));
+        }
+        if let Some(end) = cuts.get(index) {
+          bounds.push(format!("end={end}"));
+        }
+        if !bounds.is_empty() {
+          trim.push('=');
+          trim.push_str(&bounds.join(":"));
+        }
+        graph.push_str(&format!(
+          ";[s{index}]{trim},setpts=PTS-STARTPTS,split[a{index}][b{index}];\
+           [a{index}]palettegen=stats_mode=full[p{index}];\
+           [b{index}][p{index}]{paletteuse}[c{index}]"
+        ));
+      }
+      graph.push(';');
+      for index in 0..count {
+        graph.push_str(&format!("[c{index}]"));
+      }
+      graph.push_str(&format!("concat=n={count}:v=1:a=0"));
+      graph
+    }
+  };
+  args.extend([
+    "-filter_complex".into(),
+    graph,
+    "-loop".into(),
+    options.loop_count.to_string(),
+    output_path.to_string_lossy().into_owned()
+  ]);
+  args
+}
+
+// Animated WebP in one pass; keeps alpha when the intermediate carries it.
+pub fn build_webp_args(
+  source: &Path,
+  output_path: &Path,
+  options: &AnimationOptions,
+  source_fps: f64
+) -> Vec<String> {
+  let mut args = base_args(source);
+  if let Some(caps) = options.caps_filter(source_fps) {
+    args.extend(["-vf".into(), caps]);
+  }
+  args.extend(["-an".into(), "-c:v".into(), "libwebp_anim".into()]);
+  if options.lossless {
+    args.extend(["-lossless".into(), "1".into()]);
+  } else {
+    args.extend(["-q:v".into(), options.quality.unwrap_or(75).to_string()]);
+  }
+  args.extend([
+    "-loop".into(),
+    options.loop_count.to_string(),
+    output_path.to_string_lossy().into_owned()
+  ]);
+  args
+}
+
+// Runs scene detection and returns the cuts; a failed run is an encode failure.
+fn detect_scene_cuts(
+  ffmpeg: FfmpegLauncher,
+  source: &Path,
+  options: &AnimationOptions,
+  source_fps: f64
+) -> Result<Vec<f64>, BitrotError> {
+  let output = ffmpeg()?
+    .args(build_scene_detect_args(source, options, source_fps))
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start scene detection: {error}"))
+    })?;
+  let log = String::from_utf8_lossy(&output.stderr);
+  if !output.status.success() {
+    return Err(
+      BitrotError::new(ErrorCode::EncodeFailed, "Scene detection failed.").with_stderr(&log)
+    );
+  }
+  Ok(parse_scene_cuts(&log))
+}
+
+// Turns the joined intermediate into the final GIF or WebP.
+pub fn write_animation(
+  ffmpeg: FfmpegLauncher,
+  source: &Path,
+  output_path: &Path,
+  format: AnimatedFormat,
+  options: &AnimationOptions,
+  source_fps: f64,
+  reporter: &dyn JobReporter
+) -> Result<(), BitrotError> {
+  if format == AnimatedFormat::Webp {
+    reporter.log("Writing animated WebP.");
+    return run_ffmpeg_output(
+      ffmpeg,
+      build_webp_args(source, output_path, options, source_fps),
+      ErrorCode::EncodeFailed,
+      "WebP encode"
+    );
+  }
+
+  match options.palette {
+    PaletteMode::Global => {
+      let palette_path = source.with_extension("palette.png");
+      reporter.log("Building GIF palette.");
+      let result = run_ffmpeg_output(
+        ffmpeg,
+        build_palette_args(source, &palette_path, options, source_fps),
+        ErrorCode::EncodeFailed,
+        "Palette generation"
+      )
+      .and_then(|()| {
+        run_ffmpeg_output(
+          ffmpeg,
+          build_gif_args(
+            source,
+            GifPalette::Global(&palette_path),
+            output_path,
+            options,
+            source_fps
+          ),
+          ErrorCode::EncodeFailed,
+          "GIF encode"
+        )
+      });
+      cleanup_file(&palette_path);
+      result
+    }
+    PaletteMode::PerScene => {
+      let cuts = detect_scene_cuts(ffmpeg, source, options, source_fps)?;
+      if cuts.len() >= MAX_SCENE_PALETTES {
+        reporter.log(&format!(
+          "Found {} scene cuts; palettes are capped at {MAX_SCENE_PALETTES}.",
+          cuts.len()
+        ));
+      } else {
+        reporter.log(&format!("Building {} GIF palette(s).", cuts.len() + 1));
+      }
+      run_ffmpeg_output(
+        ffmpeg,
+        build_gif_args(source, GifPalette::Scenes(&cuts), output_path, options, source_fps),
+        ErrorCode::EncodeFailed,
+        "GIF encode"
+      )
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn filter_after<'a>(args: &'a [String], flag: &str) -> &'a str {
+    let index = args.iter().position(|arg| arg == flag).unwrap() + 1;
+    &args[index]
+  }
+
+  #[test]
+  fn gif_passes_share_caps_and_palette_settings() {
+    let options = AnimationOptions {
+      dither: Dither::Bayer,
+      bayer_scale: Some(3),
+      max_fps: Some(15.0),
+      max_width: Some(480),
+      loop_count: 2,
+      ..AnimationOptions::default()
+    };
+    let source = Path::new("clip.video.mkv");
+    let palette = Path::new("clip.palette.png");
+    let caps = "fps=15.000,scale='min(iw,480)':-1:flags=lanczos";
+
+    let pass1 = build_palette_args(source, palette, &options, 30.0);
+    assert_eq!(filter_after(&pass1, "-vf"), format!("{caps},palettegen=stats_mode=full"));
+
+    let pass2 = build_gif_args(source, GifPalette::Global(palette), Path::new("out.gif"), &options, 30.0);
+    assert_eq!(
+      filter_after(&pass2, "-filter_complex"),
+      format!("[0:v]{caps}[x];[x][1:v]paletteuse=dither=bayer:bayer_scale=3")
+    );
+    assert_eq!(filter_after(&pass2, "-loop"), "2");
+
+    // A source already under the caps is left alone.
+    let slow = build_gif_args(source, GifPalette::Global(palette), Path::new("out.gif"), &options, 12.0);
+    assert!(filter_after(&slow, "-filter_complex").starts_with("[0:v]scale="));
+  }
+
+  #[test]
+  fn scene_palettes_follow_detected_cuts() {
+    let log = "[scdet @ 0x1] lavfi.scd.score: 41.200, lavfi.scd.time: 1.5\n\
+               frame=   90 fps=0.0 q=-0.0 size=N/A\n\
+               [scdet @ 0x1] lavfi.scd.score: 22.800, lavfi.scd.time: 3.25\n";
+    let cuts = parse_scene_cuts(log);
+    assert_eq!(cuts, [1.5, 3.25]);
+
+    let options = AnimationOptions {
+      palette: PaletteMode::PerScene,
+      dither: Dither::None,
+      ..AnimationOptions::default()
+    };
+    let args = build_gif_args(
+      Path::new("clip.video.mkv"),
+      GifPalette::Scenes(&cuts),
+      Path::new("out.gif"),
+      &options,
+      24.0
+    );
+    let graph = filter_after(&args, "-filter_complex");
+    assert!(graph.starts_with("[0:v]split=3[s0][s1][s2];"));
+    assert!(graph.contains("[s0]trim=end=1.5,"));
+    assert!(graph.contains("[s1]trim=start=1.5:end=3.25,"));
+    assert!(graph.contains("[s2]trim=start=3.25,"));
+    assert_eq!(graph.matches("palettegen").count(), 3);
+    assert!(graph.ends_with("[c0][c1][c2]concat=n=3:v=1:a=0"));
+    assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 1);
+  }
+
+  #[test]
+  fn animation_options_reject_out_of_range_values() {
+    let options = |options: AnimationOptions| options.validate(AnimatedFormat::Gif);
+    assert!(options(AnimationOptions::default()).is_ok());
+    assert!(options(AnimationOptions { max_fps: Some(0.0), ..AnimationOptions::default() }).is_err());
+    assert!(options(AnimationOptions { bayer_scale: Some(6), ..AnimationOptions::default() }).is_err());
+    let per_scene = AnimationOptions { palette: PaletteMode::PerScene, ..AnimationOptions::default() };
+    assert!(per_scene.validate(AnimatedFormat::Webp).is_err());
+
+    let webp = build_webp_args(
+      Path::new("clip.video.mkv"),
+      Path::new("out.webp"),
+      &AnimationOptions { quality: Some(60), ..AnimationOptions::default() },
+      24.0
+    );
+    assert_eq!(filter_after(&webp, "-c:v"), "libwebp_anim");
+    assert_eq!(filter_after(&webp, "-q:v"), "60");
+    assert!(!webp.contains(&"-vf".to_string()));
+  }
+}
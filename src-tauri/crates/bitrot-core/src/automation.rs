@@ -0,0 +1,305 @@
+// Keyframed parameter automation: per-parameter time -> value tracks evaluated every frame.
+// Tracks address config fields by their JSON names ("rotationDegrees", "steps.1.config.intensity"),
+// so every serde config (including chains) is automatable without per-mode code.
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::effect::NativeEffect;
+use crate::error::BitrotError;
+use crate::sample::Sample;
+
+// How a keyframe blends into the next one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Interpolation {
+  #[default]
+  Linear,
+  Ease,
+  Step
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Keyframe {
+  // Seconds from the start of the rendered range.
+  pub time: f64,
+  pub value: f64,
+  #[serde(default)]
+  pub interpolation: Interpolation
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyframeTrack(pub Vec<Keyframe>);
+
+// Parameter path -> track, as sent next to a mode config.
+pub type Automation = BTreeMap<String, KeyframeTrack>;
+
+impl KeyframeTrack {
+  // Holds the first/last value outside the keyed range.
+  pub fn value_at(&self, time: f64) -> Option<f64> {
+    let keyframes = &self.0;
+    let first = keyframes.first()?;
+    if time <= first.time {
+      return Some(first.value);
+    }
+    for pair in keyframes.windows(2) {
+      let (from, to) = (pair[0], pair[1]);
+      if time >= to.time {
+        continue;
+      }
+      let span = to.time - from.time;
+      if span <= 0.0 {
+        return Some(to.value);
+      }
+      let t = ((time - from.time) / span).clamp(0.0, 1.0);
+      let eased = match from.interpolation {
+        Interpolation::Linear => t,
+        // Smoothstep keeps velocity at zero on both keys.
+        Interpolation::Ease => t * t * (3.0 - 2.0 * t),
+        Interpolation::Step => 0.0
+      };
+      return Some(from.value + (to.value - from.value) * eased);
+    }
+    keyframes.last().map(|keyframe| keyframe.value)
+  }
+}
+
+fn lookup_mut<'a>(root: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+  path.split('.').try_fold(root, |node, segment| match node {
+    Value::Object(map) => map.get_mut(segment),
+    Value::Array(items) => segment
+      .parse::<usize>()
+      .ok()
+      .and_then(|index| items.get_mut(index)),
+    _ => None
+  })
+}
+
+// Keeps integer fields integral so u32 configs still deserialize.
+fn patch_number(target: &mut Value, value: f64) {
+  let patched = match target {
+    Value::Number(number) if number.is_u64() => Value::from(value.round().max(0.0) as u64),
+    Value::Number(number) if number.is_i64() => Value::from(value.round() as i64),
+    _ => serde_json::Number::from_f64(value)
+      .map(Value::Number)
+      .unwrap_or(Value::Null)
+  };
+  *target = patched;
+}
+
+// Wraps an effect so its config is re-evaluated from the tracks on every frame.
+// Without tracks it delegates straight to the base config.
+pub struct Automated<E> {
+  base: E,
+  base_value: Value,
+  tracks: Vec<(String, KeyframeTrack)>,
+  fps: f64,
+  // First frame whose config could not be built; reported once per render.
+  warned: AtomicBool,
+  warning: Mutex<Option<String>>
+}
+
+impl<E> Automated<E>
+where
+  E: NativeEffect + Serialize + DeserializeOwned
+{
+  pub fn new(base: E, automation: Automation, fps: f64) -> Result<Self, BitrotError> {
+    let mut base_value = serde_json::to_value(&base)
+      .map_err(|error| BitrotError::invalid(format!("Invalid config: {error}")))?;
+    let mut tracks = Vec::with_capacity(automation.len());
+    for (path, mut track) in automation {
+      if track.0.is_empty() {
+        continue;
+      }
+      if track
+        .0
+        .iter()
+        .any(|keyframe| !keyframe.time.is_finite() || !keyframe.value.is_finite())
+      {
+        return Err(BitrotError::invalid(format!(
+          "Automation for \"{path}\" has non-finite keyframes."
+        )));
+      }
+      let is_number = lookup_mut(&mut base_value, &path).is_some_and(|target| target.is_number());
+      if !is_number {
+        return Err(BitrotError::invalid(format!(
+          "Unknown automated parameter \"{path}\" for {}.",
+          E::LABEL.to_lowercase()
+        )));
+      }
+      track.0.sort_by(|left, right| left.time.total_cmp(&right.time));
+      tracks.push((path, track));
+    }
+
+    let automated = Self {
+      base,
+      base_value,
+      tracks,
+      fps: if fps > 0.0 { fps } else { 30.0 },
+      warned: AtomicBool::new(false),
+      warning: Mutex::new(None)
+    };
+    // Catch out-of-range values (e.g. a u8 pushed past 255) before the render starts.
+    // Between keys a value stays between its neighbours, so checking every key time
+    // covers each segment.
+    if !automated.tracks.is_empty() {
+      let key_times = automated
+        .tracks
+        .iter()
+        .flat_map(|(_, track)| track.0.iter().map(|keyframe| keyframe.time));
+      for time in std::iter::once(0.0).chain(key_times) {
+        automated.config_at(time)?;
+      }
+    }
+    Ok(automated)
+  }
+
+  pub fn is_animated(&self) -> bool {
+    !self.tracks.is_empty()
+  }
+
+  // Config with every track applied at `time` seconds.
+  pub fn config_at(&self, time: f64) -> Result<E, BitrotError> {
+    let mut value = self.base_value.clone();
+    for (path, track) in &self.tracks {
+      if let (Some(target), Some(keyed)) = (lookup_mut(&mut value, path), track.value_at(time)) {
+        patch_number(target, keyed);
+      }
+    }
+    serde_json::from_value(value).map_err(|error| {
+      BitrotError::invalid(format!("Automated config is invalid at {time:.3}s: {error}"))
+    })
+  }
+}
+
+impl<E> NativeEffect for Automated<E>
+where
+  E: NativeEffect + Serialize + DeserializeOwned
+{
+  const LABEL: &'static str = E::LABEL;
+  const TAG: &'static str = E::TAG;
+
+  type Workspace<S: Sample> = E::Workspace<S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    self.base.workspace(width, height)
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    if !self.is_animated() {
+      return self.base.process_frame(frame, workspace, frame_index);
+    }
+    let time = frame_index as f64 / self.fps;
+    // `new` checked every keyframe, so this is rare; the frame keeps the base config and
+    // the host logs a warning.
+    match self.config_at(time) {
+      Ok(config) => config.process_frame(frame, workspace, frame_index),
+      Err(error) => {
+        if !self.warned.swap(true, Ordering::Relaxed) {
+          let warning = format!(
+            "{} Frame {frame_index} and any others like it use the unautomated config.",
+            error.message
+          );
+          *self.warning.lock().unwrap_or_else(|error| error.into_inner()) = Some(warning);
+        }
+        self.base.process_frame(frame, workspace, frame_index)
+      }
+    }
+  }
+
+  fn warmup_frames(&self) -> u64 {
+    self.base.warmup_frames()
+  }
+
+  fn take_warning(&self) -> Option<String> {
+    if !self.warned.load(Ordering::Relaxed) {
+      return None;
+    }
+    self.warning.lock().unwrap_or_else(|error| error.into_inner()).take()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn track(keyframes: &[(f64, f64, Interpolation)]) -> KeyframeTrack {
+    let keyframes = keyframes
+      .iter()
+      .map(|&(time, value, interpolation)| Keyframe { time, value, interpolation })
+      .collect();
+    KeyframeTrack(keyframes)
+  }
+
+  #[test]
+  fn track_interpolates_between_keys_and_holds_outside() {
+    let linear = track(&[(1.0, 0.0, Interpolation::Linear), (3.0, 10.0, Interpolation::Linear)]);
+    assert_eq!(linear.value_at(0.0), Some(0.0));
+    assert_eq!(linear.value_at(2.0), Some(5.0));
+    assert_eq!(linear.value_at(9.0), Some(10.0));
+
+    let step = track(&[(0.0, 2.0, Interpolation::Step), (1.0, 8.0, Interpolation::Linear)]);
+    assert_eq!(step.value_at(0.99), Some(2.0));
+    assert_eq!(step.value_at(1.0), Some(8.0));
+
+    let ease = track(&[(0.0, 0.0, Interpolation::Ease), (1.0, 1.0, Interpolation::Linear)]);
+    assert_eq!(ease.value_at(0.5), Some(0.5));
+    assert!(ease.value_at(0.25).unwrap() < 0.25);
+  }
+
+  #[test]
+  fn automated_config_patches_nested_and_integer_fields() {
+    use crate::modes::block_shift::BlockShiftConfig;
+    use crate::modes::chain::{ChainConfig, ChainStep};
+
+    let chain = ChainConfig {
+      steps: vec![ChainStep::BlockShift(BlockShiftConfig {
+        block_size: 8,
+        max_offset: 4,
+        offset_step: 1,
+        intensity: 100.0,
+        seed: 1
+      })]
+    };
+    let mut automation = Automation::new();
+    automation.insert(
+      "steps.0.config.maxOffset".into(),
+      track(&[(0.0, 0.0, Interpolation::Linear), (2.0, 21.0, Interpolation::Linear)])
+    );
+    let automated = Automated::new(chain, automation, 24.0).unwrap();
+    let ChainStep::BlockShift(config) = &automated.config_at(1.0).unwrap().steps[0] else {
+      panic!("expected a block shift step");
+    };
+    assert_eq!(config.max_offset, 11);
+
+    let mut unknown = Automation::new();
+    unknown.insert("steps.0.config.missing".into(), track(&[(0.0, 1.0, Interpolation::Step)]));
+    let chain = ChainConfig { steps: Vec::new() };
+    assert!(Automated::new(chain, unknown, 24.0).is_err());
+
+    // Fine at the start, past u32 at a later key: rejected up front, not mid-render.
+    let block_shift = BlockShiftConfig {
+      block_size: 8,
+      max_offset: 4,
+      offset_step: 1,
+      intensity: 100.0,
+      seed: 1
+    };
+    let mut overflow = Automation::new();
+    overflow.insert(
+      "maxOffset".into(),
+      track(&[(0.0, 4.0, Interpolation::Linear), (3.0, 5e9, Interpolation::Linear)])
+    );
+    assert!(Automated::new(block_shift, overflow, 24.0).is_err());
+  }
+}
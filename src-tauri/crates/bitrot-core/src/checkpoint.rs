@@ -0,0 +1,229 @@
+// Checkpointed native renders: frames are encoded in fixed-length segments next to the
+// output, with a small JSON journal recording the job spec and the last finished segment.
+// A canceled or crashed render resumes from there instead of frame zero.
+use std::{
+  fs,
+  path::{Path, PathBuf}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BitrotError;
+use crate::job::JobSpec;
+use crate::pipeline::cleanup_file;
+
+pub const JOURNAL_VERSION: u32 = 1;
+// Long enough that the concat step is noise, short enough that a crash loses little work.
+pub const SEGMENT_SECONDS: f64 = 30.0;
+const JOURNAL_FILE_NAME: &str = "journal.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderJournal {
+  pub version: u32,
+  pub spec: JobSpec,
+  // Fixed when the render starts so resumed segments line up with the finished ones.
+  pub segment_frames: u64,
+  pub completed_segments: u64
+}
+
+// Checkpoint directory next to the output: "<stem>.<tag>.checkpoint".
+pub fn checkpoint_dir(output_path: &str, tag: &str) -> PathBuf {
+  let output = PathBuf::from(output_path);
+  let stem = output
+    .file_stem()
+    .and_then(|value| value.to_str())
+    .unwrap_or("native");
+  let safe_tag = tag.replace(|c: char| !c.is_ascii_alphanumeric(), "-");
+  output.with_file_name(format!("{stem}.{safe_tag}.checkpoint"))
+}
+
+// Checkpoint directory an earlier run of `spec` left behind, if its journal holds the same
+// spec; how the render queue picks up crashed and retried entries instead of frame zero.
+pub fn resumable_checkpoint(spec: &JobSpec) -> Option<PathBuf> {
+  let dir = checkpoint_dir(&spec.output, spec.mode.tag());
+  let checkpoint = Checkpoint::open(&dir).ok()?;
+  let recorded = serde_json::to_value(&checkpoint.journal.spec).ok()?;
+  (recorded == serde_json::to_value(spec).ok()?).then_some(dir)
+}
+
+pub struct Checkpoint {
+  dir: PathBuf,
+  pub journal: RenderJournal
+}
+
+impl Checkpoint {
+  // Starts from scratch; segments left by an earlier render of the same output are dropped.
+  pub fn start(dir: PathBuf, spec: JobSpec, fps: f64) -> Result<Self, BitrotError> {
+    let safe_fps = if fps > 0.0 { fps } else { 30.0 };
+    let checkpoint = Self {
+      dir,
+      journal: RenderJournal {
+        version: JOURNAL_VERSION,
+        spec,
+        segment_frames: ((SEGMENT_SECONDS * safe_fps).ceil() as u64).max(1),
+        completed_segments: 0
+      }
+    };
+    checkpoint.remove();
+    fs::create_dir_all(&checkpoint.dir)
+      .map_err(|error| BitrotError::io("Failed to create checkpoint directory", error))?;
+    checkpoint.save()?;
+    Ok(checkpoint)
+  }
+
+  // Reopens a checkpoint to resume it. The journal only counts segments that are still on
+  // disk, so a missing file is rendered again rather than leaving a gap.
+  pub fn open(dir: &Path) -> Result<Self, BitrotError> {
+    let raw = fs::read_to_string(dir.join(JOURNAL_FILE_NAME)).map_err(|error| {
+      BitrotError::io(&format!("No resumable render at {}", dir.display()), error)
+    })?;
+    let journal: RenderJournal = serde_json::from_str(&raw)
+      .map_err(|error| BitrotError::invalid(format!("Invalid render journal: {error}")))?;
+    if journal.version != JOURNAL_VERSION {
+      return Err(BitrotError::invalid(format!(
+        "Render journal version {} is not supported (expected {JOURNAL_VERSION}).",
+        journal.version
+      )));
+    }
+    if journal.segment_frames == 0 {
+      return Err(BitrotError::invalid("Render journal has an empty segment length."));
+    }
+    let mut checkpoint = Self {
+      dir: dir.to_path_buf(),
+      journal
+    };
+    let present = (0..checkpoint.journal.completed_segments)
+      .take_while(|index| checkpoint.segment_path(*index).is_file())
+      .count() as u64;
+    if present != checkpoint.journal.completed_segments {
+      checkpoint.journal.completed_segments = present;
+      checkpoint.save()?;
+    }
+    Ok(checkpoint)
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  // First frame the next segment starts at.
+  pub fn completed_frames(&self) -> u64 {
+    self.journal.completed_segments * self.journal.segment_frames
+  }
+
+  // Segments use the temp video's container so the concat step can copy streams as-is.
+  pub(crate) fn segment_path(&self, index: u64) -> PathBuf {
+    let format = self
+      .journal
+      .spec
+      .encoding
+      .as_ref()
+      .map(|encoding| encoding.segment_container())
+      .filter(|format| !format.is_empty())
+      .unwrap_or_else(|| "mp4".into());
+    self.dir.join(format!("segment-{index:05}.{format}"))
+  }
+
+  // Writes through a temp file so a crash mid-save never leaves a truncated journal.
+  fn save(&self) -> Result<(), BitrotError> {
+    let raw = serde_json::to_string_pretty(&self.journal).map_err(|error| {
+      BitrotError::internal(format!("Failed to serialize render journal: {error}"))
+    })?;
+    let path = self.dir.join(JOURNAL_FILE_NAME);
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, raw)
+      .map_err(|error| BitrotError::io("Failed to write render journal", error))?;
+    fs::rename(&temp_path, &path)
+      .map_err(|error| BitrotError::io("Failed to save render journal", error))
+  }
+
+  // Called once a segment's encoder has exited cleanly.
+  pub(crate) fn complete_segment(&mut self, index: u64) -> Result<(), BitrotError> {
+    self.journal.completed_segments = index + 1;
+    self.save()
+  }
+
+  // Concat demuxer list for segments `0..count`; quotes are escaped the way ffmpeg expects.
+  pub(crate) fn write_concat_list(&self, count: u64) -> Result<PathBuf, BitrotError> {
+    let list: String = (0..count)
+      .map(|index| {
+        let path = self.segment_path(index).to_string_lossy().replace('\'', "'\\''");
+        format!("file '{path}'\n")
+      })
+      .collect();
+    let path = self.dir.join("segments.txt");
+    fs::write(&path, list).map_err(|error| BitrotError::io("Failed to write segment list", error))?;
+    Ok(path)
+  }
+
+  // Drops segments past the journal (e.g. one cut short) so a resume renders them again.
+  pub(crate) fn discard_incomplete(&self) {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return;
+    };
+    for entry in entries.flatten() {
+      let name = entry.file_name();
+      let index = name
+        .to_str()
+        .and_then(|name| name.strip_prefix("segment-"))
+        .and_then(|rest| rest.split('.').next())
+        .and_then(|index| index.parse::<u64>().ok());
+      if index.is_some_and(|index| index >= self.journal.completed_segments) {
+        cleanup_file(&entry.path());
+      }
+    }
+  }
+
+  pub fn remove(&self) {
+    let _ = fs::remove_dir_all(&self.dir);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spec() -> JobSpec {
+    serde_json::from_value(serde_json::json!({
+      "mode": "block-shift",
+      "config": {
+        "blockSize": 16,
+        "maxOffset": 24,
+        "offsetStep": 4,
+        "intensity": 100.0,
+        "seed": 7
+      },
+      "input": "in.mp4",
+      "output": "out.webm",
+      "encoding": {
+        "encoder": "libvpx-vp9",
+        "preset": "medium",
+        "format": "webm",
+        "audioEnabled": false
+      }
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn reopened_checkpoint_only_counts_segments_on_disk() {
+    let dir = std::env::temp_dir().join(format!("bitrot-checkpoint-test-{}", std::process::id()));
+    let mut checkpoint = Checkpoint::start(dir.clone(), spec(), 24.0).unwrap();
+    assert_eq!(checkpoint.journal.segment_frames, 720);
+    for index in 0..3 {
+      fs::write(checkpoint.segment_path(index), b"segment").unwrap();
+      checkpoint.complete_segment(index).unwrap();
+    }
+    assert!(checkpoint.segment_path(0).ends_with("segment-00000.webm"));
+    assert_eq!(Checkpoint::open(&dir).unwrap().completed_frames(), 3 * 720);
+
+    fs::remove_file(checkpoint.segment_path(1)).unwrap();
+    let reopened = Checkpoint::open(&dir).unwrap();
+    assert_eq!(reopened.journal.completed_segments, 1);
+    reopened.discard_incomplete();
+    assert!(checkpoint.segment_path(0).exists());
+    assert!(!checkpoint.segment_path(2).exists());
+    reopened.remove();
+  }
+}
@@ -0,0 +1,37 @@
+// Effect contract for the native host: one RGBA frame in, one RGBA frame out.
+// Modes only describe their pixel work here; decode/encode, progress and cleanup live in the host.
+use crate::sample::Sample;
+
+// Effects run on worker threads, so configs must be shareable across them.
+pub trait NativeEffect: Send + Sync {
+  // Display label used in job logs ("Block shift started.").
+  const LABEL: &'static str;
+  // Kebab-case tag used for event names and temp files ("block-shift-progress").
+  const TAG: &'static str;
+
+  // Reusable per-job buffers so frames do not allocate; sized for one sample depth.
+  type Workspace<S: Sample>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S>;
+
+  // Processes one RGBA frame (8 or 16 bits per channel) and returns the output held by
+  // the workspace.
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S];
+
+  // Frames before `frame_index` the workspace must see again to rebuild temporal state
+  // when a render resumes mid-stream; stateless effects need none.
+  fn warmup_frames(&self) -> u64 {
+    0
+  }
+
+  // A problem met while processing that the host logs as a warning, taken once; wrappers
+  // pass their base's through.
+  fn take_warning(&self) -> Option<String> {
+    None
+  }
+}
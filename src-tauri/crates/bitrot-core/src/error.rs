@@ -0,0 +1,160 @@
+// Structured errors returned by core functions and every Tauri command. UIs and scripts
+// match on `code` (stable, kebab-case); `message` is for people.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// ffmpeg stderr can run to thousands of lines; the end holds the actual failure.
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+  // The user canceled the job.
+  Canceled,
+  // Arguments, config or job spec rejected before any work started.
+  InvalidRequest,
+  // The output would overwrite the input.
+  OutputConflict,
+  // Unknown job/preview id, missing file or checkpoint.
+  NotFound,
+  // ffmpeg/ffprobe could not be resolved.
+  FfmpegUnavailable,
+  // The resolved ffmpeg lacks an encoder, filter or pixel format the request needs.
+  FfmpegUnsupported,
+  // A process could not be started.
+  SpawnFailed,
+  DecodeFailed,
+  EncodeFailed,
+  MuxFailed,
+  // Any other ffmpeg/ffprobe run that exited with an error.
+  FfmpegFailed,
+  DatamoshFailed,
+  PreviewFailed,
+  // Filesystem errors.
+  Io,
+  // Poisoned locks, panicked workers and other bugs.
+  Internal
+}
+
+impl ErrorCode {
+  // Same string the code serializes to.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      ErrorCode::Canceled => "canceled",
+      ErrorCode::InvalidRequest => "invalid-request",
+      ErrorCode::OutputConflict => "output-conflict",
+      ErrorCode::NotFound => "not-found",
+      ErrorCode::FfmpegUnavailable => "ffmpeg-unavailable",
+      ErrorCode::FfmpegUnsupported => "ffmpeg-unsupported",
+      ErrorCode::SpawnFailed => "spawn-failed",
+      ErrorCode::DecodeFailed => "decode-failed",
+      ErrorCode::EncodeFailed => "encode-failed",
+      ErrorCode::MuxFailed => "mux-failed",
+      ErrorCode::FfmpegFailed => "ffmpeg-failed",
+      ErrorCode::DatamoshFailed => "datamosh-failed",
+      ErrorCode::PreviewFailed => "preview-failed",
+      ErrorCode::Io => "io",
+      ErrorCode::Internal => "internal"
+    }
+  }
+
+  // Whether the same request may succeed when simply run again.
+  pub fn retryable(self) -> bool {
+    matches!(self, ErrorCode::SpawnFailed | ErrorCode::Io | ErrorCode::Internal)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitrotError {
+  pub code: ErrorCode,
+  pub message: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub stderr_tail: Option<String>,
+  pub retryable: bool
+}
+
+impl BitrotError {
+  pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      stderr_tail: None,
+      retryable: code.retryable()
+    }
+  }
+
+  pub fn canceled() -> Self {
+    Self::new(ErrorCode::Canceled, "Canceled")
+  }
+
+  pub fn invalid(message: impl Into<String>) -> Self {
+    Self::new(ErrorCode::InvalidRequest, message)
+  }
+
+  pub fn not_found(message: impl Into<String>) -> Self {
+    Self::new(ErrorCode::NotFound, message)
+  }
+
+  pub fn internal(message: impl Into<String>) -> Self {
+    Self::new(ErrorCode::Internal, message)
+  }
+
+  // "<context>: <error>", with missing files reported as not found.
+  pub fn io(context: &str, error: std::io::Error) -> Self {
+    let code = if error.kind() == std::io::ErrorKind::NotFound {
+      ErrorCode::NotFound
+    } else {
+      ErrorCode::Io
+    };
+    Self::new(code, format!("{context}: {error}"))
+  }
+
+  // Keeps the last lines of a process's stderr; blank output attaches nothing.
+  pub fn with_stderr(mut self, stderr: &str) -> Self {
+    let lines: Vec<&str> = stderr
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty())
+      .collect();
+    let start = lines.len().saturating_sub(STDERR_TAIL_LINES);
+    self.stderr_tail = (!lines.is_empty()).then(|| lines[start..].join("\n"));
+    self
+  }
+
+  pub fn is_canceled(&self) -> bool {
+    self.code == ErrorCode::Canceled
+  }
+}
+
+impl fmt::Display for BitrotError {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    formatter.write_str(&self.message)
+  }
+}
+
+impl std::error::Error for BitrotError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn errors_serialize_with_code_and_stderr_tail() {
+    let stderr: String = (1..=30).map(|line| format!("line {line}\n\n")).collect();
+    let error = BitrotError::new(ErrorCode::MuxFailed, "Mux step failed.").with_stderr(&stderr);
+    let value = serde_json::to_value(&error).unwrap();
+    assert_eq!(value["code"], "mux-failed");
+    assert_eq!(value["code"], ErrorCode::MuxFailed.as_str());
+    assert_eq!(value["retryable"], false);
+    let tail = value["stderrTail"].as_str().unwrap();
+    assert!(tail.starts_with("line 11\nline 12"));
+    assert!(tail.ends_with("line 30"));
+
+    let missing = BitrotError::io("Failed to read job", std::io::ErrorKind::NotFound.into());
+    assert_eq!(missing.code, ErrorCode::NotFound);
+    assert!(BitrotError::new(ErrorCode::SpawnFailed, "busy").retryable);
+    assert!(serde_json::to_value(BitrotError::canceled()).unwrap().get("stderrTail").is_none());
+  }
+}
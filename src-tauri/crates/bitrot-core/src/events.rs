@@ -0,0 +1,163 @@
+// Versioned job event schema shared by every job type (native modes, datamosh, raw ffmpeg).
+// Events go out on one channel keyed by job id and kind, so a UI, the CLI or an automation
+// client can follow any job the same way. Bump the version on breaking payload changes.
+use serde::{Deserialize, Serialize};
+
+use crate::error::BitrotError;
+use crate::progress::ProgressSnapshot;
+
+pub const JOB_EVENT_VERSION: u32 = 1;
+pub const JOB_EVENT_CHANNEL: &str = "job-event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogLevel {
+  Debug,
+  Info,
+  Warn,
+  Error
+}
+
+// Raw process output keeps its stream so callers can parse e.g. `-progress pipe:1` lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogStream {
+  Stdout,
+  Stderr
+}
+
+// Coarse steps of a native render, in order; single-pass jobs only report `Render`.
+// `SecondPass` only runs for target-size renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStage {
+  Render,
+  Concat,
+  SecondPass,
+  Mux
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JobEventPayload {
+  Started,
+  Progress(ProgressSnapshot),
+  #[serde(rename_all = "camelCase")]
+  Log {
+    level: LogLevel,
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stream: Option<LogStream>
+  },
+  Preview {
+    frame: u64,
+    path: String
+  },
+  Stage {
+    stage: JobStage
+  },
+  Finished,
+  Failed {
+    error: BitrotError
+  },
+  Canceled
+}
+
+impl JobEventPayload {
+  pub fn log(level: LogLevel, message: &str) -> Self {
+    JobEventPayload::Log {
+      level,
+      message: message.to_string(),
+      stream: None
+    }
+  }
+
+  // Terminal event for a job result; a `canceled` error becomes `Canceled`.
+  pub fn from_result(result: &Result<(), BitrotError>) -> Self {
+    match result {
+      Ok(()) => JobEventPayload::Finished,
+      Err(error) if error.is_canceled() => JobEventPayload::Canceled,
+      Err(error) => JobEventPayload::Failed {
+        error: error.clone()
+      }
+    }
+  }
+}
+
+// `kind` is the mode tag ("block-shift", "datamosh") or the program for raw ffmpeg jobs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobEvent {
+  pub version: u32,
+  pub job_id: String,
+  pub kind: String,
+  #[serde(flatten)]
+  pub payload: JobEventPayload
+}
+
+impl JobEvent {
+  pub fn new(job_id: &str, kind: &str, payload: JobEventPayload) -> Self {
+    Self {
+      version: JOB_EVENT_VERSION,
+      job_id: job_id.to_string(),
+      kind: kind.to_string(),
+      payload
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::error::ErrorCode;
+  use crate::progress::ProgressClock;
+
+  #[test]
+  fn events_serialize_flat_with_version_kind_and_type() {
+    let log = JobEvent::new("job-1", "vaporwave", JobEventPayload::log(LogLevel::Warn, "decode: oops"));
+    assert_eq!(
+      serde_json::to_value(&log).unwrap(),
+      serde_json::json!({
+        "version": JOB_EVENT_VERSION,
+        "jobId": "job-1",
+        "kind": "vaporwave",
+        "type": "log",
+        "level": "warn",
+        "message": "decode: oops"
+      })
+    );
+
+    let progress = JobEvent::new(
+      "job-1",
+      "vaporwave",
+      JobEventPayload::Progress(ProgressClock::start(30.0, Some(90)).snapshot(45))
+    );
+    let value = serde_json::to_value(&progress).unwrap();
+    assert_eq!(value["type"], "progress");
+    assert_eq!(value["frame"], 45);
+    assert_eq!(value["percent"], 50.0);
+
+    let round_trip: JobEvent = serde_json::from_value(value).unwrap();
+    assert!(matches!(round_trip.payload, JobEventPayload::Progress(snapshot) if snapshot.frame == 45));
+  }
+
+  #[test]
+  fn results_map_to_terminal_events() {
+    assert!(matches!(JobEventPayload::from_result(&Ok(())), JobEventPayload::Finished));
+    assert!(matches!(
+      JobEventPayload::from_result(&Err(BitrotError::canceled())),
+      JobEventPayload::Canceled
+    ));
+    let failed = JobEvent::new(
+      "job-1",
+      "vaporwave",
+      JobEventPayload::from_result(&Err(BitrotError::new(
+        ErrorCode::EncodeFailed,
+        "Encoder failed with exit code 1"
+      )))
+    );
+    let value = serde_json::to_value(&failed).unwrap();
+    assert_eq!(value["type"], "failed");
+    assert_eq!(value["error"]["code"], "encode-failed");
+  }
+}
@@ -0,0 +1,184 @@
+// FFmpeg binary resolution shared by every host (app, CLI, embedders).
+// Order: executable dir -> sidecar binaries dir -> system PATH.
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandSource {
+  Local,
+  Sidecar,
+  Path
+}
+
+fn executable_dir() -> Option<PathBuf> {
+  let exe = std::env::current_exe().ok()?;
+  exe.parent().map(Path::to_path_buf)
+}
+
+fn with_platform_suffix(program: &str) -> String {
+  if cfg!(windows) && !program.to_lowercase().ends_with(".exe") {
+    format!("{program}.exe")
+  } else {
+    program.to_string()
+  }
+}
+
+fn local_binary_dir(program: &str) -> Option<PathBuf> {
+  let dir = executable_dir()?;
+  let candidate = dir.join(with_platform_suffix(program));
+  if candidate.exists() {
+    Some(dir)
+  } else {
+    None
+  }
+}
+
+// Keep these in sync with scripts/setup-ffmpeg.mjs and scripts/make-portable.mjs.
+fn target_triple() -> Option<&'static str> {
+  if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+    Some("x86_64-pc-windows-msvc")
+  } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+    Some("x86_64-apple-darwin")
+  } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+    Some("aarch64-apple-darwin")
+  } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+    Some("x86_64-unknown-linux-gnu")
+  } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+    Some("aarch64-unknown-linux-gnu")
+  } else {
+    None
+  }
+}
+
+fn triple_sidecar_name(program: &str) -> Option<String> {
+  let triple = target_triple()?;
+  Some(with_platform_suffix(&format!("{program}-{triple}")))
+}
+
+// Resolve the dev-time `src-tauri/binaries` location when running via cargo/tauri dev.
+fn dev_binaries_dir() -> Option<PathBuf> {
+  let cwd = std::env::current_dir().ok()?;
+  if cwd.join("tauri.conf.json").exists() {
+    return Some(cwd.join("binaries"));
+  }
+  let src_tauri = cwd.join("src-tauri");
+  if src_tauri.join("tauri.conf.json").exists() {
+    return Some(src_tauri.join("binaries"));
+  }
+  None
+}
+
+fn check_binaries_dir(
+  dir: PathBuf,
+  base_name: &str,
+  triple_name: Option<&str>
+) -> Option<PathBuf> {
+  let base_path = dir.join(base_name);
+  if base_path.exists() {
+    return Some(base_path);
+  }
+  let triple_name = triple_name?;
+  let triple_path = dir.join(triple_name);
+  if triple_path.exists() {
+    return Some(triple_path);
+  }
+  None
+}
+
+fn sidecar_binary_path(program: &str) -> Option<PathBuf> {
+  let base_name = with_platform_suffix(program);
+  let triple_name = triple_sidecar_name(program);
+
+  if let Some(exe_dir) = executable_dir() {
+    let dir = exe_dir.join("binaries");
+    if let Some(path) = check_binaries_dir(dir, &base_name, triple_name.as_deref()) {
+      return Some(path);
+    }
+  }
+
+  if let Some(dev_dir) = dev_binaries_dir() {
+    if let Some(path) = check_binaries_dir(dev_dir, &base_name, triple_name.as_deref()) {
+      return Some(path);
+    }
+  }
+
+  None
+}
+
+fn path_delimiter() -> char {
+  if cfg!(windows) {
+    ';'
+  } else {
+    ':'
+  }
+}
+
+// Where a binary was found, independent of how it ends up being spawned.
+pub struct ResolvedProgram {
+  pub program: PathBuf,
+  pub working_dir: Option<PathBuf>,
+  pub source: CommandSource
+}
+
+impl ResolvedProgram {
+  // Local binaries run from their own dir with that dir prepended to PATH.
+  pub fn path_env(&self) -> Option<String> {
+    let dir = self.working_dir.as_ref()?;
+    let existing = std::env::var("PATH").unwrap_or_default();
+    Some(if existing.is_empty() {
+      dir.to_string_lossy().into_owned()
+    } else {
+      format!("{}{}{}", dir.to_string_lossy(), path_delimiter(), existing)
+    })
+  }
+
+  // Full path of the binary that runs: local binaries sit in their working dir, PATH ones
+  // are looked up the way the OS would. None when nothing on PATH matches.
+  pub fn absolute_program(&self) -> Option<PathBuf> {
+    let name = with_platform_suffix(&self.program.to_string_lossy());
+    match self.source {
+      CommandSource::Local => self.working_dir.as_ref().map(|dir| dir.join(name)),
+      CommandSource::Sidecar => Some(self.program.clone()),
+      CommandSource::Path => {
+        let paths = std::env::var_os("PATH")?;
+        std::env::split_paths(&paths)
+          .map(|dir| dir.join(&name))
+          .find(|candidate| candidate.is_file())
+      }
+    }
+  }
+
+  // Plain std command for hosts that spawn ffmpeg directly (CLI, blocking pipelines).
+  pub fn std_command(&self) -> std::process::Command {
+    let mut command = std::process::Command::new(&self.program);
+    if let (Some(dir), Some(path_value)) = (self.working_dir.as_ref(), self.path_env()) {
+      command.current_dir(dir).env("PATH", path_value);
+    }
+    command
+  }
+}
+
+// Path-level resolution; hosts wrap the result in whatever command type they spawn with.
+pub fn resolve_ffmpeg_program(program: &str) -> ResolvedProgram {
+  if let Some(dir) = local_binary_dir(program) {
+    return ResolvedProgram {
+      program: PathBuf::from(program),
+      working_dir: Some(dir),
+      source: CommandSource::Local
+    };
+  }
+  if let Some(path) = sidecar_binary_path(program) {
+    return ResolvedProgram {
+      program: path,
+      working_dir: None,
+      source: CommandSource::Sidecar
+    };
+  }
+  ResolvedProgram {
+    program: PathBuf::from(program),
+    working_dir: None,
+    source: CommandSource::Path
+  }
+}
@@ -0,0 +1,362 @@
+// Serializable job specs (input, mode + config, encoding, output) and the runner that
+// turns one into a finished render. Shared by the CLI and the app's render queue.
+use std::{path::Path, sync::atomic::AtomicBool};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::automation::{Automated, Automation};
+use crate::checkpoint::{checkpoint_dir, Checkpoint};
+use crate::effect::NativeEffect;
+use crate::error::{BitrotError, ErrorCode};
+use crate::events::{JobStage, LogLevel};
+use crate::mask::{Mask, Masked};
+use crate::modes::block_shift::BlockShiftConfig;
+use crate::modes::byte_range::ModuloMappingConfig;
+use crate::modes::chain::ChainConfig;
+use crate::modes::datamosh::{process_datamosh, SceneWindow};
+use crate::modes::kaleidoscope::KaleidoscopeConfig;
+use crate::modes::pixelsort::algo::PixelsortConfig;
+use crate::modes::vaporwave::VaporwaveConfig;
+use crate::pipeline::{paths_match, run_native_job, FfmpegLauncher, NativeEncoding, NativeJobRequest};
+use crate::probe::probe_video;
+use crate::progress::JobReporter;
+use crate::provenance::{record_render, RenderProvenance};
+use crate::sequence::{input_options, is_sequence_pattern, OutputSequence};
+use crate::still::{is_still_image, run_still_job, StillOptions, StillRequest, STILL_FPS};
+use crate::timeline::{validate_windows, ActiveWindow, Windowed};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatamoshSpec {
+  pub fps: f64,
+  pub windows: Vec<SceneWindow>,
+  pub intensity: f64,
+  pub seed: u64,
+  pub extradata_hex: Option<String>
+}
+
+// Mode ids match the app's event tags; `config` is the same JSON the UI sends.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "config", rename_all = "kebab-case")]
+pub enum JobMode {
+  Pixelsort(PixelsortConfig),
+  BlockShift(BlockShiftConfig),
+  Vaporwave(VaporwaveConfig),
+  Kaleidoscope(KaleidoscopeConfig),
+  ModuloMapping(ModuloMappingConfig),
+  Chain(ChainConfig),
+  Datamosh(DatamoshSpec)
+}
+
+impl JobMode {
+  // Event tag for the mode, e.g. "block-shift" -> "block-shift-progress".
+  pub fn tag(&self) -> &'static str {
+    match self {
+      JobMode::Pixelsort(_) => PixelsortConfig::TAG,
+      JobMode::BlockShift(_) => BlockShiftConfig::TAG,
+      JobMode::Vaporwave(_) => VaporwaveConfig::TAG,
+      JobMode::Kaleidoscope(_) => KaleidoscopeConfig::TAG,
+      JobMode::ModuloMapping(_) => ModuloMappingConfig::TAG,
+      JobMode::Chain(_) => ChainConfig::TAG,
+      JobMode::Datamosh(_) => "datamosh"
+    }
+  }
+}
+
+// Video geometry is probed with ffprobe when left out of the spec.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobSpec {
+  pub input: String,
+  pub output: String,
+  #[serde(flatten)]
+  pub mode: JobMode,
+  pub encoding: Option<NativeEncoding>,
+  pub automation: Option<Automation>,
+  pub mask: Option<Mask>,
+  pub active_windows: Option<Vec<ActiveWindow>>,
+  pub width: Option<u32>,
+  pub height: Option<u32>,
+  pub fps: Option<f64>,
+  pub duration_seconds: Option<f64>,
+  pub trim_start_seconds: Option<f64>,
+  pub trim_end_seconds: Option<f64>,
+  // Frame index, quality and depth for still image inputs.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub still: Option<StillOptions>
+}
+
+impl JobSpec {
+  // Cheap checks worth running before a spec sits in a queue for hours.
+  pub fn validate(&self) -> Result<(), BitrotError> {
+    if self.input.trim().is_empty() {
+      return Err(BitrotError::invalid("Job input path is empty."));
+    }
+    if self.output.trim().is_empty() {
+      return Err(BitrotError::invalid("Job output path is empty."));
+    }
+    if paths_match(&self.input, &self.output) {
+      return Err(BitrotError::new(
+        ErrorCode::OutputConflict,
+        "Output path matches the input file. Choose a different output name."
+      ));
+    }
+    if is_still_image(&self.input) {
+      return self.validate_still();
+    }
+    if self.encoding.is_none() && !matches!(self.mode, JobMode::Datamosh(_)) {
+      return Err(BitrotError::invalid(format!(
+        "\"encoding\" is required for {}.",
+        self.mode.tag()
+      )));
+    }
+    if is_sequence_pattern(&self.input) && self.fps.is_none() {
+      return Err(BitrotError::invalid(
+        "Image sequence inputs need \"fps\"; frames carry no timing of their own."
+      ));
+    }
+    if let Some(encoding) = &self.encoding {
+      if OutputSequence::for_output(&self.output, encoding)?.is_none() {
+        encoding.validate()?;
+      }
+    }
+    if let Some(mask) = &self.mask {
+      mask.validate()?;
+    }
+    if let Some(windows) = &self.active_windows {
+      if matches!(self.mode, JobMode::Datamosh(_)) {
+        return Err(BitrotError::invalid(
+          "\"activeWindows\" only applies to native modes; datamosh takes windows in its config."
+        ));
+      }
+      validate_windows(windows)?;
+    }
+    Ok(())
+  }
+
+  fn validate_still(&self) -> Result<(), BitrotError> {
+    if matches!(self.mode, JobMode::Datamosh(_)) {
+      return Err(BitrotError::invalid("Datamosh needs a video input."));
+    }
+    if self.mask.as_ref().is_some_and(|mask| mask.matte.is_some()) {
+      return Err(BitrotError::invalid("A matte video cannot gate a still image."));
+    }
+    if let Some(mask) = &self.mask {
+      mask.validate()?;
+    }
+    if let Some(windows) = &self.active_windows {
+      validate_windows(windows)?;
+    }
+    self.still.clone().unwrap_or_default().validate(&self.output)
+  }
+
+  // Spec for a render started from the app's per-mode commands, kept in its checkpoint.
+  pub fn from_request<E: NativeEffect + Serialize>(
+    request: &NativeJobRequest,
+    effect: &E,
+    automation: Option<Automation>,
+    mask: Option<Mask>,
+    active_windows: Option<Vec<ActiveWindow>>
+  ) -> Result<Self, BitrotError> {
+    let config = serde_json::to_value(effect).map_err(|error| {
+      BitrotError::internal(format!("Failed to serialize {} config: {error}", E::LABEL))
+    })?;
+    let mode = serde_json::from_value(serde_json::json!({ "mode": E::TAG, "config": config }))
+      .map_err(|error| BitrotError::invalid(format!("Unsupported job mode {}: {error}", E::TAG)))?;
+    Ok(Self {
+      input: request.input_path.clone(),
+      output: request.output_path.clone(),
+      mode,
+      encoding: Some(request.encoding.clone()),
+      automation,
+      mask,
+      active_windows,
+      width: Some(request.width),
+      height: Some(request.height),
+      fps: Some(request.fps),
+      duration_seconds: request.duration_seconds,
+      trim_start_seconds: request.trim_start_seconds,
+      trim_end_seconds: request.trim_end_seconds,
+      still: None
+    })
+  }
+
+  pub fn to_request(
+    &self,
+    job_id: &str,
+    ffprobe: FfmpegLauncher,
+    encoding: NativeEncoding
+  ) -> Result<NativeJobRequest, BitrotError> {
+    let probed = match (self.width, self.height, self.fps) {
+      (Some(_), Some(_), Some(_)) => None,
+      _ => {
+        let input_options = input_options(&self.input, self.fps.unwrap_or(25.0))?;
+        Some(probe_video(ffprobe, &self.input, &input_options)?)
+      }
+    };
+    let pick = |explicit: Option<u32>, probed: Option<u32>| explicit.or(probed).unwrap_or(0);
+    Ok(NativeJobRequest {
+      job_id: job_id.into(),
+      input_path: self.input.clone(),
+      output_path: self.output.clone(),
+      width: pick(self.width, probed.as_ref().map(|video| video.width)),
+      height: pick(self.height, probed.as_ref().map(|video| video.height)),
+      fps: self
+        .fps
+        .or(probed.as_ref().map(|video| video.fps))
+        .unwrap_or(0.0),
+      duration_seconds: self
+        .duration_seconds
+        .or(probed.as_ref().and_then(|video| video.duration_seconds)),
+      trim_start_seconds: self.trim_start_seconds,
+      trim_end_seconds: self.trim_end_seconds,
+      preview_enabled: false,
+      encoding,
+      matte: self.mask.as_ref().and_then(|mask| mask.matte.clone())
+    })
+  }
+}
+
+// The spec's automation, mask and active windows around the mode's effect.
+fn wrap_effect<E: NativeEffect + Clone + Serialize + DeserializeOwned>(
+  spec: &JobSpec,
+  effect: &E,
+  fps: f64
+) -> Result<Windowed<Masked<Automated<E>>>, BitrotError> {
+  let automation = spec.automation.clone().unwrap_or_default();
+  Windowed::new(
+    Masked::new(Automated::new(effect.clone(), automation, fps)?, spec.mask.clone())?,
+    spec.active_windows.clone(),
+    fps
+  )
+}
+
+// Launchers and cancellation for a spec run; `ffprobe` is only used when geometry is missing.
+pub struct JobRunner<'a> {
+  pub ffmpeg: FfmpegLauncher<'a>,
+  pub ffprobe: FfmpegLauncher<'a>,
+  pub cancel_flag: &'a AtomicBool,
+  pub reporter: &'a dyn JobReporter
+}
+
+impl JobRunner<'_> {
+  // Runs a spec to completion; blocking, like `run_native_job`.
+  pub fn run(&self, job_id: &str, spec: &JobSpec) -> Result<(), BitrotError> {
+    self.dispatch(job_id, spec, None)
+  }
+
+  // Continues an interrupted render from its checkpoint directory, using the spec it
+  // was started with.
+  pub fn resume(&self, job_id: &str, checkpoint_dir: &Path) -> Result<(), BitrotError> {
+    let checkpoint = Checkpoint::open(checkpoint_dir)?;
+    let spec = checkpoint.journal.spec.clone();
+    self.dispatch(job_id, &spec, Some(checkpoint))
+  }
+
+  // Renders a finished output's `.bitrot.json` sidecar again with the recorded spec. An
+  // input that changed since is reported but does not stop the render.
+  pub fn replay(&self, job_id: &str, sidecar_path: &Path) -> Result<(), BitrotError> {
+    let provenance = RenderProvenance::load(sidecar_path)?;
+    if let Some(mismatch) = provenance.input_mismatch() {
+      self.reporter.log_at(
+        LogLevel::Warn,
+        &format!("{mismatch} The replay may not match the original render.")
+      );
+    }
+    self.run(job_id, &provenance.spec)
+  }
+
+  fn dispatch(
+    &self,
+    job_id: &str,
+    spec: &JobSpec,
+    checkpoint: Option<Checkpoint>
+  ) -> Result<(), BitrotError> {
+    match &spec.mode {
+      JobMode::Pixelsort(config) => self.run_effect(job_id, spec, config, checkpoint),
+      JobMode::BlockShift(config) => self.run_effect(job_id, spec, config, checkpoint),
+      JobMode::Vaporwave(config) => self.run_effect(job_id, spec, config, checkpoint),
+      JobMode::Kaleidoscope(config) => self.run_effect(job_id, spec, config, checkpoint),
+      JobMode::ModuloMapping(config) => self.run_effect(job_id, spec, config, checkpoint),
+      JobMode::Chain(config) => {
+        if config.steps.is_empty() {
+          return Err(BitrotError::invalid("Effect chain has no steps."));
+        }
+        self.run_effect(job_id, spec, config, checkpoint)
+      }
+      JobMode::Datamosh(datamosh) => self.run_datamosh(spec, datamosh)
+    }
+  }
+
+  fn run_effect<E>(
+    &self,
+    job_id: &str,
+    spec: &JobSpec,
+    effect: &E,
+    checkpoint: Option<Checkpoint>
+  ) -> Result<(), BitrotError>
+  where
+    E: NativeEffect + Clone + Serialize + DeserializeOwned
+  {
+    if is_still_image(&spec.input) {
+      return self.run_still(spec, effect);
+    }
+    let encoding = spec
+      .encoding
+      .clone()
+      .ok_or_else(|| {
+        BitrotError::invalid(format!("\"encoding\" is required for {}.", E::LABEL.to_lowercase()))
+      })?;
+    let request = spec.to_request(job_id, self.ffprobe, encoding)?;
+    let effect = wrap_effect(spec, effect, request.fps)?;
+    let mut checkpoint = match checkpoint {
+      Some(checkpoint) => checkpoint,
+      None => Checkpoint::start(checkpoint_dir(&spec.output, E::TAG), spec.clone(), request.fps)?
+    };
+    run_native_job(
+      self.ffmpeg,
+      &request,
+      &effect,
+      &mut checkpoint,
+      self.cancel_flag,
+      self.reporter
+    )
+  }
+
+  // Still images skip checkpoints and segments: one decode, one frame, one encode.
+  fn run_still<E>(&self, spec: &JobSpec, effect: &E) -> Result<(), BitrotError>
+  where
+    E: NativeEffect + Clone + Serialize + DeserializeOwned
+  {
+    spec.validate_still()?;
+    let options = spec.still.clone().unwrap_or_default();
+    let effect = wrap_effect(spec, effect, spec.fps.unwrap_or(STILL_FPS))?;
+    let request = StillRequest {
+      input_path: &spec.input,
+      output_path: &spec.output,
+      size: spec.width.zip(spec.height),
+      options: &options
+    };
+    run_still_job(self.ffmpeg, self.ffprobe, &request, &effect, self.reporter)?;
+    record_render(self.ffmpeg, spec, self.reporter);
+    Ok(())
+  }
+
+  // Datamosh works on a prepared MPEG-4 Part 2 bitstream and runs in one pass.
+  fn run_datamosh(&self, spec: &JobSpec, datamosh: &DatamoshSpec) -> Result<(), BitrotError> {
+    self.reporter.log("Datamosh started.");
+    self.reporter.stage(JobStage::Render);
+    process_datamosh(
+      &spec.input,
+      &spec.output,
+      datamosh.fps,
+      &datamosh.windows,
+      datamosh.intensity,
+      datamosh.seed,
+      datamosh.extradata_hex.as_deref()
+    )?;
+    record_render(self.ffmpeg, spec, self.reporter);
+    self.reporter.log("Datamosh completed.");
+    Ok(())
+  }
+}
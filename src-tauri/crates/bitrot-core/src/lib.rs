@@ -0,0 +1,26 @@
+// Tauri-independent effect core: frame processors, the native decode/encode host,
+// ffmpeg arg builders and the datamosh bitstream pass.
+
+pub mod animated;
+pub mod automation;
+pub mod checkpoint;
+pub mod effect;
+pub mod error;
+pub mod events;
+pub mod ffmpeg;
+pub mod job;
+pub mod mask;
+pub mod modes;
+pub mod parallel;
+pub mod pipeline;
+pub mod preview;
+pub mod probe;
+pub mod progress;
+pub mod provenance;
+pub mod queue;
+pub mod sample;
+pub mod sequence;
+pub mod still;
+pub mod target_size;
+pub mod timeline;
+pub mod toolchain;
@@ -0,0 +1,503 @@
+// Masks restricting where an effect applies: geometric shapes in normalized coordinates and
+// luma/chroma keys evaluated on each source frame. The effect output is blended back over
+// the original frame through the mask, so every native mode gets masking for free.
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::error::BitrotError;
+use crate::parallel::for_each_row_band;
+use crate::sample::Sample;
+
+// Shapes use 0..1 coordinates with the origin at the top-left of the frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum MaskShape {
+  Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64
+  },
+  Ellipse {
+    center_x: f64,
+    center_y: f64,
+    radius_x: f64,
+    radius_y: f64
+  },
+  Polygon {
+    points: Vec<[f64; 2]>
+  },
+  // Selects pixels whose Rec. 709 luma (0..1) lies in `low..=high`.
+  LumaKey {
+    low: f64,
+    high: f64
+  },
+  // Selects pixels whose chroma is within `tolerance` of `color` (distance in the CbCr plane).
+  ChromaKey {
+    color: [u8; 3],
+    tolerance: f64
+  }
+}
+
+// How a layer combines with the layers above it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MaskOperation {
+  #[default]
+  Add,
+  Subtract,
+  Intersect
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaskLayer {
+  #[serde(flatten)]
+  pub shape: MaskShape,
+  #[serde(default)]
+  pub operation: MaskOperation,
+  // Soft edge width: a fraction of the frame's shorter side for shapes, luma or chroma
+  // distance for keys. Zero is a hard edge.
+  #[serde(default)]
+  pub feather: f64,
+  #[serde(default)]
+  pub invert: bool
+}
+
+// What a matte that ends before the input leaves behind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatteEnd {
+  // Keep gating with the matte's last frame.
+  #[default]
+  Hold,
+  // Show the source frame unchanged.
+  Source,
+  // Show the full effect.
+  Effect
+}
+
+// Black/white matte video decoded alongside the input at the same size, fps and trim;
+// white shows the effect, black the source. The host decodes and applies it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Matte {
+  pub path: String,
+  #[serde(default)]
+  pub invert: bool,
+  #[serde(default)]
+  pub after_end: MatteEnd
+}
+
+// Layers apply top to bottom; a mask without layers leaves the effect unmasked. A matte
+// multiplies with the layers; `invert` only flips the layers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Mask {
+  #[serde(default)]
+  pub layers: Vec<MaskLayer>,
+  #[serde(default)]
+  pub invert: bool,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub matte: Option<Matte>
+}
+
+fn finite(values: &[f64]) -> bool {
+  values.iter().all(|value| value.is_finite())
+}
+
+impl MaskLayer {
+  fn validate(&self, index: usize) -> Result<(), BitrotError> {
+    let invalid = |reason: &str| BitrotError::invalid(format!("Mask layer {}: {reason}", index + 1));
+    if !self.feather.is_finite() || self.feather < 0.0 {
+      return Err(invalid("feather must be zero or more."));
+    }
+    match &self.shape {
+      MaskShape::Rect { x, y, width, height } => {
+        if !finite(&[*x, *y, *width, *height]) || *width < 0.0 || *height < 0.0 {
+          return Err(invalid("rectangle needs a finite position and a non-negative size."));
+        }
+      }
+      MaskShape::Ellipse { center_x, center_y, radius_x, radius_y } => {
+        if !finite(&[*center_x, *center_y, *radius_x, *radius_y])
+          || *radius_x < 0.0
+          || *radius_y < 0.0
+        {
+          return Err(invalid("ellipse needs a finite center and non-negative radii."));
+        }
+      }
+      MaskShape::Polygon { points } => {
+        if points.len() < 3 || !points.iter().all(|point| finite(point)) {
+          return Err(invalid("polygon needs at least 3 finite points."));
+        }
+      }
+      MaskShape::LumaKey { low, high } => {
+        if !finite(&[*low, *high]) || low > high {
+          return Err(invalid("luma key needs low <= high."));
+        }
+      }
+      MaskShape::ChromaKey { tolerance, .. } => {
+        if !tolerance.is_finite() || *tolerance < 0.0 {
+          return Err(invalid("chroma key tolerance must be zero or more."));
+        }
+      }
+    }
+    Ok(())
+  }
+
+  // Coverage for shapes that don't depend on frame content; keys return None.
+  fn static_coverage(&self, width: usize, height: usize) -> Option<Vec<u8>> {
+    let scale_x = width as f64;
+    let scale_y = height as f64;
+    let feather = self.feather * scale_x.min(scale_y);
+    let signed_distance: Box<dyn Fn(f64, f64) -> f64> = match &self.shape {
+      MaskShape::Rect { x, y, width, height } => {
+        let half_x = width * scale_x / 2.0;
+        let half_y = height * scale_y / 2.0;
+        let center_x = x * scale_x + half_x;
+        let center_y = y * scale_y + half_y;
+        Box::new(move |px, py| {
+          let dx = (px - center_x).abs() - half_x;
+          let dy = (py - center_y).abs() - half_y;
+          dx.max(0.0).hypot(dy.max(0.0)) + dx.max(dy).min(0.0)
+        })
+      }
+      MaskShape::Ellipse { center_x, center_y, radius_x, radius_y } => {
+        let (cx, cy) = (center_x * scale_x, center_y * scale_y);
+        let (rx, ry) = (radius_x * scale_x, radius_y * scale_y);
+        if rx <= 0.0 || ry <= 0.0 {
+          return Some(vec![0; width * height]);
+        }
+        // Scaled radial distance; exact on circles, close enough on ellipses for feathering.
+        Box::new(move |px, py| (((px - cx) / rx).hypot((py - cy) / ry) - 1.0) * rx.min(ry))
+      }
+      MaskShape::Polygon { points } => {
+        let points: Vec<(f64, f64)> = points
+          .iter()
+          .map(|[x, y]| (x * scale_x, y * scale_y))
+          .collect();
+        Box::new(move |px, py| polygon_signed_distance(&points, px, py))
+      }
+      MaskShape::LumaKey { .. } | MaskShape::ChromaKey { .. } => return None
+    };
+
+    let mut coverage = vec![0u8; width * height];
+    for (index, value) in coverage.iter_mut().enumerate() {
+      let px = (index % width) as f64 + 0.5;
+      let py = (index / width) as f64 + 0.5;
+      let distance = signed_distance(px, py);
+      let amount = if feather > 0.0 {
+        (0.5 - distance / feather).clamp(0.0, 1.0)
+      } else if distance <= 0.0 {
+        1.0
+      } else {
+        0.0
+      };
+      *value = (amount * 255.0).round() as u8;
+    }
+    Some(coverage)
+  }
+
+  fn key_coverage<S: Sample>(&self, pixel: &[S]) -> f64 {
+    let ramp = |distance: f64| {
+      if distance <= 0.0 {
+        1.0
+      } else if self.feather > 0.0 {
+        (1.0 - distance / self.feather).max(0.0)
+      } else {
+        0.0
+      }
+    };
+    match &self.shape {
+      MaskShape::LumaKey { low, high } => {
+        let luma = luma(pixel[0].level(), pixel[1].level(), pixel[2].level());
+        ramp((low - luma).max(luma - high))
+      }
+      MaskShape::ChromaKey { color, tolerance } => {
+        let (cb, cr) = chroma(pixel[0].level(), pixel[1].level(), pixel[2].level());
+        let (key_cb, key_cr) = chroma(color[0].into(), color[1].into(), color[2].into());
+        ramp((cb - key_cb).hypot(cr - key_cr) - tolerance)
+      }
+      _ => 0.0
+    }
+  }
+}
+
+// Channels are 0..255 levels, fractional for 16-bit frames.
+fn luma(r: f32, g: f32, b: f32) -> f64 {
+  (0.2126 * f64::from(r) + 0.7152 * f64::from(g) + 0.0722 * f64::from(b)) / 255.0
+}
+
+// Rec. 709 Cb/Cr in -0.5..0.5.
+fn chroma(r: f32, g: f32, b: f32) -> (f64, f64) {
+  let y = luma(r, g, b);
+  ((f64::from(b) / 255.0 - y) / 1.8556, (f64::from(r) / 255.0 - y) / 1.5748)
+}
+
+// Negative inside (even-odd rule), positive outside.
+fn polygon_signed_distance(points: &[(f64, f64)], px: f64, py: f64) -> f64 {
+  let mut inside = false;
+  let mut nearest = f64::INFINITY;
+  let mut previous = points[points.len() - 1];
+  for &current in points {
+    let ((ax, ay), (bx, by)) = (previous, current);
+    if (ay > py) != (by > py) && px < (bx - ax) * (py - ay) / (by - ay) + ax {
+      inside = !inside;
+    }
+    let (ex, ey) = (bx - ax, by - ay);
+    let length = ex * ex + ey * ey;
+    let t = if length > 0.0 {
+      (((px - ax) * ex + (py - ay) * ey) / length).clamp(0.0, 1.0)
+    } else {
+      0.0
+    };
+    nearest = nearest.min((px - ax - ex * t).hypot(py - ay - ey * t));
+    previous = current;
+  }
+  if inside {
+    -nearest
+  } else {
+    nearest
+  }
+}
+
+impl Mask {
+  pub fn validate(&self) -> Result<(), BitrotError> {
+    for (index, layer) in self.layers.iter().enumerate() {
+      layer.validate(index)?;
+    }
+    if self.matte.as_ref().is_some_and(|matte| matte.path.trim().is_empty()) {
+      return Err(BitrotError::invalid("Matte path is empty."));
+    }
+    Ok(())
+  }
+}
+
+// Mixes one RGBA pixel: weight 0 keeps `source`, 255 takes `effect`.
+pub(crate) fn blend<S: Sample>(source: &[S], effect: &[S], weight: u32, out: &mut [S]) {
+  for channel in 0..4 {
+    let blended = source[channel].into() * (255 - weight) + effect[channel].into() * weight;
+    out[channel] = S::from_u32((blended + 127) / 255);
+  }
+}
+
+// Gates a processed frame by one matte frame (a luma byte per pixel) into `output`.
+pub fn apply_matte<S: Sample>(
+  source: &[S],
+  processed: &[S],
+  matte: &[u8],
+  invert: bool,
+  width: usize,
+  output: &mut Vec<S>
+) {
+  output.resize(processed.len(), S::default());
+  let row_len = width * 4;
+  for_each_row_band(output, row_len, 1, |first_row, band| {
+    let start = first_row * row_len;
+    for (offset, out) in band.chunks_exact_mut(4).enumerate() {
+      let byte = start + offset * 4;
+      let value = u32::from(matte[byte / 4]);
+      let weight = if invert { 255 - value } else { value };
+      blend(&source[byte..byte + 4], &processed[byte..byte + 4], weight, out);
+    }
+  });
+}
+
+// Wraps an effect so its output only shows through the mask; unmasked jobs pass straight through.
+pub struct Masked<E> {
+  base: E,
+  mask: Option<Mask>
+}
+
+impl<E> Masked<E> {
+  pub fn new(base: E, mask: Option<Mask>) -> Result<Self, BitrotError> {
+    let mask = mask.filter(|mask| !mask.layers.is_empty());
+    if let Some(mask) = &mask {
+      mask.validate()?;
+    }
+    Ok(Self { base, mask })
+  }
+}
+
+pub struct MaskedWorkspace<W, S> {
+  inner: W,
+  width: usize,
+  // Precomputed coverage per layer for shapes; None for keys.
+  static_layers: Vec<Option<Vec<u8>>>,
+  output: Vec<S>
+}
+
+impl<E: NativeEffect> NativeEffect for Masked<E> {
+  const LABEL: &'static str = E::LABEL;
+  const TAG: &'static str = E::TAG;
+
+  type Workspace<S: Sample> = MaskedWorkspace<E::Workspace<S>, S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    let static_layers = self
+      .mask
+      .iter()
+      .flat_map(|mask| &mask.layers)
+      .map(|layer| layer.static_coverage(width, height))
+      .collect();
+    MaskedWorkspace {
+      inner: self.base.workspace(width, height),
+      width,
+      static_layers,
+      output: Vec::new()
+    }
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    let Some(mask) = &self.mask else {
+      return self.base.process_frame(frame, &mut workspace.inner, frame_index);
+    };
+    let processed = self.base.process_frame(frame, &mut workspace.inner, frame_index);
+    workspace.output.resize(processed.len(), S::default());
+    let static_layers = &workspace.static_layers;
+    let row_len = workspace.width * 4;
+    for_each_row_band(&mut workspace.output, row_len, 1, |first_row, band| {
+      let start = first_row * row_len;
+      for (offset, out) in band.chunks_exact_mut(4).enumerate() {
+        let byte = start + offset * 4;
+        let source = &frame[byte..byte + 4];
+        let mut coverage = 0.0f64;
+        for (layer, precomputed) in mask.layers.iter().zip(static_layers) {
+          let mut amount = match precomputed {
+            Some(values) => f64::from(values[byte / 4]) / 255.0,
+            None => layer.key_coverage(source)
+          };
+          if layer.invert {
+            amount = 1.0 - amount;
+          }
+          coverage = match layer.operation {
+            MaskOperation::Add => coverage.max(amount),
+            MaskOperation::Subtract => coverage.min(1.0 - amount),
+            MaskOperation::Intersect => coverage.min(amount)
+          };
+        }
+        if mask.invert {
+          coverage = 1.0 - coverage;
+        }
+        let weight = (coverage * 255.0).round() as u32;
+        blend(source, &processed[byte..byte + 4], weight, out);
+      }
+    });
+    &workspace.output
+  }
+
+  fn warmup_frames(&self) -> u64 {
+    self.base.warmup_frames()
+  }
+
+  fn take_warning(&self) -> Option<String> {
+    self.base.take_warning()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Fill;
+
+  impl NativeEffect for Fill {
+    const LABEL: &'static str = "Fill";
+    const TAG: &'static str = "fill";
+    type Workspace<S: Sample> = Vec<S>;
+
+    fn workspace<S: Sample>(&self, width: usize, height: usize) -> Vec<S> {
+      vec![S::default(); width * height * 4]
+    }
+
+    fn process_frame<'a, S: Sample>(&self, _frame: &[S], workspace: &'a mut Vec<S>, _index: u64) -> &'a [S] {
+      workspace.fill(S::from_u8(255));
+      workspace
+    }
+  }
+
+  fn layer(shape: MaskShape, operation: MaskOperation, feather: f64) -> MaskLayer {
+    MaskLayer {
+      shape,
+      operation,
+      feather,
+      invert: false
+    }
+  }
+
+  fn mask(layers: Vec<MaskLayer>, invert: bool) -> Mask {
+    Mask {
+      layers,
+      invert,
+      matte: None
+    }
+  }
+
+  fn render(mask: Mask, frame: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let masked = Masked::new(Fill, Some(mask)).unwrap();
+    let mut workspace = masked.workspace(width, height);
+    masked.process_frame(frame, &mut workspace, 0).to_vec()
+  }
+
+  #[test]
+  fn shapes_and_keys_limit_where_the_effect_shows() {
+    let (width, height) = (8, 4);
+    let black = vec![0u8; width * height * 4];
+    let rect = MaskShape::Rect { x: 0.0, y: 0.0, width: 0.5, height: 1.0 };
+    let left_half = render(
+      mask(vec![layer(rect.clone(), MaskOperation::Add, 0.0)], false),
+      &black,
+      width,
+      height
+    );
+    assert_eq!(left_half[0], 255);
+    assert_eq!(left_half[(width - 1) * 4], 0);
+
+    // Cutting the top row out of the rectangle, then inverting the whole mask.
+    let top_row = MaskShape::Rect { x: 0.0, y: 0.0, width: 1.0, height: 0.25 };
+    let cut = render(
+      mask(
+        vec![
+          layer(rect.clone(), MaskOperation::Add, 0.0),
+          layer(top_row, MaskOperation::Subtract, 0.0)
+        ],
+        true
+      ),
+      &black,
+      width,
+      height
+    );
+    assert_eq!(cut[0], 255);
+    assert_eq!(cut[width * 4], 0);
+
+    let feathered = render(
+      mask(vec![layer(rect, MaskOperation::Add, 0.5)], false),
+      &black,
+      width,
+      height
+    );
+    assert!(feathered[3 * 4] > 0 && feathered[3 * 4] < 255);
+
+    // Luma key: only the bright pixel takes the effect.
+    let mut frame = vec![10u8; width * height * 4];
+    frame[4..8].copy_from_slice(&[240, 240, 240, 255]);
+    let key = MaskShape::LumaKey { low: 0.8, high: 1.0 };
+    let keyed = render(
+      mask(vec![layer(key, MaskOperation::Add, 0.0)], false),
+      &frame,
+      width,
+      height
+    );
+    assert_eq!(keyed[0], 10);
+    assert_eq!(keyed[4], 255);
+
+    let bad = MaskShape::Polygon { points: vec![[0.0, 0.0], [1.0, 1.0]] };
+    let invalid = mask(vec![layer(bad, MaskOperation::Add, 0.0)], false);
+    assert!(Masked::new(Fill, Some(invalid)).is_err());
+  }
+}
@@ -0,0 +1,157 @@
+// Native block shift pipeline for macroblock-style displacement.
+// This keeps the codec intact while rearranging pixels in an 8x8/16x16 grid.
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::parallel::for_each_row_band;
+use crate::sample::{blend_channel, Sample};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockShiftConfig {
+  pub block_size: u32,
+  pub max_offset: u32,
+  pub offset_step: u32,
+  pub intensity: f32,
+  pub seed: u32
+}
+
+// Reusable buffers for per-frame processing to avoid extra allocations.
+pub struct BlockShiftWorkspace<S> {
+  width: usize,
+  height: usize,
+  output: Vec<S>
+}
+
+impl<S: Sample> BlockShiftWorkspace<S> {
+  fn new(width: usize, height: usize) -> Self {
+    let byte_len = width.saturating_mul(height).saturating_mul(4);
+    Self {
+      width,
+      height,
+      output: vec![S::default(); byte_len]
+    }
+  }
+
+  fn ensure_size(&mut self, byte_len: usize) {
+    if self.output.len() != byte_len {
+      self.output.resize(byte_len, S::default());
+    }
+  }
+}
+
+fn quantize_offset(value: i32, step: i32) -> i32 {
+  if step <= 1 {
+    return value;
+  }
+  (value / step) * step
+}
+
+fn block_offset(
+  seed: u32,
+  frame_index: u64,
+  block_x: usize,
+  block_y: usize,
+  max_offset: i32,
+  step: i32
+) -> (i32, i32) {
+  if max_offset <= 0 {
+    return (0, 0);
+  }
+  let mut rng = seed
+    ^ (frame_index as u32).wrapping_mul(1664525)
+    ^ (block_x as u32).wrapping_mul(1013904223)
+    ^ (block_y as u32).wrapping_mul(69069);
+  rng = rng.wrapping_mul(1664525).wrapping_add(1013904223);
+  let range = (max_offset * 2 + 1) as u32;
+  let dx = (rng.rotate_left(5) % range) as i32 - max_offset;
+  rng = rng.wrapping_mul(1664525).wrapping_add(1013904223);
+  let dy = (rng.rotate_left(9) % range) as i32 - max_offset;
+  (quantize_offset(dx, step), quantize_offset(dy, step))
+}
+
+fn process_block_shift_frame<'a, S: Sample>(
+  frame: &[S],
+  workspace: &'a mut BlockShiftWorkspace<S>,
+  config: &BlockShiftConfig,
+  frame_index: u64
+) -> &'a [S] {
+  let byte_len = frame.len();
+  workspace.ensure_size(byte_len);
+  if byte_len == 0 {
+    return &workspace.output;
+  }
+  workspace.output.copy_from_slice(frame);
+
+  let width = workspace.width;
+  let height = workspace.height;
+  if width == 0 || height == 0 {
+    return &workspace.output;
+  }
+  let pixel_count = width.saturating_mul(height);
+  if pixel_count < 2 {
+    return &workspace.output;
+  }
+
+  let block_size = config.block_size.max(2) as usize;
+  let max_offset = config.max_offset as i32;
+  let step = config.offset_step.max(1) as i32;
+  let mix = (config.intensity / 100.0).clamp(0.0, 1.0);
+  if mix <= 0.0 || max_offset <= 0 {
+    return &workspace.output;
+  }
+  let is_full_strength = mix >= 0.999;
+
+  // Bands start on block rows so every block is shifted by exactly one thread.
+  for_each_row_band(&mut workspace.output, width * 4, block_size, |first_row, band| {
+    let band_end = first_row + band.len() / (width * 4);
+    for by in (first_row..band_end).step_by(block_size) {
+      let end_y = (by + block_size).min(band_end);
+      for bx in (0..width).step_by(block_size) {
+        let end_x = (bx + block_size).min(width);
+        let (dx, dy) = block_offset(config.seed, frame_index, bx, by, max_offset, step);
+        for y in by..end_y {
+          for x in bx..end_x {
+            let src_x = (x as i32 + dx).clamp(0, (width - 1) as i32) as usize;
+            let src_y = (y as i32 + dy).clamp(0, (height - 1) as i32) as usize;
+            let src_idx = (src_y * width + src_x) * 4;
+            let frame_idx = (y * width + x) * 4;
+            let dst_idx = ((y - first_row) * width + x) * 4;
+            if is_full_strength {
+              band[dst_idx..dst_idx + 3].copy_from_slice(&frame[src_idx..src_idx + 3]);
+            } else {
+              for channel in 0..3 {
+                let base = frame[frame_idx + channel];
+                let mapped = frame[src_idx + channel];
+                band[dst_idx + channel] = blend_channel(base, mapped, mix);
+              }
+            }
+            band[dst_idx + 3] = frame[frame_idx + 3];
+          }
+        }
+      }
+    }
+  });
+
+  &workspace.output
+}
+
+impl NativeEffect for BlockShiftConfig {
+  const LABEL: &'static str = "Block shift";
+  const TAG: &'static str = "block-shift";
+
+  type Workspace<S: Sample> = BlockShiftWorkspace<S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    BlockShiftWorkspace::new(width, height)
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    process_block_shift_frame(frame, workspace, self, frame_index)
+  }
+}
@@ -0,0 +1,208 @@
+// Native effect chain: runs several pixel modes back to back on each RGBA frame.
+// One decode/encode pass replaces rendering intermediates between modes.
+use serde::{Deserialize, Serialize};
+
+use super::block_shift::{BlockShiftConfig, BlockShiftWorkspace};
+use super::byte_range::{ModuloMappingConfig, ModuloMappingWorkspace};
+use super::kaleidoscope::{KaleidoscopeConfig, KaleidoscopeWorkspace};
+use super::pixelsort::algo::PixelsortConfig;
+use super::pixelsort::workspace::FrameWorkspace;
+use super::vaporwave::{VaporwaveConfig, VaporwaveWorkspace};
+use crate::effect::NativeEffect;
+use crate::sample::Sample;
+
+// Step ids match the per-mode event tags so the UI can reuse its mode keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", content = "config", rename_all = "kebab-case")]
+pub enum ChainStep {
+  Pixelsort(PixelsortConfig),
+  BlockShift(BlockShiftConfig),
+  Vaporwave(VaporwaveConfig),
+  Kaleidoscope(KaleidoscopeConfig),
+  ModuloMapping(ModuloMappingConfig)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainConfig {
+  pub steps: Vec<ChainStep>
+}
+
+enum StepWorkspace<S> {
+  Pixelsort(Box<FrameWorkspace<S>>),
+  BlockShift(BlockShiftWorkspace<S>),
+  Vaporwave(VaporwaveWorkspace<S>),
+  Kaleidoscope(KaleidoscopeWorkspace<S>),
+  ModuloMapping(ModuloMappingWorkspace<S>)
+}
+
+impl ChainStep {
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> StepWorkspace<S> {
+    match self {
+      ChainStep::Pixelsort(config) => {
+        StepWorkspace::Pixelsort(Box::new(config.workspace(width, height)))
+      }
+      ChainStep::BlockShift(config) => StepWorkspace::BlockShift(config.workspace(width, height)),
+      ChainStep::Vaporwave(config) => StepWorkspace::Vaporwave(config.workspace(width, height)),
+      ChainStep::Kaleidoscope(config) => {
+        StepWorkspace::Kaleidoscope(config.workspace(width, height))
+      }
+      ChainStep::ModuloMapping(config) => {
+        StepWorkspace::ModuloMapping(config.workspace(width, height))
+      }
+    }
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut StepWorkspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    match (self, workspace) {
+      (ChainStep::Pixelsort(config), StepWorkspace::Pixelsort(workspace)) => {
+        config.process_frame(frame, workspace, frame_index)
+      }
+      (ChainStep::BlockShift(config), StepWorkspace::BlockShift(workspace)) => {
+        config.process_frame(frame, workspace, frame_index)
+      }
+      (ChainStep::Vaporwave(config), StepWorkspace::Vaporwave(workspace)) => {
+        config.process_frame(frame, workspace, frame_index)
+      }
+      (ChainStep::Kaleidoscope(config), StepWorkspace::Kaleidoscope(workspace)) => {
+        config.process_frame(frame, workspace, frame_index)
+      }
+      (ChainStep::ModuloMapping(config), StepWorkspace::ModuloMapping(workspace)) => {
+        config.process_frame(frame, workspace, frame_index)
+      }
+      // Workspaces are built from the same step list, so kinds always line up.
+      _ => unreachable!("chain workspace does not match its step")
+    }
+  }
+}
+
+// One workspace per step; each step reads the previous step's output buffer.
+pub struct ChainWorkspace<S> {
+  steps: Vec<StepWorkspace<S>>,
+  passthrough: Vec<S>
+}
+
+impl NativeEffect for ChainConfig {
+  const LABEL: &'static str = "Effect chain";
+  const TAG: &'static str = "chain";
+
+  type Workspace<S: Sample> = ChainWorkspace<S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    ChainWorkspace {
+      steps: self
+        .steps
+        .iter()
+        .map(|step| step.workspace(width, height))
+        .collect(),
+      passthrough: Vec::new()
+    }
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    let ChainWorkspace { steps, passthrough } = workspace;
+    let mut stages = self.steps.iter().zip(steps.iter_mut());
+    let Some((first, first_workspace)) = stages.next() else {
+      // An empty chain still has to hand back an owned frame.
+      passthrough.clear();
+      passthrough.extend_from_slice(frame);
+      return passthrough;
+    };
+    let mut current = first.process_frame(frame, first_workspace, frame_index);
+    for (step, step_workspace) in stages {
+      current = step.process_frame(current, step_workspace, frame_index);
+    }
+    current
+  }
+
+  // A stateful step's input depends on the steps before it, so their warmups add up.
+  fn warmup_frames(&self) -> u64 {
+    self
+      .steps
+      .iter()
+      .map(|step| match step {
+        ChainStep::Pixelsort(config) => config.warmup_frames(),
+        ChainStep::BlockShift(config) => config.warmup_frames(),
+        ChainStep::Vaporwave(config) => config.warmup_frames(),
+        ChainStep::Kaleidoscope(config) => config.warmup_frames(),
+        ChainStep::ModuloMapping(config) => config.warmup_frames()
+      })
+      .sum()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn gradient_frame(width: usize, height: usize) -> Vec<u8> {
+    (0..width * height * 4)
+      .map(|index| ((index * 37) % 251) as u8)
+      .collect()
+  }
+
+  fn block_shift() -> BlockShiftConfig {
+    BlockShiftConfig {
+      block_size: 4,
+      max_offset: 3,
+      offset_step: 1,
+      intensity: 100.0,
+      seed: 7
+    }
+  }
+
+  fn vaporwave() -> VaporwaveConfig {
+    VaporwaveConfig {
+      intensity: 80.0,
+      black_threshold: 20,
+      cyan_max: 90,
+      magenta_max: 140,
+      purple_max: 190,
+      teal_max: 220,
+      white_threshold: 235
+    }
+  }
+
+  #[test]
+  fn empty_chain_passes_frames_through() {
+    let frame = gradient_frame(8, 6);
+    let chain = ChainConfig { steps: Vec::new() };
+    let mut workspace = chain.workspace(8, 6);
+    assert_eq!(chain.process_frame(&frame, &mut workspace, 0), frame.as_slice());
+  }
+
+  #[test]
+  fn chain_matches_steps_applied_in_order() {
+    let (width, height) = (16, 12);
+    let frame = gradient_frame(width, height);
+    let chain = ChainConfig {
+      steps: vec![
+        ChainStep::BlockShift(block_shift()),
+        ChainStep::Vaporwave(vaporwave())
+      ]
+    };
+    let mut chain_workspace = chain.workspace(width, height);
+    let chained = chain
+      .process_frame(&frame, &mut chain_workspace, 3)
+      .to_vec();
+
+    let mut shift_workspace = block_shift().workspace(width, height);
+    let shifted = block_shift()
+      .process_frame(&frame, &mut shift_workspace, 3)
+      .to_vec();
+    let mut vapor_workspace = vaporwave().workspace(width, height);
+    let expected = vaporwave().process_frame(&shifted, &mut vapor_workspace, 3);
+
+    assert_eq!(chained.as_slice(), expected);
+  }
+}
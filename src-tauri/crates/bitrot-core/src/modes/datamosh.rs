@@ -1,9 +1,11 @@
 // Bitstream-level I-frame removal for MPEG-4 Part 2 (m4v) datamosh output.
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 
-#[derive(Clone, Copy, Deserialize)]
+use crate::error::{BitrotError, ErrorCode};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct SceneWindow {
   pub start: f64,
   pub end: f64,
@@ -24,8 +26,7 @@ impl LcgRng {
       .state
       .wrapping_mul(1664525)
       .wrapping_add(1013904223);
-    let value = ((self.state >> 8) & 0xFFFFFF) as f64 / 0xFFFFFF as f64;
-    value
+    ((self.state >> 8) & 0xFFFFFF) as f64 / 0xFFFFFF as f64
   }
 }
 
@@ -72,12 +73,8 @@ fn find_start_code(data: &[u8], from: usize) -> Option<usize> {
   if data.len() < 3 || from >= data.len().saturating_sub(2) {
     return None;
   }
-  for idx in from..=(data.len() - 3) {
-    if data[idx] == 0 && data[idx + 1] == 0 && data[idx + 2] == 1 {
-      return Some(idx);
-    }
-  }
-  None
+  (from..=(data.len() - 3))
+    .find(|&idx| data[idx] == 0 && data[idx + 1] == 0 && data[idx + 2] == 1)
 }
 
 // Stream MPEG-4 start-code-delimited units without loading the full file.
@@ -98,7 +95,7 @@ impl<R: Read> StartCodeReader<R> {
     }
   }
 
-  fn next_unit(&mut self) -> Result<Option<Vec<u8>>, String> {
+  fn next_unit(&mut self) -> Result<Option<Vec<u8>>, BitrotError> {
     const CHUNK_SIZE: usize = 64 * 1024;
     loop {
       if let Some(unit) = self.try_split_unit()? {
@@ -113,7 +110,7 @@ impl<R: Read> StartCodeReader<R> {
       let read = self
         .reader
         .read(&mut chunk)
-        .map_err(|err| format!("Failed to read bitstream: {err}"))?;
+        .map_err(|err| BitrotError::io("Failed to read bitstream", err))?;
       if read == 0 {
         self.eof = true;
       } else {
@@ -122,7 +119,7 @@ impl<R: Read> StartCodeReader<R> {
     }
   }
 
-  fn try_split_unit(&mut self) -> Result<Option<Vec<u8>>, String> {
+  fn try_split_unit(&mut self) -> Result<Option<Vec<u8>>, BitrotError> {
     if !self.aligned {
       if let Some(start) = find_start_code(&self.buffer, 0) {
         if start > 0 {
@@ -139,7 +136,10 @@ impl<R: Read> StartCodeReader<R> {
 
     if self.buffer.len() < 4 {
       if self.eof && !self.buffer.is_empty() {
-        return Err("Truncated MPEG-4 start code at end of file.".into());
+        return Err(BitrotError::new(
+          ErrorCode::DatamoshFailed,
+          "Truncated MPEG-4 start code at end of file.",
+        ));
       }
       return Ok(None);
     }
@@ -173,7 +173,7 @@ fn parse_extradata_hex(extradata_hex: Option<&str>) -> Vec<u8> {
     return Vec::new();
   };
   let filtered: String = raw.chars().filter(|ch| ch.is_ascii_hexdigit()).collect();
-  if filtered.len() < 2 || filtered.len() % 2 != 0 {
+  if filtered.len() < 2 || !filtered.len().is_multiple_of(2) {
     return Vec::new();
   }
 
@@ -228,7 +228,7 @@ pub fn process_datamosh(
   intensity: f64,
   seed: u64,
   extradata_hex: Option<&str>,
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   let extradata_bytes = parse_extradata_hex(extradata_hex);
   let extradata_prefix = if extradata_bytes.is_empty() {
     Vec::new()
@@ -247,19 +247,18 @@ pub fn process_datamosh(
   let mut window_drop_started = vec![false; windows.len()];
   let mut window_cursor = 0usize;
   let input = File::open(input_path)
-    .map_err(|err| format!("Failed to open bitstream: {err}"))?;
+    .map_err(|err| BitrotError::io("Failed to open bitstream", err))?;
   let mut reader = StartCodeReader::new(input);
   let mut output = File::create(output_path)
-    .map_err(|err| format!("Failed to create output bitstream: {err}"))?;
+    .map_err(|err| BitrotError::io("Failed to create output bitstream", err))?;
 
   if !extradata_prefix.is_empty() {
     output
       .write_all(&extradata_prefix)
-      .map_err(|err| format!("Failed to write extradata prefix: {err}"))?;
+      .map_err(|err| BitrotError::io("Failed to write extradata prefix", err))?;
   }
 
-  loop {
-    let Some(unit) = reader.next_unit()? else { break };
+  while let Some(unit) = reader.next_unit()? {
     has_units = true;
     if unit.len() < 4 {
       continue;
@@ -274,7 +273,7 @@ pub fn process_datamosh(
       // Always prepend the leading header units so ffmpeg can read codec params.
       output
         .write_all(&unit)
-        .map_err(|err| format!("Failed to write header units: {err}"))?;
+        .map_err(|err| BitrotError::io("Failed to write header units", err))?;
       continue;
     }
 
@@ -284,14 +283,14 @@ pub fn process_datamosh(
       // Keep headers and non-frame units so the stream stays decodable.
       output
         .write_all(&unit)
-        .map_err(|err| format!("Failed to write output bitstream: {err}"))?;
+        .map_err(|err| BitrotError::io("Failed to write output bitstream", err))?;
       continue;
     }
 
     if is_intra_vop && !has_reference_intra_vop {
       output
         .write_all(&unit)
-        .map_err(|err| format!("Failed to write first intra frame: {err}"))?;
+        .map_err(|err| BitrotError::io("Failed to write first intra frame", err))?;
       has_reference_intra_vop = true;
       vop_index += 1;
       continue;
@@ -319,7 +318,7 @@ pub fn process_datamosh(
     if !should_drop {
       output
         .write_all(&unit)
-        .map_err(|err| format!("Failed to write output bitstream: {err}"))?;
+        .map_err(|err| BitrotError::io("Failed to write output bitstream", err))?;
     }
 
     vop_index += 1;
@@ -327,9 +326,15 @@ pub fn process_datamosh(
 
   if !has_vop {
     if !has_units {
-      return Err("No MPEG-4 start codes found in bitstream".into());
+      return Err(BitrotError::new(
+        ErrorCode::DatamoshFailed,
+        "No MPEG-4 start codes found in bitstream",
+      ));
     }
-    return Err("No VOP frames found in MPEG-4 bitstream".into());
+    return Err(BitrotError::new(
+      ErrorCode::DatamoshFailed,
+      "No VOP frames found in MPEG-4 bitstream",
+    ));
   }
 
   Ok(())
@@ -429,8 +434,9 @@ mod tests {
       None,
     );
     assert!(result.is_err());
-    let message = result.err().unwrap();
-    assert!(message.contains("No MPEG-4 start codes found"));
+    let error = result.err().unwrap();
+    assert_eq!(error.code, ErrorCode::DatamoshFailed);
+    assert!(error.message.contains("No MPEG-4 start codes found"));
 
     remove_path(&input_path);
     remove_path(&output_path);
@@ -454,8 +460,9 @@ mod tests {
       None,
     );
     assert!(result.is_err());
-    let message = result.err().unwrap();
-    assert!(message.contains("No VOP frames found"));
+    let error = result.err().unwrap();
+    assert_eq!(error.code, ErrorCode::DatamoshFailed);
+    assert!(error.message.contains("No VOP frames found"));
 
     remove_path(&input_path);
     remove_path(&output_path);
@@ -0,0 +1,237 @@
+// Native kaleidoscope pipeline for mirrored symmetry wedges.
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::parallel::for_each_row_band;
+use crate::sample::{blend_channel, Sample};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KaleidoscopeConfig {
+  pub sectors: u32,
+  pub rotation_degrees: f32,
+  pub center_x: f32,
+  pub center_y: f32,
+  pub intensity: f32
+}
+
+#[derive(Clone, Copy)]
+struct KaleidoscopeMapKey {
+  width: usize,
+  height: usize,
+  sectors: u32,
+  rotation_bits: u32,
+  center_x_bits: u32,
+  center_y_bits: u32
+}
+
+impl PartialEq for KaleidoscopeMapKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.width == other.width
+      && self.height == other.height
+      && self.sectors == other.sectors
+      && self.rotation_bits == other.rotation_bits
+      && self.center_x_bits == other.center_x_bits
+      && self.center_y_bits == other.center_y_bits
+  }
+}
+
+struct NormalizedKaleidoscope {
+  sectors: u32,
+  rotation_radians: f32,
+  center_x: f32,
+  center_y: f32
+}
+
+fn clamp_f32(value: f32, min: f32, max: f32) -> f32 {
+  value.max(min).min(max)
+}
+
+fn normalize_kaleidoscope(
+  config: &KaleidoscopeConfig,
+  width: usize,
+  height: usize
+) -> NormalizedKaleidoscope {
+  let sectors = config.sectors.clamp(2, 64);
+  let rotation_radians = config.rotation_degrees.to_radians();
+  let center_x_norm = clamp_f32(config.center_x, 0.0, 1.0);
+  let center_y_norm = clamp_f32(config.center_y, 0.0, 1.0);
+  let max_x = width.saturating_sub(1) as f32;
+  let max_y = height.saturating_sub(1) as f32;
+  NormalizedKaleidoscope {
+    sectors,
+    rotation_radians,
+    center_x: center_x_norm * max_x,
+    center_y: center_y_norm * max_y
+  }
+}
+
+fn build_kaleidoscope_map(
+  width: usize,
+  height: usize,
+  normalized: NormalizedKaleidoscope
+) -> Vec<usize> {
+  let pixel_count = width.saturating_mul(height);
+  let mut map = vec![0usize; pixel_count];
+  if width == 0 || height == 0 {
+    return map;
+  }
+
+  let tau = std::f32::consts::TAU;
+  let step = tau / normalized.sectors as f32;
+  let half_step = step * 0.5;
+  let max_x = (width - 1) as f32;
+  let max_y = (height - 1) as f32;
+
+  for_each_row_band(&mut map, width, 1, |first_row, rows| {
+    for (offset, row) in rows.chunks_mut(width).enumerate() {
+      let dy = (first_row + offset) as f32 - normalized.center_y;
+      for (x, entry) in row.iter_mut().enumerate() {
+        let dx = x as f32 - normalized.center_x;
+        let mut angle = dy.atan2(dx) + normalized.rotation_radians;
+        angle %= tau;
+        if angle < 0.0 {
+          angle += tau;
+        }
+        let sector_index = (angle / step).floor();
+        let local = angle - sector_index * step;
+        let mirrored = if local > half_step {
+          step - local
+        } else {
+          local
+        };
+        let mapped_angle = sector_index * step + mirrored;
+        let radius = (dx * dx + dy * dy).sqrt();
+        let src_x = (normalized.center_x + radius * mapped_angle.cos())
+          .round()
+          .clamp(0.0, max_x);
+        let src_y = (normalized.center_y + radius * mapped_angle.sin())
+          .round()
+          .clamp(0.0, max_y);
+        let src_idx =
+          (src_y as usize).saturating_mul(width) + src_x as usize;
+        *entry = src_idx * 4;
+      }
+    }
+  });
+  map
+}
+
+// Reusable buffers for per-frame processing to avoid extra allocations.
+pub struct KaleidoscopeWorkspace<S> {
+  width: usize,
+  height: usize,
+  output: Vec<S>,
+  map: Vec<usize>,
+  map_key: Option<KaleidoscopeMapKey>
+}
+
+impl<S: Sample> KaleidoscopeWorkspace<S> {
+  fn new(width: usize, height: usize) -> Self {
+    let byte_len = width.saturating_mul(height).saturating_mul(4);
+    Self {
+      width,
+      height,
+      output: vec![S::default(); byte_len],
+      map: Vec::new(),
+      map_key: None
+    }
+  }
+
+  fn ensure_size(&mut self, byte_len: usize) {
+    if self.output.len() != byte_len {
+      self.output.resize(byte_len, S::default());
+    }
+  }
+
+  fn ensure_map(&mut self, config: &KaleidoscopeConfig) {
+    let normalized = normalize_kaleidoscope(config, self.width, self.height);
+    let next_key = KaleidoscopeMapKey {
+      width: self.width,
+      height: self.height,
+      sectors: normalized.sectors,
+      rotation_bits: normalized.rotation_radians.to_bits(),
+      center_x_bits: normalized.center_x.to_bits(),
+      center_y_bits: normalized.center_y.to_bits()
+    };
+    if self.map_key == Some(next_key) {
+      return;
+    }
+    self.map = build_kaleidoscope_map(self.width, self.height, normalized);
+    self.map_key = Some(next_key);
+  }
+}
+
+fn process_kaleidoscope_frame<'a, S: Sample>(
+  frame: &[S],
+  workspace: &'a mut KaleidoscopeWorkspace<S>,
+  config: &KaleidoscopeConfig
+) -> &'a [S] {
+  let byte_len = frame.len();
+  workspace.ensure_size(byte_len);
+  if byte_len == 0 {
+    return &workspace.output;
+  }
+  workspace.output.copy_from_slice(frame);
+
+  let mix = (config.intensity / 100.0).clamp(0.0, 1.0);
+  if mix <= 0.0 {
+    return &workspace.output;
+  }
+
+  let width = workspace.width;
+  let height = workspace.height;
+  if width == 0 || height == 0 {
+    return &workspace.output;
+  }
+  let pixel_count = width.saturating_mul(height);
+  if pixel_count < 2 {
+    return &workspace.output;
+  }
+
+  workspace.ensure_map(config);
+  let is_full_strength = mix >= 0.999;
+
+  let map = &workspace.map;
+  for_each_row_band(&mut workspace.output[..pixel_count * 4], 4, 1, |first_pixel, band| {
+    for (offset, pixel) in band.chunks_exact_mut(4).enumerate() {
+      let dst_idx = (first_pixel + offset) * 4;
+      let src_idx = map[first_pixel + offset];
+      let mapped_r = frame[src_idx];
+      let mapped_g = frame[src_idx + 1];
+      let mapped_b = frame[src_idx + 2];
+      if is_full_strength {
+        pixel[0] = mapped_r;
+        pixel[1] = mapped_g;
+        pixel[2] = mapped_b;
+      } else {
+        pixel[0] = blend_channel(frame[dst_idx], mapped_r, mix);
+        pixel[1] = blend_channel(frame[dst_idx + 1], mapped_g, mix);
+        pixel[2] = blend_channel(frame[dst_idx + 2], mapped_b, mix);
+      }
+      // Alpha stays as-is from the original frame.
+    }
+  });
+
+  &workspace.output
+}
+
+impl NativeEffect for KaleidoscopeConfig {
+  const LABEL: &'static str = "Kaleidoscope";
+  const TAG: &'static str = "kaleidoscope";
+
+  type Workspace<S: Sample> = KaleidoscopeWorkspace<S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    KaleidoscopeWorkspace::new(width, height)
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    _frame_index: u64
+  ) -> &'a [S] {
+    process_kaleidoscope_frame(frame, workspace, self)
+  }
+}
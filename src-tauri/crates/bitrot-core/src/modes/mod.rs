@@ -0,0 +1,9 @@
+// Effect implementations: per-frame RGBA processors plus the datamosh bitstream pass.
+
+pub mod block_shift;
+pub mod byte_range;
+pub mod chain;
+pub mod datamosh;
+pub mod kaleidoscope;
+pub mod pixelsort;
+pub mod vaporwave;
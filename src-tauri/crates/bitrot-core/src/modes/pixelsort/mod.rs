@@ -0,0 +1,4 @@
+// Pixelsort module split into focused submodules.
+
+pub mod algo;
+pub mod workspace;
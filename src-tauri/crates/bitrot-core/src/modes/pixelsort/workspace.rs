@@ -0,0 +1,99 @@
+// Workspace buffers and low-level pixel helpers for the pixelsort pipeline.
+// Keeping these in a dedicated module lets the job code focus on control flow.
+use crate::sample::Sample;
+
+// Use standard luma weights to preserve perceptual brightness; 0..255 level, unrounded.
+pub(crate) fn luma_level<S: Sample>(r: S, g: S, b: S) -> f32 {
+  0.299 * r.level() + 0.587 * g.level() + 0.114 * b.level()
+}
+
+// Sort keys stay 8-bit at every depth so the counting sort keeps its 256 buckets.
+pub(crate) fn luma<S: Sample>(r: S, g: S, b: S) -> u8 {
+  luma_level(r, g, b).round().clamp(0.0, 255.0) as u8
+}
+
+// Reusable buffers for per-frame processing to avoid extra allocations.
+pub struct FrameWorkspace<S> {
+  pub(crate) width: usize,
+  pub(crate) height: usize,
+  pub(crate) pixel_count: usize,
+  pub(crate) output: Vec<S>,
+  pub(crate) luma: Vec<u8>,
+  pub(crate) scratch: Vec<S>,
+  // Column-major copy of the frame so vertical passes can split by column.
+  pub(crate) columns: Vec<S>,
+  // One sort scratch per row band, kept across frames.
+  pub(crate) sorts: Vec<SortScratch<S>>
+}
+
+impl<S: Sample> FrameWorkspace<S> {
+  pub(crate) fn new(width: usize, height: usize) -> Self {
+    let pixel_count = width.saturating_mul(height);
+    let byte_len = pixel_count * 4;
+    Self {
+      width,
+      height,
+      pixel_count,
+      output: vec![S::default(); byte_len],
+      luma: vec![0; pixel_count],
+      scratch: vec![S::default(); byte_len],
+      columns: Vec::new(),
+      sorts: Vec::new()
+    }
+  }
+
+  // Copies input into the output buffer and precomputes luma for fast access.
+  pub(crate) fn prepare(&mut self, input: &[S]) {
+    if self.output.len() != input.len() {
+      self.output.resize(input.len(), S::default());
+    }
+    if self.scratch.len() != input.len() {
+      self.scratch.resize(input.len(), S::default());
+    }
+    self.output.copy_from_slice(input);
+    if self.luma.len() != self.pixel_count {
+      self.luma.resize(self.pixel_count, 0);
+    }
+    for i in 0..self.pixel_count {
+      let idx = i * 4;
+      self.luma[i] = luma(input[idx], input[idx + 1], input[idx + 2]);
+    }
+  }
+
+  pub(crate) fn ensure_columns(&mut self) {
+    let byte_len = self.pixel_count * 4;
+    if self.columns.len() != byte_len {
+      self.columns.resize(byte_len, S::default());
+    }
+  }
+
+  pub(crate) fn output(&self) -> &[S] {
+    &self.output
+  }
+}
+
+// Per-band sort buffers; each band of a parallel pass owns one.
+pub(crate) struct SortScratch<S> {
+  pub(crate) bytes: Vec<S>,
+  pub(crate) segment_indices: Vec<usize>,
+  pub(crate) luma_counts: [usize; 256],
+  pub(crate) luma_offsets: [usize; 256]
+}
+
+impl<S: Sample> SortScratch<S> {
+  pub(crate) fn new(max_segment: usize) -> Self {
+    Self {
+      bytes: vec![S::default(); max_segment * 4],
+      segment_indices: Vec::with_capacity(max_segment),
+      luma_counts: [0; 256],
+      luma_offsets: [0; 256]
+    }
+  }
+
+  // Grows the byte buffer when a pass needs longer segments than the last one.
+  pub(crate) fn reserve(&mut self, max_segment: usize) {
+    if self.bytes.len() < max_segment * 4 {
+      self.bytes.resize(max_segment * 4, S::default());
+    }
+  }
+}
@@ -0,0 +1,192 @@
+// Native vaporwave palette remap pipeline for neon banding.
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::parallel::for_each_row_band;
+use crate::sample::{blend_channel, Sample};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaporwaveConfig {
+  pub intensity: f32,
+  pub black_threshold: u32,
+  pub cyan_max: u32,
+  pub magenta_max: u32,
+  pub purple_max: u32,
+  pub teal_max: u32,
+  pub white_threshold: u32
+}
+
+const COLOR_CYAN: (u8, u8, u8) = (0, 184, 255);
+const COLOR_MAGENTA: (u8, u8, u8) = (255, 0, 193);
+const COLOR_PURPLE: (u8, u8, u8) = (150, 0, 255);
+const COLOR_TEAL: (u8, u8, u8) = (0, 255, 249);
+
+// Thresholds scaled to the frame's sample depth.
+struct VaporwaveThresholds<S> {
+  black: S,
+  cyan: S,
+  magenta: S,
+  purple: S,
+  teal: S,
+  white: S
+}
+
+fn clamp_u8(value: u32) -> u8 {
+  value.min(255) as u8
+}
+
+// Normalizes threshold ordering so bands stay in ascending order.
+fn normalize_thresholds<S: Sample>(config: &VaporwaveConfig) -> VaporwaveThresholds<S> {
+  let black = clamp_u8(config.black_threshold);
+  let cyan = clamp_u8(config.cyan_max).max(black);
+  let magenta = clamp_u8(config.magenta_max).max(cyan);
+  let purple = clamp_u8(config.purple_max).max(magenta);
+  let teal = clamp_u8(config.teal_max).max(purple);
+  let white = clamp_u8(config.white_threshold).max(teal);
+  VaporwaveThresholds {
+    black: S::from_u8(black),
+    cyan: S::from_u8(cyan),
+    magenta: S::from_u8(magenta),
+    purple: S::from_u8(purple),
+    teal: S::from_u8(teal),
+    white: S::from_u8(white)
+  }
+}
+
+// Maps grayscale-ish pixels into the vaporwave palette.
+fn map_vaporwave_color<S: Sample>(
+  r: S,
+  g: S,
+  b: S,
+  thresholds: &VaporwaveThresholds<S>
+) -> (S, S, S) {
+  let color = |(r, g, b): (u8, u8, u8)| (S::from_u8(r), S::from_u8(g), S::from_u8(b));
+  if r <= thresholds.black && g <= thresholds.black && b <= thresholds.black {
+    return color((0, 0, 0));
+  }
+  if r >= thresholds.white && g >= thresholds.white && b >= thresholds.white {
+    return color((255, 255, 255));
+  }
+  if r > thresholds.black
+    && r <= thresholds.cyan
+    && g > thresholds.black
+    && g <= thresholds.cyan
+    && b > thresholds.black
+    && b <= thresholds.cyan
+  {
+    return color(COLOR_CYAN);
+  }
+  if r > thresholds.cyan
+    && r <= thresholds.magenta
+    && g > thresholds.cyan
+    && g <= thresholds.magenta
+    && b > thresholds.cyan
+    && b <= thresholds.magenta
+  {
+    return color(COLOR_MAGENTA);
+  }
+  if r > thresholds.magenta
+    && r <= thresholds.purple
+    && g > thresholds.magenta
+    && g <= thresholds.purple
+    && b > thresholds.magenta
+    && b <= thresholds.purple
+  {
+    return color(COLOR_PURPLE);
+  }
+  if r > thresholds.purple
+    && r <= thresholds.teal
+    && g > thresholds.purple
+    && g <= thresholds.teal
+    && b > thresholds.purple
+    && b <= thresholds.teal
+  {
+    return color(COLOR_TEAL);
+  }
+  (r, g, b)
+}
+
+// Reusable buffer for per-frame processing to avoid extra allocations.
+pub struct VaporwaveWorkspace<S> {
+  output: Vec<S>
+}
+
+impl<S: Sample> VaporwaveWorkspace<S> {
+  fn new(byte_len: usize) -> Self {
+    Self {
+      output: vec![S::default(); byte_len]
+    }
+  }
+
+  fn ensure_size(&mut self, byte_len: usize) {
+    if self.output.len() != byte_len {
+      self.output.resize(byte_len, S::default());
+    }
+  }
+}
+
+fn process_vaporwave_frame<'a, S: Sample>(
+  frame: &[S],
+  workspace: &'a mut VaporwaveWorkspace<S>,
+  config: &VaporwaveConfig
+) -> &'a [S] {
+  let byte_len = frame.len();
+  workspace.ensure_size(byte_len);
+  if byte_len == 0 {
+    return &workspace.output;
+  }
+  workspace.output.copy_from_slice(frame);
+
+  let mix = (config.intensity / 100.0).clamp(0.0, 1.0);
+  if mix <= 0.0 {
+    return &workspace.output;
+  }
+  let thresholds = normalize_thresholds(config);
+  let is_full_strength = mix >= 0.999;
+
+  // Pixels are independent, so each one is treated as its own row.
+  for_each_row_band(&mut workspace.output, 4, 1, |first_pixel, band| {
+    let source = &frame[first_pixel * 4..first_pixel * 4 + band.len()];
+    for idx in (0..band.len()).step_by(4) {
+      let r = source[idx];
+      let g = source[idx + 1];
+      let b = source[idx + 2];
+
+      let (mapped_r, mapped_g, mapped_b) =
+        map_vaporwave_color(r, g, b, &thresholds);
+      if is_full_strength {
+        band[idx] = mapped_r;
+        band[idx + 1] = mapped_g;
+        band[idx + 2] = mapped_b;
+      } else {
+        band[idx] = blend_channel(r, mapped_r, mix);
+        band[idx + 1] = blend_channel(g, mapped_g, mix);
+        band[idx + 2] = blend_channel(b, mapped_b, mix);
+      }
+      // Alpha stays as-is from the original frame.
+    }
+  });
+
+  &workspace.output
+}
+
+impl NativeEffect for VaporwaveConfig {
+  const LABEL: &'static str = "Vaporwave";
+  const TAG: &'static str = "vaporwave";
+
+  type Workspace<S: Sample> = VaporwaveWorkspace<S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    VaporwaveWorkspace::new(width.saturating_mul(height).saturating_mul(4))
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    _frame_index: u64
+  ) -> &'a [S] {
+    process_vaporwave_frame(frame, workspace, self)
+  }
+}
@@ -0,0 +1,326 @@
+// Row-band parallelism for the per-frame processors.
+// Each band owns a disjoint run of whole rows and only reads shared inputs, so the
+// output is byte-identical to a single-threaded pass regardless of the thread count.
+use std::cell::Cell;
+use std::sync::OnceLock;
+use std::thread;
+
+// Bands smaller than this cost more to spawn than they save.
+const MIN_BAND_BYTES: usize = 256 * 1024;
+
+thread_local! {
+  static WORKER_LIMIT: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+// Worker threads per frame: `BITROT_THREADS` when set, otherwise every available core.
+pub fn worker_count() -> usize {
+  if let Some(limit) = WORKER_LIMIT.with(Cell::get) {
+    return limit.max(1);
+  }
+  static DEFAULT: OnceLock<usize> = OnceLock::new();
+  *DEFAULT.get_or_init(|| {
+    std::env::var("BITROT_THREADS")
+      .ok()
+      .and_then(|value| value.trim().parse::<usize>().ok())
+      .filter(|count| *count > 0)
+      .unwrap_or_else(|| thread::available_parallelism().map_or(1, |count| count.get()))
+  })
+}
+
+// Runs `run` with frame processing on this thread capped at `count` workers.
+pub fn with_worker_count<R>(count: usize, run: impl FnOnce() -> R) -> R {
+  let previous = WORKER_LIMIT.with(|limit| limit.replace(Some(count)));
+  let result = run();
+  WORKER_LIMIT.with(|limit| limit.set(previous));
+  result
+}
+
+// Splits `data` into bands of whole rows (`row_len` elements each) and calls
+// `work(first_row, band)` for every band. Band starts are multiples of `row_align`
+// so block-based effects never see a block cut in half.
+pub(crate) fn for_each_row_band<T, F>(data: &mut [T], row_len: usize, row_align: usize, work: F)
+where
+  T: Send,
+  F: Fn(usize, &mut [T]) + Sync
+{
+  for_each_row_band_with(data, row_len, row_align, &mut Vec::new(), || (), |first_row, band, _| {
+    work(first_row, band)
+  });
+}
+
+// Same as `for_each_row_band`, but every band also gets its own entry of `states`, so
+// scratch buffers can live in a workspace across frames. Missing entries come from
+// `new_state`; extra ones are left alone.
+pub(crate) fn for_each_row_band_with<T, U, F>(
+  data: &mut [T],
+  row_len: usize,
+  row_align: usize,
+  states: &mut Vec<U>,
+  new_state: impl Fn() -> U,
+  work: F
+) where
+  T: Send,
+  U: Send,
+  F: Fn(usize, &mut [T], &mut U) + Sync
+{
+  let rows = data.len().checked_div(row_len).unwrap_or(0);
+  let row_align = row_align.max(1);
+  let groups = rows.div_ceil(row_align);
+  let byte_len = std::mem::size_of_val(data);
+  let band_count = worker_count()
+    .min(groups)
+    .min((byte_len / MIN_BAND_BYTES).max(1));
+  if states.len() < band_count.max(1) {
+    states.resize_with(band_count.max(1), new_state);
+  }
+  if band_count <= 1 {
+    work(0, data, &mut states[0]);
+    return;
+  }
+
+  let band_rows = groups.div_ceil(band_count) * row_align;
+  thread::scope(|scope| {
+    let mut bands = data.chunks_mut(band_rows * row_len).zip(states.iter_mut()).enumerate();
+    let first = bands.next();
+    for (index, (band, state)) in bands {
+      let work = &work;
+      scope.spawn(move || work(index * band_rows, band, state));
+    }
+    // The calling thread takes the first band instead of idling.
+    if let Some((_, (band, state))) = first {
+      work(0, band, state);
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::effect::NativeEffect;
+  use crate::modes::block_shift::BlockShiftConfig;
+  use crate::modes::byte_range::ModuloMappingConfig;
+  use crate::modes::kaleidoscope::KaleidoscopeConfig;
+  use crate::modes::pixelsort::algo::PixelsortConfig;
+  use crate::modes::vaporwave::VaporwaveConfig;
+  use crate::sample::Sample;
+
+  // Large enough for several bands; odd sizes leave a short final band.
+  const WIDTH: usize = 642;
+  const HEIGHT: usize = 362;
+
+  fn test_frame(seed: u32) -> Vec<u8> {
+    let mut state = seed;
+    (0..WIDTH * HEIGHT * 4)
+      .map(|index| {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        // Smooth gradients with some noise so every luma band gets hit.
+        let gradient = ((index / 4) % WIDTH * 255 / WIDTH) as u32;
+        (gradient / 2 + (state >> 25)) as u8
+      })
+      .collect()
+  }
+
+  fn render<E: NativeEffect>(effect: &E, workers: usize) -> Vec<Vec<u8>> {
+    with_worker_count(workers, || {
+      let mut workspace = effect.workspace(WIDTH, HEIGHT);
+      (0..3u64)
+        .map(|frame_index| {
+          let frame = test_frame(frame_index as u32 + 7);
+          effect
+            .process_frame(&frame, &mut workspace, frame_index)
+            .to_vec()
+        })
+        .collect()
+    })
+  }
+
+  fn assert_matches_serial<E: NativeEffect>(effect: &E) {
+    let serial = render(effect, 1);
+    for workers in [2, 3, 8] {
+      assert!(
+        render(effect, workers) == serial,
+        "{} differs with {workers} workers",
+        E::LABEL
+      );
+    }
+  }
+
+  // FNV-1a over every output frame, rendered across several bands.
+  fn render_hash<E: NativeEffect>(effect: &E) -> u64 {
+    render(effect, 4)
+      .iter()
+      .flatten()
+      .fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+  }
+
+  // 16-bit processing of an 8-bit frame lands within one 8-bit step of the 8-bit render.
+  fn assert_tracks_eight_bit<E: NativeEffect>(effect: &E) {
+    let frame = test_frame(3);
+    let wide: Vec<u16> = frame.iter().map(|value| u16::from_u8(*value)).collect();
+    let mut narrow_workspace = effect.workspace(WIDTH, HEIGHT);
+    let mut wide_workspace = effect.workspace(WIDTH, HEIGHT);
+    let narrow = effect.process_frame(&frame, &mut narrow_workspace, 1);
+    let wide = effect.process_frame(&wide, &mut wide_workspace, 1);
+    let drift = narrow
+      .iter()
+      .zip(wide)
+      .map(|(narrow, wide)| narrow.abs_diff(wide.to_u8()))
+      .max();
+    assert!(drift <= Some(1), "{} drifts by {drift:?} at 16 bits", E::LABEL);
+  }
+
+  #[test]
+  fn row_bands_cover_every_row_once_on_aligned_starts() {
+    let mut data = vec![0u32; 97 * 4096];
+    with_worker_count(4, || {
+      for_each_row_band(&mut data, 4096, 8, |first_row, band| {
+        assert_eq!(first_row % 8, 0);
+        for (offset, row) in band.chunks_mut(4096).enumerate() {
+          row.iter_mut().for_each(|value| *value += (first_row + offset) as u32 + 1);
+        }
+      });
+    });
+    for (row, values) in data.chunks(4096).enumerate() {
+      assert!(values.iter().all(|value| *value == row as u32 + 1));
+    }
+  }
+
+  #[test]
+  fn parallel_processors_match_serial_output() {
+    for intensity in [100.0, 60.0] {
+      assert_matches_serial(&VaporwaveConfig {
+        intensity,
+        black_threshold: 20,
+        cyan_max: 60,
+        magenta_max: 110,
+        purple_max: 160,
+        teal_max: 210,
+        white_threshold: 240
+      });
+      assert_matches_serial(&BlockShiftConfig {
+        block_size: 12,
+        max_offset: 9,
+        offset_step: 2,
+        intensity,
+        seed: 4242
+      });
+      assert_matches_serial(&KaleidoscopeConfig {
+        sectors: 7,
+        rotation_degrees: 33.0,
+        center_x: 0.4,
+        center_y: 0.6,
+        intensity
+      });
+      assert_matches_serial(&ModuloMappingConfig {
+        modulus: 10,
+        stride: 3,
+        offset: 14,
+        intensity
+      });
+      for direction in ["horizontal", "vertical", "block"] {
+        assert_matches_serial(&PixelsortConfig {
+          intensity,
+          threshold: 40.0,
+          max_threshold: 200.0,
+          block_size: 9,
+          direction: direction.into()
+        });
+      }
+    }
+  }
+
+  // Hashes captured from the serial processors before row bands, so banding can't drift
+  // from the original output even when every worker count agrees.
+  #[test]
+  fn parallel_processors_match_pre_band_output() {
+    let vaporwave = VaporwaveConfig {
+      intensity: 60.0,
+      black_threshold: 20,
+      cyan_max: 60,
+      magenta_max: 110,
+      purple_max: 160,
+      teal_max: 210,
+      white_threshold: 240
+    };
+    assert_eq!(render_hash(&vaporwave), 0xa88abdb8ac658787);
+    let block_shift = BlockShiftConfig {
+      block_size: 12,
+      max_offset: 9,
+      offset_step: 2,
+      intensity: 60.0,
+      seed: 4242
+    };
+    assert_eq!(render_hash(&block_shift), 0x1ba81cba465afc1d);
+    let kaleidoscope = KaleidoscopeConfig {
+      sectors: 7,
+      rotation_degrees: 33.0,
+      center_x: 0.4,
+      center_y: 0.6,
+      intensity: 60.0
+    };
+    assert_eq!(render_hash(&kaleidoscope), 0xf11e4a70f53d86c8);
+    let modulo = ModuloMappingConfig {
+      modulus: 10,
+      stride: 3,
+      offset: 14,
+      intensity: 60.0
+    };
+    assert_eq!(render_hash(&modulo), 0x1c6f2bba79320e5b);
+    for (direction, expected) in [
+      ("horizontal", 0x64983336580761f3),
+      ("vertical", 0xa10ef3064c8bf2ec),
+      ("block", 0xaa72b31c86fe97b0)
+    ] {
+      let pixelsort = PixelsortConfig {
+        intensity: 60.0,
+        threshold: 40.0,
+        max_threshold: 200.0,
+        block_size: 9,
+        direction: direction.into()
+      };
+      assert_eq!(render_hash(&pixelsort), expected, "{direction} pixel sort");
+    }
+  }
+
+  #[test]
+  fn sixteen_bit_processors_track_eight_bit_output() {
+    assert_tracks_eight_bit(&VaporwaveConfig {
+      intensity: 70.0,
+      black_threshold: 20,
+      cyan_max: 60,
+      magenta_max: 110,
+      purple_max: 160,
+      teal_max: 210,
+      white_threshold: 240
+    });
+    assert_tracks_eight_bit(&BlockShiftConfig {
+      block_size: 12,
+      max_offset: 9,
+      offset_step: 2,
+      intensity: 60.0,
+      seed: 4242
+    });
+    assert_tracks_eight_bit(&KaleidoscopeConfig {
+      sectors: 7,
+      rotation_degrees: 33.0,
+      center_x: 0.4,
+      center_y: 0.6,
+      intensity: 60.0
+    });
+    assert_tracks_eight_bit(&ModuloMappingConfig {
+      modulus: 10,
+      stride: 3,
+      offset: 14,
+      intensity: 60.0
+    });
+    for direction in ["horizontal", "vertical", "block"] {
+      assert_tracks_eight_bit(&PixelsortConfig {
+        intensity: 60.0,
+        threshold: 40.0,
+        max_threshold: 200.0,
+        block_size: 9,
+        direction: direction.into()
+      });
+    }
+  }
+}
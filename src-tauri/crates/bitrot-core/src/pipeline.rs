@@ -0,0 +1,2299 @@
+// Shared native effect host (decode -> per-frame effect -> encode -> mux) plus arg/path helpers.
+// The host is blocking std::process code; async callers run it on a worker thread.
+use std::{
+  borrow::Cow,
+  io::{BufRead, BufReader, Read, Write},
+  path::{Path, PathBuf},
+  process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio},
+  sync::atomic::{AtomicBool, AtomicU64, Ordering},
+  sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender},
+  thread::{JoinHandle, ScopedJoinHandle},
+  time::{Duration, Instant}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::animated::{write_animation, AnimatedFormat, AnimationOptions};
+use crate::checkpoint::Checkpoint;
+use crate::effect::NativeEffect;
+use crate::error::{BitrotError, ErrorCode};
+use crate::events::{JobStage, LogLevel};
+use crate::mask::{apply_matte, Matte, MatteEnd};
+use crate::progress::{JobReporter, ProgressClock, StageWaits};
+use crate::provenance::{container_metadata, record_render};
+use crate::sample::{to_rgba8, Sample};
+use crate::sequence::{
+  self, build_sequence_encode_args, find_start_number, is_sequence_pattern, remove_frames_from,
+  OutputSequence
+};
+use crate::target_size::{
+  build_first_pass_args, build_second_pass_args, encoded_duration, report_size,
+  validate_target_size, video_bitrate_kbps, PassInput, PASS_LOG_NAME
+};
+use crate::toolchain::toolchain_report;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeEncoding {
+  pub encoder: String,
+  pub preset: String,
+  pub crf: Option<u32>,
+  pub cq: Option<u32>,
+  pub max_bitrate_kbps: Option<u32>,
+  pub target_bitrate_kbps: Option<u32>,
+  pub vp9_deadline: Option<String>,
+  pub vp9_cpu_used: Option<u8>,
+  pub format: String,
+  pub audio_enabled: bool,
+  pub audio_codec: Option<String>,
+  pub audio_bitrate_kbps: Option<u32>,
+  #[serde(default)]
+  pub extra_encode_args: Vec<String>,
+  #[serde(default)]
+  pub extra_mux_args: Vec<String>,
+  // Decode, process and encode at 16 bits per channel (`rgba64le`) instead of 8.
+  #[serde(default)]
+  pub high_bit_depth: bool,
+  // Output pixel format; the encoder's default when unset.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub pixel_format: Option<String>,
+  // Keep the frame's alpha channel; needs an encoder and container that carry it.
+  #[serde(default)]
+  pub alpha: bool,
+  // Palette, caps and loop settings for `gif`/`webp` formats.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub animation: Option<AnimationOptions>,
+  // SVT-AV1 preset (0-13) or libaom cpu-used (0-8); lower is slower and better.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub av1_speed: Option<u8>,
+  // AV1 tile grid for parallel encoding and decoding.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tiles: Option<Tiles>,
+  // Whole-file size budget in megabytes; switches the render to a two-pass encode.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub target_size_mb: Option<f64>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tiles {
+  pub columns: u32,
+  pub rows: u32
+}
+
+// Audio bitrate the mux step uses when `audio_bitrate_kbps` is unset.
+pub const DEFAULT_AUDIO_BITRATE_KBPS: u32 = 192;
+
+const MP4_FAMILY: &[&str] = &["mp4", "m4v", "mov", "mkv"];
+const AV1_CONTAINERS: &[&str] = &["mp4", "mkv", "webm"];
+const PCM_CONTAINERS: &[&str] = &["mov", "mkv", "avi"];
+
+// 8- and 10-bit YUV at 4:2:0, 4:2:2 and 4:4:4; 4:2:0 stays the default for playback.
+const YUV_FORMATS: &[&str] = &[
+  "yuv420p",
+  "yuv422p",
+  "yuv444p",
+  "yuv420p10le",
+  "yuv422p10le",
+  "yuv444p10le"
+];
+// The same plus planar RGB, for encoders that code RGB without a colour conversion.
+const YUV_AND_RGB_FORMATS: &[&str] = &[
+  "yuv420p",
+  "yuv422p",
+  "yuv444p",
+  "yuv420p10le",
+  "yuv422p10le",
+  "yuv444p10le",
+  "gbrp"
+];
+// Planar RGB from a delivery codec is only signalled reliably in Matroska.
+const PLANAR_RGB_CONTAINERS: &[&str] = &["mkv"];
+
+// What one native encoder can write. The first pixel format in each list is the default.
+struct EncoderProfile {
+  name: &'static str,
+  // Containers the mux step can put the stream in.
+  containers: &'static [&'static str],
+  pixel_formats: &'static [&'static str],
+  alpha_pixel_formats: &'static [&'static str],
+  // Containers that keep the alpha plane; empty when the codec has none.
+  alpha_containers: &'static [&'static str],
+  // Intra-only mezzanine codec: no rate control, PCM audio by default and no `+faststart`.
+  intra: bool
+}
+
+const ENCODERS: &[EncoderProfile] = &[
+  EncoderProfile {
+    name: "libx264",
+    containers: MP4_FAMILY,
+    pixel_formats: YUV_FORMATS,
+    alpha_pixel_formats: &[],
+    alpha_containers: &[],
+    intra: false
+  },
+  EncoderProfile {
+    name: "h264_nvenc",
+    containers: MP4_FAMILY,
+    pixel_formats: &["yuv420p", "yuv444p"],
+    alpha_pixel_formats: &[],
+    alpha_containers: &[],
+    intra: false
+  },
+  EncoderProfile {
+    name: "libx265",
+    containers: MP4_FAMILY,
+    pixel_formats: YUV_AND_RGB_FORMATS,
+    alpha_pixel_formats: &[],
+    alpha_containers: &[],
+    intra: false
+  },
+  EncoderProfile {
+    name: "libvpx-vp9",
+    containers: &["webm", "mkv", "mp4"],
+    pixel_formats: YUV_AND_RGB_FORMATS,
+    alpha_pixel_formats: &["yuva420p"],
+    alpha_containers: &["webm", "mkv"],
+    intra: false
+  },
+  EncoderProfile {
+    name: "libsvtav1",
+    containers: AV1_CONTAINERS,
+    pixel_formats: &["yuv420p", "yuv420p10le"],
+    alpha_pixel_formats: &[],
+    alpha_containers: &[],
+    intra: false
+  },
+  EncoderProfile {
+    name: "libaom-av1",
+    containers: AV1_CONTAINERS,
+    pixel_formats: YUV_AND_RGB_FORMATS,
+    alpha_pixel_formats: &[],
+    alpha_containers: &[],
+    intra: false
+  },
+  // ProRes 4444, or 422 HQ for `yuv422p10le`; the 4:4:4 plane layout is what carries alpha.
+  EncoderProfile {
+    name: "prores_ks",
+    containers: &["mov", "mkv"],
+    pixel_formats: &["yuv444p10le", "yuv422p10le"],
+    alpha_pixel_formats: &["yuva444p10le"],
+    alpha_containers: &["mov"],
+    intra: true
+  },
+  // QuickTime Animation.
+  EncoderProfile {
+    name: "qtrle",
+    containers: &["mov"],
+    pixel_formats: &["rgb24"],
+    alpha_pixel_formats: &["argb"],
+    alpha_containers: &["mov"],
+    intra: true
+  },
+  EncoderProfile {
+    name: "png",
+    containers: &["mov", "mkv"],
+    pixel_formats: &["rgb24", "rgb48be"],
+    alpha_pixel_formats: &["rgba", "rgba64be"],
+    alpha_containers: &["mov", "mkv"],
+    intra: true
+  },
+  // Lossless; 4:4:4 so the effect's chroma detail survives, `gbrp` for bit-exact RGB.
+  EncoderProfile {
+    name: "ffv1",
+    containers: &["mkv"],
+    pixel_formats: &["yuv444p10le", "yuv444p", "yuv422p10le", "gbrp", "gbrp16le"],
+    alpha_pixel_formats: &["yuva444p10le", "gbrap", "gbrap16le"],
+    alpha_containers: &["mkv"],
+    intra: true
+  },
+  // UT Video; lossless and fast to decode in editors.
+  EncoderProfile {
+    name: "utvideo",
+    containers: &["avi", "mkv"],
+    pixel_formats: &["gbrp", "yuv444p", "yuv422p", "yuv420p"],
+    alpha_pixel_formats: &["gbrap"],
+    alpha_containers: &["avi", "mkv"],
+    intra: true
+  }
+];
+
+impl EncoderProfile {
+  fn lookup(encoder: &str) -> Option<&'static EncoderProfile> {
+    ENCODERS.iter().find(|profile| profile.name == encoder)
+  }
+
+  fn is_intra(encoder: &str) -> bool {
+    Self::lookup(encoder).is_some_and(|profile| profile.intra)
+  }
+
+  fn formats(&self, alpha: bool) -> &'static [&'static str] {
+    if alpha {
+      self.alpha_pixel_formats
+    } else {
+      self.pixel_formats
+    }
+  }
+}
+
+// Native encoders that can write an alpha channel to at least one container.
+pub fn alpha_capable_encoders() -> Vec<&'static str> {
+  ENCODERS
+    .iter()
+    .filter(|profile| !profile.alpha_containers.is_empty())
+    .map(|profile| profile.name)
+    .collect()
+}
+
+impl NativeEncoding {
+  // Container extension, lowercased and without the dot.
+  pub fn container(&self) -> String {
+    self.format.trim().trim_start_matches('.').to_lowercase()
+  }
+
+  pub fn animated_format(&self) -> Option<AnimatedFormat> {
+    AnimatedFormat::from_container(&self.container())
+  }
+
+  // GIF/WebP and target-size renders write segments to a lossless intermediate that a
+  // final step re-encodes.
+  fn uses_intermediate(&self) -> bool {
+    self.animated_format().is_some() || self.target_size_mb.is_some()
+  }
+
+  // Container for segments and the joined temp video.
+  pub fn segment_container(&self) -> String {
+    if self.uses_intermediate() {
+      "mkv".into()
+    } else {
+      self.container()
+    }
+  }
+
+  // What segments are encoded with. The intermediate is PNG-in-MKV, so palette generation
+  // and the second pass see the exact processed frames; 16-bit renders keep their depth.
+  pub fn segment_encoding(&self) -> Cow<'_, NativeEncoding> {
+    if !self.uses_intermediate() {
+      return Cow::Borrowed(self);
+    }
+    let deep_format = if self.alpha { "rgba64be" } else { "rgb48be" };
+    Cow::Owned(NativeEncoding {
+      encoder: "png".into(),
+      format: "mkv".into(),
+      crf: None,
+      cq: None,
+      max_bitrate_kbps: None,
+      target_bitrate_kbps: None,
+      audio_enabled: false,
+      extra_encode_args: Vec::new(),
+      extra_mux_args: Vec::new(),
+      pixel_format: self.high_bit_depth.then(|| deep_format.into()),
+      animation: None,
+      target_size_mb: None,
+      ..self.clone()
+    })
+  }
+
+  // The same settings at a fixed video bitrate, as a target-size pass encodes them.
+  pub fn with_video_bitrate(&self, video_kbps: u32) -> NativeEncoding {
+    NativeEncoding {
+      crf: None,
+      cq: None,
+      target_bitrate_kbps: Some(video_kbps),
+      target_size_mb: None,
+      ..self.clone()
+    }
+  }
+
+  // Lowercased audio codec; mezzanine encoders default to PCM, everything else to AAC.
+  pub fn audio_codec_name(&self) -> String {
+    match self.audio_codec.as_deref() {
+      Some(codec) => codec.trim().to_lowercase(),
+      None if EncoderProfile::is_intra(&self.encoder) => "pcm".into(),
+      None => "aac".into()
+    }
+  }
+
+  // ffmpeg encoder behind `audio_codec_name`; "copy" passes the source track through.
+  pub fn audio_encoder(&self) -> &'static str {
+    match self.audio_codec_name().as_str() {
+      "opus" => "libopus",
+      "copy" => "copy",
+      "pcm" => "pcm_s24le",
+      _ => "aac"
+    }
+  }
+
+  // Audio bitrate the muxed file will carry; zero without audio.
+  pub fn audio_kbps(&self) -> u32 {
+    if self.audio_enabled {
+      self.audio_bitrate_kbps.unwrap_or(DEFAULT_AUDIO_BITRATE_KBPS)
+    } else {
+      0
+    }
+  }
+
+  pub fn output_pixel_format(&self) -> &str {
+    if let Some(pixel_format) = self.pixel_format.as_deref() {
+      return pixel_format;
+    }
+    EncoderProfile::lookup(&self.encoder)
+      .and_then(|profile| profile.formats(self.alpha).first().copied())
+      .unwrap_or("yuv420p")
+  }
+
+  // Rejects settings the encoder cannot honor before any ffmpeg process starts.
+  pub fn validate(&self) -> Result<(), BitrotError> {
+    if let Some(target_mb) = self.target_size_mb {
+      validate_target_size(self, target_mb)?;
+    }
+    if let Some(format) = self.animated_format() {
+      if self.alpha && format == AnimatedFormat::Gif {
+        return Err(BitrotError::invalid("GIF has no alpha channel; use webp to keep alpha."));
+      }
+      return self.animation.clone().unwrap_or_default().validate(format);
+    }
+    let Some(profile) = EncoderProfile::lookup(&self.encoder) else {
+      let known: Vec<&str> = ENCODERS.iter().map(|profile| profile.name).collect();
+      return Err(BitrotError::invalid(format!(
+        "Unknown encoder \"{}\". Use one of: {}.",
+        self.encoder,
+        known.join(", ")
+      )));
+    };
+    let encoder = profile.name;
+    let container = self.container();
+    if !profile.containers.contains(&container.as_str()) {
+      return Err(BitrotError::invalid(format!(
+        "{encoder} cannot be written to .{container}; use .{}.",
+        profile.containers.join(", .")
+      )));
+    }
+    if let Some(tiles) = self.tiles {
+      if !matches!(encoder, "libsvtav1" | "libaom-av1") {
+        return Err(BitrotError::invalid("Tiles only apply to the AV1 encoders."));
+      }
+      let valid = |count: u32| count.is_power_of_two() && count <= 64;
+      if !valid(tiles.columns) || !valid(tiles.rows) {
+        return Err(BitrotError::invalid("Tile columns and rows must be powers of two up to 64."));
+      }
+    }
+    if self.alpha {
+      if profile.alpha_containers.is_empty() {
+        return Err(BitrotError::invalid(format!(
+          "{encoder} cannot keep alpha; use {} instead.",
+          alpha_capable_encoders().join(", ")
+        )));
+      }
+      if !profile.alpha_containers.contains(&container.as_str()) {
+        return Err(BitrotError::invalid(format!(
+          "{encoder} only keeps alpha in .{}, not .{container}.",
+          profile.alpha_containers.join(" or .")
+        )));
+      }
+    }
+
+    let pcm = self.audio_enabled && self.audio_codec_name() == "pcm";
+    if pcm && !PCM_CONTAINERS.contains(&container.as_str()) {
+      return Err(BitrotError::invalid(format!(
+        "PCM audio needs .{}; use aac or opus in .{container}.",
+        PCM_CONTAINERS.join(", .")
+      )));
+    }
+
+    let pixel_format = self.output_pixel_format();
+    let formats = profile.formats(self.alpha);
+    if !formats.contains(&pixel_format) {
+      let allowed = formats.join(", ");
+      let message = if pixel_format == "rgb24" && formats.contains(&"gbrp") {
+        format!("{encoder} codes RGB as planar gbrp; set pixelFormat to gbrp.")
+      } else if !profile.formats(!self.alpha).contains(&pixel_format) {
+        format!("{encoder} cannot encode {pixel_format}. Use one of: {allowed}.")
+      } else if self.alpha {
+        format!("{pixel_format} has no alpha plane. Use one of: {allowed}.")
+      } else {
+        format!("{pixel_format} carries alpha; enable alpha or use one of: {allowed}.")
+      };
+      return Err(BitrotError::invalid(message));
+    }
+    let planar_rgb = pixel_format.starts_with("gbr");
+    if planar_rgb && !profile.intra && !PLANAR_RGB_CONTAINERS.contains(&container.as_str()) {
+      return Err(BitrotError::invalid(format!(
+        "{pixel_format} from {encoder} needs .mkv; players treat .{container} video as YUV."
+      )));
+    }
+    Ok(())
+  }
+}
+
+// Normalizes paths for comparison without touching the filesystem.
+fn normalize_path_for_compare(value: &str) -> String {
+  value
+    .trim()
+    .trim_matches('"')
+    .replace('\\', "/")
+    .trim_end_matches('/')
+    .to_string()
+}
+
+// Compares two paths with a case-insensitive match on Windows.
+pub fn paths_match(left: &str, right: &str) -> bool {
+  let left = normalize_path_for_compare(left);
+  let right = normalize_path_for_compare(right);
+  if left.is_empty() || right.is_empty() {
+    return false;
+  }
+  #[cfg(windows)]
+  {
+    left.eq_ignore_ascii_case(&right)
+  }
+  #[cfg(not(windows))]
+  {
+    left == right
+  }
+}
+
+pub fn normalize_trim_range(start: Option<f64>, end: Option<f64>) -> Option<(f64, f64)> {
+  let start = start?;
+  let end = end?;
+  if !start.is_finite() || !end.is_finite() {
+    return None;
+  }
+  let safe_start = start.max(0.0);
+  let safe_end = end.max(0.0);
+  if safe_end <= safe_start {
+    return None;
+  }
+  Some((safe_start, safe_end))
+}
+
+pub fn push_trim_args(args: &mut Vec<String>, trim: Option<(f64, f64)>) {
+  if let Some((start, end)) = trim {
+    args.push("-ss".into());
+    args.push(format!("{start:.3}"));
+    args.push("-to".into());
+    args.push(format!("{end:.3}"));
+  }
+}
+
+// Best-effort cleanup for temp artifacts created during native processing.
+pub fn cleanup_file(path: &Path) {
+  for _ in 0..6 {
+    match std::fs::remove_file(path) {
+      Ok(_) => return,
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+      Err(_) => std::thread::sleep(Duration::from_millis(120))
+    }
+  }
+}
+
+// Builds a temp video path next to the output for muxing.
+pub fn build_temp_video_path(output_path: &str, format: &str, tag: &str) -> PathBuf {
+  let output = PathBuf::from(output_path);
+  let stem = output
+    .file_stem()
+    .and_then(|value| value.to_str())
+    .unwrap_or("native");
+  let clean_format = format
+    .trim()
+    .trim_start_matches('.')
+    .to_lowercase();
+  let extension = if clean_format.is_empty() {
+    "mp4".to_string()
+  } else {
+    clean_format
+  };
+  let safe_tag = tag.replace(|c: char| !c.is_ascii_alphanumeric(), "-");
+  let file_name = format!("{stem}.{safe_tag}.video.{extension}");
+  output.with_file_name(file_name)
+}
+
+// `input_options` go before `-i`; image sequences use them for their rate and start number.
+pub fn build_decode_args(
+  input_path: &str,
+  input_options: &[String],
+  width: u32,
+  height: u32,
+  trim: Option<(f64, f64)>,
+  pixel_format: &str
+) -> Vec<String> {
+  raw_decode_args(
+    input_path,
+    input_options,
+    trim,
+    format!("scale={width}:{height},setsar=1"),
+    pixel_format
+  )
+}
+
+// Matte frames as full-range luma, resampled to the input's frame rate so the two
+// streams pair up frame by frame.
+pub fn build_matte_decode_args(
+  matte_path: &str,
+  input_options: &[String],
+  width: u32,
+  height: u32,
+  fps: f64,
+  trim: Option<(f64, f64)>
+) -> Vec<String> {
+  raw_decode_args(
+    matte_path,
+    input_options,
+    trim,
+    format!("scale={width}:{height}:out_range=full,setsar=1,fps={fps}"),
+    "gray"
+  )
+}
+
+fn raw_decode_args(
+  input_path: &str,
+  input_options: &[String],
+  trim: Option<(f64, f64)>,
+  filter: String,
+  pixel_format: &str
+) -> Vec<String> {
+  let mut args: Vec<String> = vec!["-hide_banner".into(), "-loglevel".into(), "error".into()];
+  args.extend(input_options.iter().cloned());
+  args.extend(["-i".into(), input_path.into()]);
+  push_trim_args(&mut args, trim);
+  args.extend([
+    "-map".into(),
+    "0:v:0".into(),
+    "-an".into(),
+    "-vf".into(),
+    filter,
+    "-f".into(),
+    "rawvideo".into(),
+    "-pix_fmt".into(),
+    pixel_format.into(),
+    "-".into()
+  ]);
+  args
+}
+
+pub fn build_encode_args(
+  width: u32,
+  height: u32,
+  fps: f64,
+  encoding: &NativeEncoding,
+  output_path: &Path,
+  pixel_format: &str
+) -> Vec<String> {
+  let mut args = vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-f".into(),
+    "rawvideo".into(),
+    "-pix_fmt".into(),
+    pixel_format.into(),
+    "-s".into(),
+    format!("{width}x{height}"),
+    "-r".into(),
+    format!("{fps:.3}"),
+    "-i".into(),
+    "-".into()
+  ];
+  // Segments are joined and remuxed, so `+faststart` waits for the mux step.
+  push_video_codec_args(&mut args, encoding, None);
+  args.extend(["-pix_fmt".into(), encoding.output_pixel_format().into()]);
+  if !encoding.extra_encode_args.is_empty() {
+    args.extend(encoding.extra_encode_args.iter().cloned());
+  }
+  args.push(output_path.to_string_lossy().into_owned());
+
+  args
+}
+
+// Codec, rate control and VBV flags, shared by segment encodes and target-size passes.
+// `pass` is the two-pass step (1 or 2) and its stats file.
+pub(crate) fn push_video_codec_args(
+  args: &mut Vec<String>,
+  encoding: &NativeEncoding,
+  pass: Option<(u8, &Path)>
+) {
+  if encoding.encoder == "h264_nvenc" {
+    let cq = encoding.cq.unwrap_or(19);
+    args.extend([
+      "-c:v".into(),
+      "h264_nvenc".into(),
+      "-preset".into(),
+      encoding.preset.clone(),
+      "-rc".into(),
+      "vbr".into(),
+      "-cq".into(),
+      cq.to_string(),
+      "-b:v".into(),
+      "0".into()
+    ]);
+  } else if encoding.encoder == "libvpx-vp9" {
+    let deadline = encoding
+      .vp9_deadline
+      .clone()
+      .unwrap_or_else(|| "good".into());
+    let cpu_used = encoding.vp9_cpu_used.unwrap_or(4);
+    args.extend([
+      "-c:v".into(),
+      "libvpx-vp9".into(),
+      "-deadline".into(),
+      deadline,
+      "-cpu-used".into(),
+      cpu_used.to_string(),
+      "-row-mt".into(),
+      "1".into()
+    ]);
+    if let Some(target_bitrate) = encoding.target_bitrate_kbps {
+      args.extend(["-b:v".into(), format!("{target_bitrate}k")]);
+    } else {
+      let crf = encoding.crf.unwrap_or(30);
+      args.extend(["-crf".into(), crf.to_string(), "-b:v".into(), "0".into()]);
+    }
+    if encoding.alpha {
+      // libvpx drops the alpha plane when alt-ref frames are on.
+      args.extend(["-auto-alt-ref".into(), "0".into()]);
+    }
+  } else if encoding.encoder == "prores_ks" {
+    let profile = if encoding.output_pixel_format() == "yuv422p10le" { "hq" } else { "4444" };
+    args.extend([
+      "-c:v".into(),
+      "prores_ks".into(),
+      "-profile:v".into(),
+      profile.into(),
+      "-vendor".into(),
+      "apl0".into()
+    ]);
+  } else if encoding.encoder == "ffv1" {
+    // Level 3 with per-slice CRCs is the usual archival setup; every frame is a keyframe.
+    args.extend([
+      "-c:v".into(),
+      "ffv1".into(),
+      "-level".into(),
+      "3".into(),
+      "-g".into(),
+      "1".into(),
+      "-slicecrc".into(),
+      "1".into()
+    ]);
+  } else if matches!(encoding.encoder.as_str(), "qtrle" | "png" | "utvideo") {
+    // Lossless codecs; rate control does not apply.
+    args.extend(["-c:v".into(), encoding.encoder.clone()]);
+  } else if encoding.encoder == "libx265" {
+    args.extend([
+      "-c:v".into(),
+      "libx265".into(),
+      "-preset".into(),
+      encoding.preset.clone()
+    ]);
+    if let Some(target_bitrate) = encoding.target_bitrate_kbps {
+      args.extend(["-b:v".into(), format!("{target_bitrate}k")]);
+    } else {
+      args.extend(["-crf".into(), encoding.crf.unwrap_or(28).to_string()]);
+    }
+    // x265 logs every segment's stats to stderr otherwise.
+    let mut params = String::from("log-level=error");
+    if let Some((number, stats)) = pass {
+      // x265 takes its pass through its own params; quoting keeps drive-letter colons intact.
+      params.push_str(&format!(":pass={number}:stats='{}'", stats.to_string_lossy()));
+    }
+    args.extend(["-x265-params".into(), params]);
+  } else if encoding.encoder == "libsvtav1" {
+    let preset = encoding.av1_speed.unwrap_or(8);
+    args.extend(["-c:v".into(), "libsvtav1".into(), "-preset".into(), preset.to_string()]);
+    if let Some(target_bitrate) = encoding.target_bitrate_kbps {
+      args.extend(["-b:v".into(), format!("{target_bitrate}k")]);
+    } else {
+      args.extend(["-crf".into(), encoding.crf.unwrap_or(35).to_string()]);
+    }
+    if let Some(tiles) = encoding.tiles {
+      // SVT-AV1 takes tile counts as log2.
+      args.extend([
+        "-svtav1-params".into(),
+        format!(
+          "tile-columns={}:tile-rows={}",
+          tiles.columns.trailing_zeros(),
+          tiles.rows.trailing_zeros()
+        )
+      ]);
+    }
+  } else if encoding.encoder == "libaom-av1" {
+    let cpu_used = encoding.av1_speed.unwrap_or(4);
+    args.extend([
+      "-c:v".into(),
+      "libaom-av1".into(),
+      "-cpu-used".into(),
+      cpu_used.to_string(),
+      "-row-mt".into(),
+      "1".into()
+    ]);
+    if let Some(target_bitrate) = encoding.target_bitrate_kbps {
+      args.extend(["-b:v".into(), format!("{target_bitrate}k")]);
+    } else {
+      let crf = encoding.crf.unwrap_or(30);
+      args.extend(["-crf".into(), crf.to_string(), "-b:v".into(), "0".into()]);
+    }
+    if let Some(tiles) = encoding.tiles {
+      args.extend(["-tiles".into(), format!("{}x{}", tiles.columns, tiles.rows)]);
+    }
+  } else if encoding.encoder == "libx264" {
+    args.extend([
+      "-c:v".into(),
+      "libx264".into(),
+      "-preset".into(),
+      encoding.preset.clone()
+    ]);
+    if let Some(target_bitrate) = encoding.target_bitrate_kbps {
+      args.extend(["-b:v".into(), format!("{target_bitrate}k")]);
+    } else {
+      args.extend(["-crf".into(), encoding.crf.unwrap_or(20).to_string()]);
+    }
+  } else {
+    // `validate` rejects unknown encoders; never swap in a different one here.
+    args.extend(["-c:v".into(), encoding.encoder.clone()]);
+  }
+
+  if let Some(max_bitrate) = encoding.max_bitrate_kbps {
+    if matches!(encoding.encoder.as_str(), "libvpx-vp9" | "libsvtav1" | "libaom-av1") {
+      // VP9 and AV1 use either a target bitrate or CRF. Adding VBV caps causes encoder errors.
+    } else if EncoderProfile::is_intra(&encoding.encoder) {
+      // Intra-only codecs have no VBV.
+    } else {
+      // Apply a VBV cap to avoid runaway file sizes on high-variance frames.
+      let maxrate = max_bitrate.max(1200);
+      let bufsize = maxrate.saturating_mul(2);
+      args.extend([
+        "-maxrate".into(),
+        format!("{maxrate}k"),
+        "-bufsize".into(),
+        format!("{bufsize}k")
+      ]);
+    }
+  }
+
+  if let Some((number, stats)) = pass.filter(|_| encoding.encoder != "libx265") {
+    args.extend([
+      "-pass".into(),
+      number.to_string(),
+      "-passlogfile".into(),
+      stats.to_string_lossy().into_owned()
+    ]);
+  }
+}
+
+pub fn build_mux_args(
+  temp_video: &Path,
+  input_path: &str,
+  output_path: &str,
+  trim: Option<(f64, f64)>,
+  encoding: &NativeEncoding,
+  metadata: &[(String, String)]
+) -> Vec<String> {
+  let mut args = vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-i".into(),
+    temp_video.to_string_lossy().into_owned()
+  ];
+  push_trim_args(&mut args, trim);
+
+  if encoding.audio_enabled {
+    args.extend([
+      "-i".into(),
+      input_path.into(),
+      "-map".into(),
+      "0:v:0".into(),
+      "-map".into(),
+      "1:a?".into()
+    ]);
+  } else {
+    args.extend(["-map".into(), "0:v:0".into(), "-an".into()]);
+  }
+
+  args.extend(["-c:v".into(), "copy".into()]);
+  let format = encoding.container();
+  if encoding.encoder == "libx265" && matches!(format.as_str(), "mp4" | "m4v" | "mov") {
+    // Apple players only open HEVC tagged `hvc1`; ffmpeg writes `hev1` by default.
+    args.extend(["-tag:v".into(), "hvc1".into()]);
+  }
+  if encoding.audio_enabled {
+    let codec = encoding.audio_codec_name();
+    args.extend(["-c:a".into(), encoding.audio_encoder().into()]);
+    if codec != "pcm" {
+      let bitrate = encoding.audio_bitrate_kbps.unwrap_or(DEFAULT_AUDIO_BITRATE_KBPS);
+      args.extend(["-b:a".into(), format!("{bitrate}k")]);
+    }
+    args.push("-shortest".into());
+  }
+  for (key, value) in metadata {
+    args.extend(["-metadata".into(), format!("{key}={value}")]);
+  }
+
+  // Moving the index up front helps streaming players; mezzanine files skip the rewrite.
+  let streams = matches!(format.as_str(), "mp4" | "m4v" | "mov");
+  if streams && !EncoderProfile::is_intra(&encoding.encoder) {
+    args.extend(["-movflags".into(), "+faststart".into()]);
+  }
+
+  if !encoding.extra_mux_args.is_empty() {
+    args.extend(encoding.extra_mux_args.iter().cloned());
+  }
+  args.push(output_path.into());
+  args
+}
+
+// Joins finished segments without re-encoding; the list comes from the checkpoint.
+pub fn build_concat_args(list_path: &Path, output_path: &Path) -> Vec<String> {
+  vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-f".into(),
+    "concat".into(),
+    "-safe".into(),
+    "0".into(),
+    "-i".into(),
+    list_path.to_string_lossy().into_owned(),
+    "-map".into(),
+    "0:v:0".into(),
+    "-c".into(),
+    "copy".into(),
+    output_path.to_string_lossy().into_owned()
+  ]
+}
+
+// Builds a fresh ffmpeg command; embedders decide how ffmpeg is resolved and spawned.
+pub type FfmpegLauncher<'a> = &'a (dyn Fn() -> Result<Command, BitrotError> + Sync);
+
+// "<step> failed: <last stderr line>", with the stderr tail attached for details.
+fn ffmpeg_failure(code: ErrorCode, step: &str, exit_code: Option<i32>, stderr: &str) -> BitrotError {
+  let message = match stderr.lines().map(str::trim).rfind(|line| !line.is_empty()) {
+    Some(line) => format!("{step} failed: {line}"),
+    None => format!("{step} failed with exit code {}", exit_code.unwrap_or(-1))
+  };
+  BitrotError::new(code, message).with_stderr(stderr)
+}
+
+// Runs a one-shot ffmpeg step; failures carry `code` and the step's stderr tail.
+pub fn run_ffmpeg_output(
+  ffmpeg: FfmpegLauncher,
+  args: Vec<String>,
+  code: ErrorCode,
+  step: &str
+) -> Result<(), BitrotError> {
+  let output = ffmpeg()?
+    .args(args)
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start {step}: {error}"))
+    })?;
+  if !output.status.success() {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    return Err(ffmpeg_failure(code, step, output.status.code(), &stderr));
+  }
+  Ok(())
+}
+
+// Inputs shared by every native effect job; mode commands and the CLI forward their args here.
+pub struct NativeJobRequest {
+  pub job_id: String,
+  pub input_path: String,
+  pub output_path: String,
+  pub width: u32,
+  pub height: u32,
+  pub fps: f64,
+  pub duration_seconds: Option<f64>,
+  pub trim_start_seconds: Option<f64>,
+  pub trim_end_seconds: Option<f64>,
+  pub preview_enabled: bool,
+  pub encoding: NativeEncoding,
+  pub matte: Option<Matte>
+}
+
+// Files a job may leave behind; removed on failure so partial outputs never linger.
+struct JobArtifacts {
+  temp_video: PathBuf,
+  // Pass 2's encode of the intermediate, for target-size renders.
+  second_pass: Option<PathBuf>,
+  output: PathBuf,
+  sequence: Option<OutputSequence>
+}
+
+impl JobArtifacts {
+  fn remove_temp(&self) {
+    cleanup_file(&self.temp_video);
+    if let Some(path) = &self.second_pass {
+      cleanup_file(path);
+    }
+  }
+
+  // Sequence frames from finished segments stay for a resume; they are the checkpoint's output.
+  fn discard(&self, kept_frames: u64) {
+    self.remove_temp();
+    match &self.sequence {
+      Some(sequence) => remove_frames_from(&sequence.pattern, sequence.first_number + kept_frames),
+      None => cleanup_file(&self.output)
+    }
+  }
+}
+
+// Fills `frame` from the decoder pipe; returns false once the stream ends.
+// A trailing partial frame is dropped, matching how ffmpeg pads nothing on EOF.
+fn read_frame(reader: &mut impl Read, frame: &mut [u8]) -> Result<bool, BitrotError> {
+  let mut filled = 0;
+  while filled < frame.len() {
+    match reader.read(&mut frame[filled..]) {
+      Ok(0) => return Ok(false),
+      Ok(read) => filled += read,
+      Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+      Err(error) => {
+        return Err(BitrotError::new(
+          ErrorCode::DecodeFailed,
+          format!("Failed to read frame: {error}")
+        ))
+      }
+    }
+  }
+  Ok(true)
+}
+
+// Fills a frame of samples; deeper samples go through `bytes` as little-endian pairs.
+fn read_samples<S: Sample>(
+  reader: &mut impl Read,
+  frame: &mut [S],
+  bytes: &mut Vec<u8>
+) -> Result<bool, BitrotError> {
+  if let Some(raw) = S::bytes_mut(frame) {
+    return read_frame(reader, raw);
+  }
+  bytes.resize(std::mem::size_of_val(frame), 0);
+  if !read_frame(reader, bytes)? {
+    return Ok(false);
+  }
+  S::read_le(bytes, frame);
+  Ok(true)
+}
+
+// Forwards ffmpeg stderr lines to the reporter and keeps them for error messages.
+fn drain_stderr(stderr: ChildStderr, log_label: &str, reporter: &dyn JobReporter) -> Vec<String> {
+  let mut lines = Vec::new();
+  for line in BufReader::new(stderr).lines() {
+    let Ok(line) = line else {
+      break;
+    };
+    let message = line.trim().to_string();
+    if !message.is_empty() {
+      reporter.log_at(LogLevel::Warn, &format!("{log_label}: {message}"));
+      lines.push(message);
+    }
+  }
+  lines
+}
+
+fn stop_child(child: &mut Child) {
+  let _ = child.kill();
+  let _ = child.wait();
+}
+
+// Stops a helper process on every exit path; a finished child is only reaped again.
+struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+  fn drop(&mut self) {
+    stop_child(&mut self.0);
+  }
+}
+
+// Starts a raw-video decoder with stdout and stderr piped back.
+fn spawn_decoder(
+  ffmpeg: FfmpegLauncher,
+  args: Vec<String>,
+  label: &str
+) -> Result<(Child, ChildStdout, ChildStderr), BitrotError> {
+  let mut child = ffmpeg()?
+    .args(args)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to spawn {label}: {error}"))
+    })?;
+  let (Some(stdout), Some(stderr)) = (child.stdout.take(), child.stderr.take()) else {
+    stop_child(&mut child);
+    return Err(BitrotError::internal("Failed to open ffmpeg pipes."));
+  };
+  Ok((child, stdout, stderr))
+}
+
+// Starts an encoder reading raw frames on stdin; stderr is collected on a plain thread.
+fn spawn_encoder(
+  ffmpeg: FfmpegLauncher,
+  args: Vec<String>,
+  label: &str
+) -> Result<(Child, ChildStdin, JoinHandle<Vec<String>>), BitrotError> {
+  let mut child = ffmpeg()?
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to spawn {label}: {error}"))
+    })?;
+  let (Some(stdin), Some(stderr)) = (child.stdin.take(), child.stderr.take()) else {
+    stop_child(&mut child);
+    return Err(BitrotError::internal("Failed to open ffmpeg pipes."));
+  };
+  let log = std::thread::spawn(move || {
+    BufReader::new(stderr)
+      .lines()
+      .map_while(Result::ok)
+      .map(|line| line.trim().to_string())
+      .filter(|line| !line.is_empty())
+      .collect()
+  });
+  Ok((child, stdin, log))
+}
+
+// Waits for an encoder whose stdin is already closed and logs what it wrote to stderr.
+fn wait_encoder(
+  child: &mut Child,
+  log: JoinHandle<Vec<String>>,
+  log_label: &str,
+  step: &str,
+  reporter: &dyn JobReporter
+) -> Result<(), BitrotError> {
+  let status = child
+    .wait()
+    .map_err(|error| BitrotError::new(ErrorCode::EncodeFailed, format!("{log_label} failed: {error}")))?;
+  let lines = log.join().unwrap_or_default();
+  for line in &lines {
+    reporter.log_at(LogLevel::Warn, &format!("{log_label}: {line}"));
+  }
+  if !status.success() {
+    return Err(
+      BitrotError::new(
+        ErrorCode::EncodeFailed,
+        format!("{step} failed with exit code {}", status.code().unwrap_or(-1))
+      )
+      .with_stderr(&lines.join("\n"))
+    );
+  }
+  Ok(())
+}
+
+// Pass 1 of a target-size render, fed the same frames as the segments.
+struct FirstPass {
+  child: ChildGuard,
+  stdin: ChildStdin,
+  log: JoinHandle<Vec<String>>
+}
+
+impl FirstPass {
+  fn spawn(ffmpeg: FfmpegLauncher, args: Vec<String>) -> Result<Self, BitrotError> {
+    let (child, stdin, log) = spawn_encoder(ffmpeg, args, "first pass")?;
+    Ok(Self {
+      child: ChildGuard(child),
+      stdin,
+      log
+    })
+  }
+
+  fn finish(self, reporter: &dyn JobReporter) -> Result<(), BitrotError> {
+    let FirstPass { mut child, stdin, log } = self;
+    drop(stdin);
+    wait_encoder(&mut child.0, log, "first pass", "First pass", reporter)
+  }
+}
+
+// One running segment encoder; stderr is collected on a plain thread and logged on close.
+struct ActiveSegment {
+  index: u64,
+  child: Child,
+  stdin: ChildStdin,
+  remaining_bytes: u64,
+  log: JoinHandle<Vec<String>>
+}
+
+// Encoder input that starts a fresh encoder every `segment_frames` frames. A segment is
+// written to the journal as soon as its encoder exits cleanly, so a resume never redoes it.
+struct SegmentWriter<'a> {
+  ffmpeg: FfmpegLauncher<'a>,
+  geometry: (u32, u32, f64),
+  // Raw input format on the encoder's stdin (`rgba` or `rgba64le`).
+  pixel_format: &'static str,
+  encoding: &'a NativeEncoding,
+  // Frames go straight into this pattern instead of checkpoint segments.
+  sequence: Option<&'a OutputSequence>,
+  checkpoint: &'a mut Checkpoint,
+  segment_bytes: u64,
+  segment_count: u64,
+  active: Option<ActiveSegment>,
+  first_pass: Option<FirstPass>,
+  reporter: &'a dyn JobReporter
+}
+
+impl<'a> SegmentWriter<'a> {
+  fn new<S: Sample>(
+    ffmpeg: FfmpegLauncher<'a>,
+    geometry: (u32, u32, f64),
+    encoding: &'a NativeEncoding,
+    sequence: Option<&'a OutputSequence>,
+    checkpoint: &'a mut Checkpoint,
+    first_pass: Option<FirstPass>,
+    reporter: &'a dyn JobReporter
+  ) -> Self {
+    let (width, height, _) = geometry;
+    let frame_bytes = (width as u64) * (height as u64) * 4 * std::mem::size_of::<S>() as u64;
+    let segment_bytes = checkpoint.journal.segment_frames * frame_bytes;
+    let segment_count = checkpoint.journal.completed_segments;
+    Self {
+      ffmpeg,
+      geometry,
+      pixel_format: S::PIX_FMT,
+      encoding,
+      sequence,
+      checkpoint,
+      segment_bytes,
+      segment_count,
+      active: None,
+      first_pass,
+      reporter
+    }
+  }
+
+  fn open_segment(&mut self) -> Result<(), BitrotError> {
+    let index = self.segment_count;
+    let (width, height, fps) = self.geometry;
+    let args = match self.sequence {
+      Some(sequence) => {
+        let start_number = sequence.first_number + index * self.checkpoint.journal.segment_frames;
+        build_sequence_encode_args(
+          width,
+          height,
+          fps,
+          sequence,
+          start_number,
+          self.encoding,
+          self.pixel_format
+        )
+      }
+      None => {
+        let path = self.checkpoint.segment_path(index);
+        build_encode_args(width, height, fps, self.encoding, &path, self.pixel_format)
+      }
+    };
+    let (child, stdin, log) = spawn_encoder(self.ffmpeg, args, "encoder")?;
+    self.active = Some(ActiveSegment {
+      index,
+      child,
+      stdin,
+      remaining_bytes: self.segment_bytes,
+      log
+    });
+    self.segment_count += 1;
+    Ok(())
+  }
+
+  // Closes the encoder's stdin and waits for it; only full segments go into the journal.
+  fn close_segment(&mut self) -> Result<(), BitrotError> {
+    let Some(segment) = self.active.take() else {
+      return Ok(());
+    };
+    let ActiveSegment {
+      index,
+      mut child,
+      stdin,
+      remaining_bytes,
+      log
+    } = segment;
+    drop(stdin);
+    wait_encoder(&mut child, log, "encode", "Encoder", self.reporter)?;
+    if remaining_bytes == 0 {
+      self.checkpoint.complete_segment(index)?;
+    }
+    Ok(())
+  }
+
+  // Flushes the last (possibly short) segment and returns how many segments exist.
+  fn finish(mut self) -> Result<u64, BitrotError> {
+    self.close_segment()?;
+    if let Some(first_pass) = self.first_pass.take() {
+      first_pass.finish(self.reporter)?;
+    }
+    Ok(self.segment_count)
+  }
+}
+
+impl Write for SegmentWriter<'_> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    if buf.is_empty() {
+      return Ok(0);
+    }
+    if self.active.is_none() {
+      self.open_segment().map_err(std::io::Error::other)?;
+    }
+    let Some(segment) = self.active.as_mut() else {
+      return Ok(0);
+    };
+    let take = segment.remaining_bytes.min(buf.len() as u64) as usize;
+    let written = segment.stdin.write(&buf[..take])?;
+    segment.remaining_bytes -= written as u64;
+    let done = segment.remaining_bytes == 0;
+    if let Some(first_pass) = self.first_pass.as_mut() {
+      if let Err(error) = first_pass.stdin.write_all(&buf[..written]) {
+        // A dead pass 1 breaks the pipe; its exit status and stderr say why.
+        let failure = match self.first_pass.take().map(|pass| pass.finish(self.reporter)) {
+          Some(Err(failure)) => failure,
+          _ => BitrotError::new(ErrorCode::EncodeFailed, format!("First pass failed: {error}"))
+        };
+        return Err(std::io::Error::other(failure));
+      }
+    }
+    if done {
+      self.close_segment().map_err(std::io::Error::other)?;
+    }
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    match self.active.as_mut() {
+      Some(segment) => segment.stdin.flush(),
+      None => Ok(())
+    }
+  }
+}
+
+// An unfinished segment is killed rather than flushed; failed renders discard it anyway.
+impl Drop for SegmentWriter<'_> {
+  fn drop(&mut self) {
+    if let Some(mut segment) = self.active.take() {
+      drop(segment.stdin);
+      stop_child(&mut segment.child);
+    }
+  }
+}
+
+// Frames buffered between two stages; memory stays bounded at a few frames per queue.
+const FRAME_QUEUE_DEPTH: usize = 4;
+
+// Nanoseconds each stage spent blocked, shared between the stage threads.
+#[derive(Default)]
+struct StageTimers {
+  decode: AtomicU64,
+  process_input: AtomicU64,
+  process_output: AtomicU64,
+  encode: AtomicU64
+}
+
+impl StageTimers {
+  fn add(counter: &AtomicU64, since: Instant) {
+    counter.fetch_add(since.elapsed().as_nanos() as u64, Ordering::Relaxed);
+  }
+
+  fn waits(&self) -> StageWaits {
+    let seconds = |counter: &AtomicU64| counter.load(Ordering::Relaxed) as f64 / 1e9;
+    StageWaits {
+      decode_seconds: seconds(&self.decode),
+      process_input_seconds: seconds(&self.process_input),
+      process_output_seconds: seconds(&self.process_output),
+      encode_seconds: seconds(&self.encode)
+    }
+  }
+}
+
+// Reads raw frames into recycled buffers and queues them for the effect.
+// Stops quietly when the effect stage hangs up; it reports its own error.
+fn run_decode_stage<S: Sample>(
+  mut decode_out: impl Read,
+  frame_size: usize,
+  frames: SyncSender<Vec<S>>,
+  recycled: Receiver<Vec<S>>,
+  timers: &StageTimers
+) -> Result<(), BitrotError> {
+  let mut bytes = Vec::new();
+  loop {
+    let mut frame = recycled
+      .try_recv()
+      .unwrap_or_else(|_| vec![S::default(); frame_size]);
+    if !read_samples(&mut decode_out, &mut frame, &mut bytes)? {
+      return Ok(());
+    }
+    let waited = Instant::now();
+    if frames.send(frame).is_err() {
+      return Ok(());
+    }
+    StageTimers::add(&timers.decode, waited);
+  }
+}
+
+// Writes processed frames to the encoder and hands it back once the queue closes.
+fn run_encode_stage<W: Write, S: Sample>(
+  mut encode_in: W,
+  frames: Receiver<Vec<S>>,
+  recycled: Sender<Vec<S>>,
+  timers: &StageTimers
+) -> Result<W, BitrotError> {
+  let mut bytes = Vec::new();
+  loop {
+    let waited = Instant::now();
+    let Ok(frame) = frames.recv() else {
+      return Ok(encode_in);
+    };
+    StageTimers::add(&timers.encode, waited);
+    let raw = match S::bytes(&frame) {
+      Some(raw) => raw,
+      None => {
+        S::write_le(&frame, &mut bytes);
+        &bytes
+      }
+    };
+    encode_in
+      .write_all(raw)
+      .map_err(|error| {
+        // Segment encoder failures travel through io::Error; keep their code and stderr.
+        match error.get_ref().and_then(|inner| inner.downcast_ref::<BitrotError>()) {
+          Some(inner) => inner.clone(),
+          None => BitrotError::new(ErrorCode::EncodeFailed, format!("Failed to write frame: {error}"))
+        }
+      })?;
+    let _ = recycled.send(frame);
+  }
+}
+
+// Matte frames decoded in lockstep with the input, one per input frame. The matte stays
+// 8-bit; `S` is the sample type of the frames it gates.
+struct MatteFeed<'a, S> {
+  matte: &'a Matte,
+  frames: Receiver<Vec<u8>>,
+  recycled: Sender<Vec<u8>>,
+  pixels: usize,
+  current: Option<Vec<u8>>,
+  ended: bool,
+  output: Vec<S>
+}
+
+impl<'a, S: Sample> MatteFeed<'a, S> {
+  fn new(
+    matte: &'a Matte,
+    frames: Receiver<Vec<u8>>,
+    recycled: Sender<Vec<u8>>,
+    pixels: usize
+  ) -> Self {
+    Self {
+      matte,
+      frames,
+      recycled,
+      pixels,
+      current: None,
+      ended: false,
+      output: Vec::new()
+    }
+  }
+
+  // Moves on to the matte frame for the next input frame; true when the matte just ran out.
+  fn advance(&mut self) -> bool {
+    if self.ended {
+      return false;
+    }
+    if let Ok(frame) = self.frames.recv() {
+      if let Some(previous) = self.current.replace(frame) {
+        let _ = self.recycled.send(previous);
+      }
+      return false;
+    }
+    self.ended = true;
+    let show_effect = match self.matte.after_end {
+      MatteEnd::Hold if self.current.is_some() => return true,
+      MatteEnd::Hold | MatteEnd::Source => false,
+      MatteEnd::Effect => true
+    };
+    // Fixed fills go through the same blend, so they are stored pre-inverted.
+    let value = if show_effect != self.matte.invert { 255 } else { 0 };
+    self.current = Some(vec![value; self.pixels]);
+    true
+  }
+
+  fn apply<'b>(&'b mut self, source: &[S], processed: &'b [S], width: usize) -> &'b [S] {
+    let Some(matte) = self.current.as_deref() else {
+      return processed;
+    };
+    apply_matte(source, processed, matte, self.matte.invert, width, &mut self.output);
+    &self.output
+  }
+}
+
+// Once the input ran out, cuts off a longer matte or checks that a shorter one ended cleanly.
+fn finish_matte<S: Sample>(
+  child: &mut Child,
+  feed: MatteFeed<S>,
+  stage: ScopedJoinHandle<Result<(), BitrotError>>,
+  log: ScopedJoinHandle<Vec<String>>
+) -> Result<(), BitrotError> {
+  let ended = feed.ended;
+  if !ended {
+    stop_child(child);
+  }
+  drop(feed);
+  let stage_result = stage
+    .join()
+    .unwrap_or_else(|_| Err(BitrotError::internal("Matte decode stage panicked.")));
+  let status = child
+    .wait()
+    .map_err(|error| BitrotError::internal(format!("matte decode wait failed: {error}")))?;
+  let errors = log.join().unwrap_or_default();
+  if !ended {
+    return Ok(());
+  }
+  stage_result?;
+  if !status.success() {
+    return Err(ffmpeg_failure(
+      ErrorCode::DecodeFailed,
+      "Matte decoder",
+      status.code(),
+      &errors.join("\n")
+    ));
+  }
+  Ok(())
+}
+
+// Runs the effect on the calling thread between the two queues; returns the frame count.
+// Frames before `first_frame` are already in finished segments: they are dropped unseen,
+// except for the effect's warmup frames, which rebuild its state but are not encoded.
+#[allow(clippy::too_many_arguments)]
+fn run_process_stage<E: NativeEffect, S: Sample>(
+  effect: &E,
+  (width, height): (u32, u32),
+  first_frame: u64,
+  decoded: Receiver<Vec<S>>,
+  decoded_free: Sender<Vec<S>>,
+  encoded: SyncSender<Vec<S>>,
+  encoded_free: Receiver<Vec<S>>,
+  mut matte: Option<&mut MatteFeed<S>>,
+  clock: &ProgressClock,
+  timers: &StageTimers,
+  cancel_flag: &AtomicBool,
+  reporter: &dyn JobReporter
+) -> Result<u64, BitrotError> {
+  let mut workspace = effect.workspace(width as usize, height as usize);
+  let mut preview = Vec::new();
+  let warmup_start = first_frame - effect.warmup_frames().min(first_frame);
+  let mut processed_frames = 0u64;
+  let mut last_progress = Instant::now();
+
+  loop {
+    if cancel_flag.load(Ordering::Relaxed) {
+      reporter.log(&format!("{} canceled.", E::LABEL));
+      return Err(BitrotError::canceled());
+    }
+    let waited = Instant::now();
+    // Poll so cancel stays responsive while the decoder is slow.
+    let frame = match decoded.recv_timeout(Duration::from_millis(100)) {
+      Ok(frame) => frame,
+      Err(RecvTimeoutError::Timeout) => {
+        StageTimers::add(&timers.process_input, waited);
+        continue;
+      }
+      Err(RecvTimeoutError::Disconnected) => break
+    };
+    StageTimers::add(&timers.process_input, waited);
+
+    if let Some(matte) = matte.as_deref_mut() {
+      if matte.advance() {
+        let fallback = match matte.matte.after_end {
+          MatteEnd::Hold => "holding its last frame",
+          MatteEnd::Source => "showing the source",
+          MatteEnd::Effect => "showing the full effect"
+        };
+        reporter.log(&format!("Matte ended at frame {processed_frames}; {fallback}."));
+      }
+    }
+    if processed_frames < warmup_start {
+      processed_frames += 1;
+      let _ = decoded_free.send(frame);
+      continue;
+    }
+    let processed = effect.process_frame(&frame, &mut workspace, processed_frames);
+    if let Some(warning) = effect.take_warning() {
+      reporter.log_at(LogLevel::Warn, &warning);
+    }
+    let processed = match matte.as_deref_mut() {
+      Some(matte) => matte.apply(&frame, processed, width as usize),
+      None => processed
+    };
+    if processed_frames < first_frame {
+      processed_frames += 1;
+      let _ = decoded_free.send(frame);
+      continue;
+    }
+    let mut output = encoded_free.try_recv().unwrap_or_default();
+    output.clear();
+    output.extend_from_slice(processed);
+    processed_frames += 1;
+    if reporter.wants_frame(processed_frames) {
+      reporter.frame(processed_frames, to_rgba8(processed, &mut preview), width, height);
+    }
+    let _ = decoded_free.send(frame);
+
+    let waited = Instant::now();
+    // A closed queue means the encode stage failed; its error is picked up on join.
+    if encoded.send(output).is_err() {
+      break;
+    }
+    StageTimers::add(&timers.process_output, waited);
+
+    if last_progress.elapsed() > Duration::from_millis(200) {
+      reporter.progress(&clock.snapshot(processed_frames).with_stage_waits(timers.waits()));
+      last_progress = Instant::now();
+    }
+  }
+  Ok(processed_frames)
+}
+
+// Runs one native effect job end to end; blocking, so async callers should use a worker thread.
+// Frames are encoded into the checkpoint's segments, starting after its last finished one.
+pub fn run_native_job<E: NativeEffect>(
+  ffmpeg: FfmpegLauncher,
+  request: &NativeJobRequest,
+  effect: &E,
+  checkpoint: &mut Checkpoint,
+  cancel_flag: &AtomicBool,
+  reporter: &dyn JobReporter
+) -> Result<(), BitrotError> {
+  if paths_match(&request.input_path, &request.output_path) {
+    return Err(BitrotError::new(
+      ErrorCode::OutputConflict,
+      "Output path matches the input file. Choose a different output name."
+    ));
+  }
+  let sequence = match OutputSequence::for_output(&request.output_path, &request.encoding)? {
+    Some(sequence) => {
+      let input_start = if is_sequence_pattern(&request.input_path) {
+        find_start_number(&request.input_path)?
+      } else {
+        0
+      };
+      let trim_start = normalize_trim_range(request.trim_start_seconds, request.trim_end_seconds)
+        .map_or(0.0, |(start, _)| start);
+      Some(sequence.starting_at(input_start, trim_start, request.fps))
+    }
+    None => {
+      request.encoding.validate()?;
+      None
+    }
+  };
+  toolchain_report(ffmpeg)?.check_encoding(&request.encoding, sequence.as_ref())?;
+  reporter.log(&format!("{} started.", E::LABEL));
+
+  let artifacts = JobArtifacts {
+    temp_video: build_temp_video_path(
+      &request.output_path,
+      &request.encoding.segment_container(),
+      E::TAG
+    ),
+    second_pass: request.encoding.target_size_mb.map(|_| {
+      build_temp_video_path(
+        &request.output_path,
+        &request.encoding.container(),
+        &format!("{}-pass2", E::TAG)
+      )
+    }),
+    output: PathBuf::from(&request.output_path),
+    sequence
+  };
+  let result = if request.encoding.high_bit_depth {
+    reporter.log("Processing at 16 bits per channel.");
+    drive_native_job::<E, u16>(ffmpeg, request, effect, checkpoint, cancel_flag, reporter, &artifacts)
+  } else {
+    drive_native_job::<E, u8>(ffmpeg, request, effect, checkpoint, cancel_flag, reporter, &artifacts)
+  };
+  match &result {
+    Ok(()) => {
+      artifacts.remove_temp();
+      record_render(ffmpeg, &checkpoint.journal.spec, reporter);
+      checkpoint.remove();
+      reporter.log(&format!("{} completed.", E::LABEL));
+    }
+    Err(_) => {
+      checkpoint.discard_incomplete();
+      artifacts.discard(checkpoint.completed_frames());
+      let completed = checkpoint.journal.completed_segments;
+      if completed == 0 {
+        checkpoint.remove();
+      } else {
+        reporter.log(&format!(
+          "Checkpoint kept with {completed} finished segment(s) at {}; resume to continue.",
+          checkpoint.dir().display()
+        ));
+      }
+    }
+  }
+  result
+}
+
+fn drive_native_job<E: NativeEffect, S: Sample>(
+  ffmpeg: FfmpegLauncher,
+  request: &NativeJobRequest,
+  effect: &E,
+  checkpoint: &mut Checkpoint,
+  cancel_flag: &AtomicBool,
+  reporter: &dyn JobReporter,
+  artifacts: &JobArtifacts
+) -> Result<(), BitrotError> {
+  let NativeJobRequest {
+    input_path,
+    output_path,
+    width,
+    height,
+    fps,
+    duration_seconds,
+    trim_start_seconds,
+    trim_end_seconds,
+    encoding,
+    ..
+  } = request;
+  let (width, height, fps) = (*width, *height, *fps);
+  if width < 2 || height < 2 {
+    return Err(BitrotError::invalid(format!(
+      "Invalid video dimensions for {}.",
+      E::LABEL.to_lowercase()
+    )));
+  }
+
+  let safe_width = if width % 2 == 0 { width } else { width - 1 };
+  let safe_height = if height % 2 == 0 { height } else { height - 1 };
+  if safe_width != width || safe_height != height {
+    reporter.log(&format!(
+      "Adjusted dimensions to even size: {safe_width}x{safe_height}."
+    ));
+  }
+
+  let safe_fps = if fps > 0.0 { fps } else { 30.0 };
+  if (safe_fps - fps).abs() > f64::EPSILON {
+    reporter.log(&format!("Fallback FPS applied: {safe_fps:.3}."));
+  }
+
+  // In samples; a 16-bit frame is twice as many bytes on the pipes.
+  let frame_size = (safe_width as usize) * (safe_height as usize) * 4;
+  let trim_range = normalize_trim_range(*trim_start_seconds, *trim_end_seconds);
+  let duration_for_progress = trim_range
+    .map(|(start, end)| (end - start).max(0.0))
+    .or(*duration_seconds);
+  let total_frames = duration_for_progress
+    .filter(|duration| *duration > 0.0 && safe_fps > 0.0)
+    .map(|duration| (duration * safe_fps).ceil() as u64);
+
+  // Target-size renders budget their bitrate up front, so an impossible target fails fast.
+  let rated_encoding = match encoding.target_size_mb {
+    Some(target_mb) => {
+      let duration = encoded_duration(trim_range, *duration_seconds);
+      let video_kbps = video_bitrate_kbps(target_mb, duration, encoding.audio_kbps())?;
+      reporter.log(&format!(
+        "Two-pass encode at {video_kbps} kbps video for a {target_mb:.2} MB target."
+      ));
+      Some(encoding.with_video_bitrate(video_kbps))
+    }
+    None => None
+  };
+  let pass_log = checkpoint.dir().join(PASS_LOG_NAME);
+
+  // Resumes decode from the start again and skip ahead frame by frame: seeking is not
+  // frame-exact, and seeded modes key their output on the frame index.
+  let first_frame = checkpoint.completed_frames();
+  if first_frame > 0 {
+    reporter.log(&format!(
+      "Resuming at frame {first_frame} after {} finished segment(s).",
+      checkpoint.journal.completed_segments
+    ));
+  }
+
+  reporter.stage(JobStage::Render);
+  let input_options = sequence::input_options(input_path, safe_fps)?;
+  let matte_options = match &request.matte {
+    Some(matte) => sequence::input_options(&matte.path, safe_fps)?,
+    None => Vec::new()
+  };
+  // Pass 1 rides along with the segments. A resumed render never sees the earlier frames,
+  // so it runs over the intermediate after the join instead.
+  let first_pass = match &rated_encoding {
+    Some(rated) if first_frame == 0 => {
+      let input = PassInput::Raw {
+        width: safe_width,
+        height: safe_height,
+        fps: safe_fps,
+        pixel_format: S::PIX_FMT
+      };
+      Some(FirstPass::spawn(ffmpeg, build_first_pass_args(&input, rated, &pass_log))?)
+    }
+    _ => None
+  };
+  let decode_args = build_decode_args(
+    input_path,
+    &input_options,
+    safe_width,
+    safe_height,
+    trim_range,
+    S::PIX_FMT
+  );
+  let (mut decoder, decode_out, decode_err) = spawn_decoder(ffmpeg, decode_args, "decoder")?;
+  let mut matte_decoder = None;
+  if let Some(matte) = &request.matte {
+    let args = build_matte_decode_args(
+      &matte.path,
+      &matte_options,
+      safe_width,
+      safe_height,
+      safe_fps,
+      trim_range
+    );
+    match spawn_decoder(ffmpeg, args, "matte decoder") {
+      Ok((child, stdout, stderr)) => matte_decoder = Some((ChildGuard(child), stdout, stderr)),
+      Err(error) => {
+        stop_child(&mut decoder);
+        return Err(error);
+      }
+    }
+    reporter.log(&format!("Gating the effect with matte {}.", matte.path));
+  }
+  let (mut matte_child, matte_pipes) = match matte_decoder {
+    Some((child, stdout, stderr)) => (Some(child), Some((stdout, stderr))),
+    None => (None, None)
+  };
+  let segment_encoding = encoding.segment_encoding();
+  let encode_in = SegmentWriter::new::<S>(
+    ffmpeg,
+    (safe_width, safe_height, safe_fps),
+    &segment_encoding,
+    artifacts.sequence.as_ref(),
+    checkpoint,
+    first_pass,
+    reporter
+  );
+
+  // decode -> effect -> encode run as separate stages joined by bounded queues,
+  // so a slow encoder write no longer stalls decoding (and the reverse).
+  let timers = StageTimers::default();
+  // The matte's waits stay out of the stage report, which describes the main pipeline.
+  let matte_timers = StageTimers::default();
+  let segment_count = std::thread::scope(|scope| {
+    let decode_log = scope.spawn(|| drain_stderr(decode_err, "decode", reporter));
+    let matte_timers = &matte_timers;
+    let matte_pixels = (safe_width as usize) * (safe_height as usize);
+    let mut matte = request.matte.as_ref().zip(matte_pipes).map(|(matte, (stdout, stderr))| {
+      let (frames_tx, frames_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+      let (free_tx, free_rx) = mpsc::channel();
+      let log = scope.spawn(move || drain_stderr(stderr, "matte decode", reporter));
+      let stage = scope
+        .spawn(move || run_decode_stage(stdout, matte_pixels, frames_tx, free_rx, matte_timers));
+      (MatteFeed::new(matte, frames_rx, free_tx, matte_pixels), stage, log)
+    });
+
+    let (decoded_tx, decoded_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+    let (encoded_tx, encoded_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+    let (decoded_free_tx, decoded_free_rx) = mpsc::channel();
+    let (encoded_free_tx, encoded_free_rx) = mpsc::channel();
+    let timers = &timers;
+    let decode_stage = scope.spawn(move || {
+      run_decode_stage::<S>(decode_out, frame_size, decoded_tx, decoded_free_rx, timers)
+    });
+    let encode_stage =
+      scope.spawn(move || run_encode_stage(encode_in, encoded_rx, encoded_free_tx, timers));
+
+    let clock = ProgressClock::start(safe_fps, total_frames).resumed_at(first_frame);
+    // On error the encode stage sees its queue close and returns the writer, whose drop
+    // stops the unfinished segment's encoder.
+    let processed_frames = match run_process_stage(
+      effect,
+      (safe_width, safe_height),
+      first_frame,
+      decoded_rx,
+      decoded_free_tx,
+      encoded_tx,
+      encoded_free_rx,
+      matte.as_mut().map(|(feed, _, _)| feed),
+      &clock,
+      timers,
+      cancel_flag,
+      reporter
+    ) {
+      Ok(processed_frames) => processed_frames,
+      Err(error) => {
+        stop_child(&mut decoder);
+        return Err(error);
+      }
+    };
+
+    let encode_result = encode_stage
+      .join()
+      .unwrap_or_else(|_| Err(BitrotError::internal("Encode stage panicked.")));
+    let decode_result = decode_stage
+      .join()
+      .unwrap_or_else(|_| Err(BitrotError::internal("Decode stage panicked.")));
+    let encode_in = match decode_result.and(encode_result) {
+      Ok(encode_in) => encode_in,
+      Err(error) => {
+        stop_child(&mut decoder);
+        return Err(error);
+      }
+    };
+
+    let decode_status = decoder
+      .wait()
+      .map_err(|error| BitrotError::internal(format!("decode wait failed: {error}")))?;
+    let decode_errors = decode_log.join().unwrap_or_default();
+    if !decode_status.success() {
+      return Err(ffmpeg_failure(
+        ErrorCode::DecodeFailed,
+        "Decoder",
+        decode_status.code(),
+        &decode_errors.join("\n")
+      ));
+    }
+    if let (Some((feed, stage, log)), Some(child)) = (matte, matte_child.as_mut()) {
+      finish_matte(&mut child.0, feed, stage, log)?;
+    }
+
+    let waits = timers.waits();
+    reporter.progress(&clock.snapshot(processed_frames).with_stage_waits(waits));
+
+    // The writer closes the last segment's stdin, letting its encoder flush.
+    let segment_count = encode_in.finish()?;
+    reporter.log(&format!(
+      "Stage waits: decode {:.2}s, effect input {:.2}s, effect output {:.2}s, encode {:.2}s.",
+      waits.decode_seconds,
+      waits.process_input_seconds,
+      waits.process_output_seconds,
+      waits.encode_seconds
+    ));
+    Ok(segment_count)
+  })?;
+
+  if segment_count == 0 {
+    return Err(BitrotError::new(ErrorCode::DecodeFailed, "Decoder produced no frames."));
+  }
+  if let Some(sequence) = &artifacts.sequence {
+    reporter.log(&format!(
+      "Wrote frames to {} starting at {}.",
+      sequence.pattern, sequence.first_number
+    ));
+    return Ok(());
+  }
+  reporter.stage(JobStage::Concat);
+  let list_path = checkpoint.write_concat_list(segment_count)?;
+  run_ffmpeg_output(
+    ffmpeg,
+    build_concat_args(&list_path, &artifacts.temp_video),
+    ErrorCode::EncodeFailed,
+    "Joining segments"
+  )?;
+
+  let mut video = &artifacts.temp_video;
+  if let (Some(rated), Some(second_pass)) = (&rated_encoding, &artifacts.second_pass) {
+    if first_frame > 0 {
+      reporter.log("Running pass 1 over the intermediate for the resumed render.");
+      run_ffmpeg_output(
+        ffmpeg,
+        build_first_pass_args(&PassInput::File(&artifacts.temp_video), rated, &pass_log),
+        ErrorCode::EncodeFailed,
+        "First pass"
+      )?;
+    }
+    reporter.stage(JobStage::SecondPass);
+    run_ffmpeg_output(
+      ffmpeg,
+      build_second_pass_args(&artifacts.temp_video, rated, &pass_log, second_pass),
+      ErrorCode::EncodeFailed,
+      "Second pass"
+    )?;
+    video = second_pass;
+  }
+
+  reporter.stage(JobStage::Mux);
+  if let Some(format) = encoding.animated_format() {
+    return write_animation(
+      ffmpeg,
+      &artifacts.temp_video,
+      &artifacts.output,
+      format,
+      &encoding.animation.clone().unwrap_or_default(),
+      safe_fps,
+      reporter
+    );
+  }
+  run_ffmpeg_output(
+    ffmpeg,
+    build_mux_args(
+      video,
+      input_path,
+      output_path,
+      trim_range,
+      encoding,
+      &container_metadata(&checkpoint.journal.spec)
+    ),
+    ErrorCode::MuxFailed,
+    "Mux"
+  )?;
+  if let Some(target_mb) = encoding.target_size_mb {
+    report_size(&artifacts.output, target_mb, reporter);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::mask::{Matte, MatteEnd};
+  use crate::modes::chain::ChainConfig;
+  use crate::progress::ProgressSnapshot;
+
+  struct Invert;
+
+  impl NativeEffect for Invert {
+    const LABEL: &'static str = "Invert";
+    const TAG: &'static str = "invert";
+
+    type Workspace<S: Sample> = Vec<S>;
+
+    fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+      vec![S::default(); width * height * 4]
+    }
+
+    fn process_frame<'a, S: Sample>(
+      &self,
+      frame: &[S],
+      workspace: &'a mut Self::Workspace<S>,
+      _frame_index: u64
+    ) -> &'a [S] {
+      for (out, value) in workspace.iter_mut().zip(frame) {
+        *out = S::from_u32(S::MAX - (*value).into());
+      }
+      workspace
+    }
+  }
+
+  struct Silent;
+
+  impl JobReporter for Silent {
+    fn log(&self, _message: &str) {}
+
+    fn progress(&self, _progress: &ProgressSnapshot) {}
+  }
+
+  // Pushes raw frames (and optional matte frames) through the stages at sample depth `S`;
+  // returns the frame count and encoded bytes.
+  fn run_stages<S: Sample, E: NativeEffect>(
+    effect: &E,
+    (width, height): (u32, u32),
+    input: &[u8],
+    first_frame: u64,
+    matte: Option<(&Matte, &[u8])>
+  ) -> (Result<u64, BitrotError>, Vec<u8>) {
+    let frame_size = (width * height * 4) as usize;
+    let mut encoded = Vec::new();
+    let timers = StageTimers::default();
+    let cancel = AtomicBool::new(false);
+
+    let processed = std::thread::scope(|scope| {
+      let (decoded_tx, decoded_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+      let (encoded_tx, encoded_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+      let (decoded_free_tx, decoded_free_rx) = mpsc::channel();
+      let (encoded_free_tx, encoded_free_rx) = mpsc::channel();
+      let timers = &timers;
+      let writer = &mut encoded;
+      let decode = scope.spawn(move || {
+        run_decode_stage::<S>(input, frame_size, decoded_tx, decoded_free_rx, timers)
+      });
+      let encode =
+        scope.spawn(move || run_encode_stage(writer, encoded_rx, encoded_free_tx, timers));
+      let pixels = (width * height) as usize;
+      let mut feed = matte.map(|(matte, frames)| {
+        let (frames_tx, frames_rx) = mpsc::sync_channel(FRAME_QUEUE_DEPTH);
+        let (free_tx, free_rx) = mpsc::channel();
+        scope.spawn(move || run_decode_stage::<u8>(frames, pixels, frames_tx, free_rx, timers));
+        MatteFeed::new(matte, frames_rx, free_tx, pixels)
+      });
+      let clock = ProgressClock::start(30.0, None);
+      let processed = run_process_stage(
+        effect,
+        (width, height),
+        first_frame,
+        decoded_rx,
+        decoded_free_tx,
+        encoded_tx,
+        encoded_free_rx,
+        feed.as_mut(),
+        &clock,
+        timers,
+        &cancel,
+        &Silent
+      );
+      decode.join().unwrap().unwrap();
+      encode.join().unwrap().unwrap();
+      processed
+    });
+    (processed, encoded)
+  }
+
+  #[test]
+  fn staged_frames_arrive_in_order_through_bounded_queues() {
+    let frame_size = 2 * 2 * 4;
+    let frame_count = FRAME_QUEUE_DEPTH * 5 + 3;
+    let input: Vec<u8> = (0..frame_size * frame_count).map(|index| index as u8).collect();
+
+    let (processed, encoded) = run_stages::<u8, _>(&Invert, (2, 2), &input, 0, None);
+
+    assert_eq!(processed, Ok(frame_count as u64));
+    let expected: Vec<u8> = input.iter().map(|value| 255 - value).collect();
+    assert_eq!(encoded, expected);
+  }
+
+  #[test]
+  fn sixteen_bit_frames_cross_the_pipes_little_endian() {
+    let samples: Vec<u16> = (0..2 * 2 * 4 * 3).map(|index| index * 1021 + 7).collect();
+    let input: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+
+    let (processed, encoded) = run_stages::<u16, _>(&Invert, (2, 2), &input, 0, None);
+
+    assert_eq!(processed, Ok(3));
+    let expected: Vec<u8> = samples
+      .iter()
+      .flat_map(|sample| (65535 - sample).to_le_bytes())
+      .collect();
+    assert_eq!(encoded, expected);
+  }
+
+  fn encoding_for(encoder: &str, format: &str) -> NativeEncoding {
+    NativeEncoding {
+      encoder: encoder.into(),
+      preset: "medium".into(),
+      crf: None,
+      cq: None,
+      max_bitrate_kbps: None,
+      target_bitrate_kbps: None,
+      vp9_deadline: None,
+      vp9_cpu_used: None,
+      format: format.into(),
+      audio_enabled: false,
+      audio_codec: None,
+      audio_bitrate_kbps: None,
+      extra_encode_args: Vec::new(),
+      extra_mux_args: Vec::new(),
+      high_bit_depth: false,
+      pixel_format: None,
+      alpha: false,
+      animation: None,
+      av1_speed: None,
+      tiles: None,
+      target_size_mb: None
+    }
+  }
+
+  fn output_pixel_format_arg(args: &[String]) -> &str {
+    let index = args.iter().rposition(|arg| arg == "-pix_fmt").unwrap() + 1;
+    &args[index]
+  }
+
+  #[test]
+  fn pixel_formats_are_checked_against_the_encoder() {
+    let encoding = |encoder: &str, pixel_format: Option<&str>| NativeEncoding {
+      high_bit_depth: true,
+      pixel_format: pixel_format.map(Into::into),
+      ..encoding_for(encoder, "mp4")
+    };
+    assert!(encoding("libx264", None).validate().is_ok());
+    assert!(encoding("libvpx-vp9", Some("yuv422p10le")).validate().is_ok());
+    assert!(encoding("h264_nvenc", Some("yuv420p10le")).validate().is_err());
+    assert!(encoding("libx264", Some("yuv411p")).validate().is_err());
+    assert!(encoding("libx264", Some("yuv444p")).validate().is_ok());
+    assert!(encoding("libsvtav1", Some("yuv444p")).validate().is_err());
+
+    // Planar RGB only where the encoder codes it, and only in Matroska.
+    let rgb = |encoder: &str, pixel_format: &str, format: &str| NativeEncoding {
+      pixel_format: Some(pixel_format.into()),
+      ..encoding_for(encoder, format)
+    };
+    assert!(rgb("libx265", "gbrp", "mkv").validate().is_ok());
+    assert!(rgb("libx265", "gbrp", "mp4").validate().is_err());
+    assert!(rgb("libx264", "gbrp", "mkv").validate().is_err());
+    let rgb24 = rgb("libvpx-vp9", "rgb24", "mkv").validate().unwrap_err();
+    assert!(rgb24.message.contains("gbrp"));
+    assert!(rgb("png", "rgb24", "mov").validate().is_ok());
+
+    let ten_bit = encoding("libx264", Some("yuv420p10le"));
+    let args = build_encode_args(64, 36, 24.0, &ten_bit, Path::new("out.mp4"), u16::PIX_FMT);
+    assert_eq!(args[7], "rgba64le");
+    assert_eq!(output_pixel_format_arg(&args), "yuv420p10le");
+  }
+
+  #[test]
+  fn alpha_needs_a_capable_encoder_and_container() {
+    let alpha = |encoder: &str, format: &str| NativeEncoding {
+      alpha: true,
+      ..encoding_for(encoder, format)
+    };
+    for (encoder, format, pixel_format) in [
+      ("prores_ks", "mov", "yuva444p10le"),
+      ("libvpx-vp9", "webm", "yuva420p"),
+      ("qtrle", "mov", "argb"),
+      ("png", "mkv", "rgba")
+    ] {
+      let encoding = alpha(encoder, format);
+      assert!(encoding.validate().is_ok(), "{encoder} in .{format}");
+      let args = build_encode_args(64, 36, 24.0, &encoding, Path::new("out"), "rgba");
+      assert_eq!(output_pixel_format_arg(&args), pixel_format);
+    }
+    assert!(alpha("libx264", "mp4").validate().is_err());
+    assert!(alpha("prores_ks", "mp4").validate().is_err());
+    assert!(alpha("libvpx-vp9", "mov").validate().is_err());
+
+    // Alpha pixel formats and the alpha switch must agree.
+    let explicit = |encoder: &str, pixel_format: &str, alpha: bool| NativeEncoding {
+      pixel_format: Some(pixel_format.into()),
+      alpha,
+      ..encoding_for(encoder, "mov")
+    };
+    assert!(explicit("png", "rgba64be", true).validate().is_ok());
+    assert!(explicit("png", "rgba", false).validate().is_err());
+    assert!(explicit("prores_ks", "yuv444p10le", true).validate().is_err());
+    assert_eq!(encoding_for("prores_ks", "mov").output_pixel_format(), "yuv444p10le");
+  }
+
+  #[test]
+  fn hevc_and_av1_encoders_get_their_own_args() {
+    let hevc = encoding_for("libx265", "mp4");
+    assert!(hevc.validate().is_ok());
+    let args = build_encode_args(64, 36, 24.0, &hevc, Path::new("out.mp4"), "rgba");
+    assert!(args.windows(2).any(|pair| pair == ["-c:v", "libx265"]));
+    assert!(args.windows(2).any(|pair| pair == ["-crf", "28"]));
+    let mux = build_mux_args(Path::new("tmp.mp4"), "in.mp4", "out.mp4", None, &hevc, &[]);
+    assert!(mux.windows(2).any(|pair| pair == ["-tag:v", "hvc1"]));
+    assert!(encoding_for("libx265", "webm").validate().is_err());
+
+    let svt = NativeEncoding {
+      av1_speed: Some(6),
+      tiles: Some(Tiles { columns: 4, rows: 2 }),
+      ..encoding_for("libsvtav1", "webm")
+    };
+    assert!(svt.validate().is_ok());
+    let args = build_encode_args(64, 36, 24.0, &svt, Path::new("out.webm"), "rgba");
+    assert!(args.windows(2).any(|pair| pair == ["-preset", "6"]));
+    assert!(args.windows(2).any(|pair| pair == ["-svtav1-params", "tile-columns=2:tile-rows=1"]));
+
+    let aom = NativeEncoding {
+      tiles: Some(Tiles { columns: 2, rows: 2 }),
+      ..encoding_for("libaom-av1", "mkv")
+    };
+    let args = build_encode_args(64, 36, 24.0, &aom, Path::new("out.mkv"), "rgba");
+    assert!(args.windows(2).any(|pair| pair == ["-cpu-used", "4"]));
+    assert!(args.windows(2).any(|pair| pair == ["-tiles", "2x2"]));
+    let odd_tiles = NativeEncoding { tiles: Some(Tiles { columns: 3, rows: 1 }), ..aom };
+    assert!(odd_tiles.validate().is_err());
+
+    // Unknown encoders are an error, not a quiet switch to libx264.
+    let unknown = encoding_for("libx266", "mp4");
+    assert!(unknown.validate().is_err());
+    let args = build_encode_args(64, 36, 24.0, &unknown, Path::new("out.mp4"), "rgba");
+    assert!(!args.contains(&"libx264".to_string()));
+  }
+
+  #[test]
+  fn mezzanine_encoders_mux_pcm_without_faststart() {
+    let ffv1 = encoding_for("ffv1", "mkv");
+    assert!(ffv1.validate().is_ok());
+    assert!(encoding_for("ffv1", "mov").validate().is_err());
+    let args = build_encode_args(64, 36, 24.0, &ffv1, Path::new("out.mkv"), "rgba");
+    assert!(args.windows(2).any(|pair| pair == ["-level", "3"]));
+    assert_eq!(output_pixel_format_arg(&args), "yuv444p10le");
+    assert!(encoding_for("utvideo", "avi").validate().is_ok());
+
+    let hq = NativeEncoding {
+      pixel_format: Some("yuv422p10le".into()),
+      audio_enabled: true,
+      ..encoding_for("prores_ks", "mov")
+    };
+    assert!(hq.validate().is_ok());
+    let args = build_encode_args(64, 36, 24.0, &hq, Path::new("out.mov"), "rgba");
+    assert!(args.windows(2).any(|pair| pair == ["-profile:v", "hq"]));
+    assert!(!args.iter().any(|arg| arg == "-movflags"));
+    let mux = build_mux_args(Path::new("tmp.mov"), "in.mp4", "out.mov", None, &hq, &[]);
+    assert!(mux.windows(2).any(|pair| pair == ["-c:a", "pcm_s24le"]));
+    assert!(!mux.iter().any(|arg| arg == "-b:a" || arg == "-movflags"));
+
+    // Delivery codecs keep AAC and +faststart; PCM cannot go in mp4.
+    let h264 = NativeEncoding { audio_enabled: true, ..encoding_for("libx264", "mp4") };
+    let mux = build_mux_args(Path::new("tmp.mp4"), "in.mp4", "out.mp4", None, &h264, &[]);
+    assert!(mux.windows(2).any(|pair| pair == ["-c:a", "aac"]));
+    assert!(mux.windows(2).any(|pair| pair == ["-movflags", "+faststart"]));
+    let pcm_mp4 = NativeEncoding { audio_codec: Some("pcm".into()), ..h264 };
+    assert!(pcm_mp4.validate().is_err());
+  }
+
+  #[test]
+  fn resumed_render_matches_the_uninterrupted_tail() {
+    // Seeded block shift plus modulo mapping, whose motion term reads the previous frame.
+    let chain: ChainConfig = serde_json::from_value(serde_json::json!({
+      "steps": [
+        {
+          "mode": "block-shift",
+          "config": { "blockSize": 4, "maxOffset": 6, "offsetStep": 2, "intensity": 100.0, "seed": 99 }
+        },
+        {
+          "mode": "modulo-mapping",
+          "config": { "modulus": 10, "stride": 3, "offset": 14, "intensity": 100.0 }
+        }
+      ]
+    }))
+    .unwrap();
+    let (width, height) = (24, 16);
+    let frame_size = (width * height * 4) as usize;
+    let frame_count = 12;
+    let input: Vec<u8> = (0..frame_size * frame_count)
+      .map(|index| ((index * 31 + (index / frame_size) * 57) % 251) as u8)
+      .collect();
+
+    let (_, full) = run_stages::<u8, _>(&chain, (width, height), &input, 0, None);
+    let first_frame = 7;
+    let (processed, resumed) =
+      run_stages::<u8, _>(&chain, (width, height), &input, first_frame, None);
+
+    assert_eq!(chain.warmup_frames(), 1);
+    assert_eq!(processed, Ok(frame_count as u64));
+    assert_eq!(resumed, full[first_frame as usize * frame_size..]);
+  }
+
+  #[test]
+  fn matte_gates_the_effect_and_handles_a_short_matte() {
+    let frame_size = 2 * 2 * 4;
+    let input: Vec<u8> = (0..frame_size * 4).map(|index| (index * 3) as u8).collect();
+    // Two matte frames for four input frames: left column, then right column.
+    let frames = [255, 0, 255, 0, 0, 255, 0, 255];
+    let run = |after_end| {
+      let matte = Matte {
+        path: "matte.mp4".into(),
+        invert: false,
+        after_end
+      };
+      run_stages::<u8, _>(&Invert, (2, 2), &input, 0, Some((&matte, &frames)))
+    };
+    let inverted = |index: usize| 255 - input[index];
+
+    let (processed, held) = run(MatteEnd::Hold);
+    assert_eq!(processed, Ok(4));
+    assert_eq!(held[0], inverted(0));
+    assert_eq!(held[4], input[4]);
+    assert_eq!(held[frame_size], input[frame_size]);
+    assert_eq!(held[frame_size + 4], inverted(frame_size + 4));
+    // Past the matte's end its last frame keeps gating.
+    assert_eq!(held[3 * frame_size], input[3 * frame_size]);
+    assert_eq!(held[3 * frame_size + 4], inverted(3 * frame_size + 4));
+
+    let (_, source) = run(MatteEnd::Source);
+    assert_eq!(source[2 * frame_size..], input[2 * frame_size..]);
+    let (_, effect) = run(MatteEnd::Effect);
+    assert_eq!(effect[3 * frame_size], inverted(3 * frame_size));
+  }
+}
@@ -0,0 +1,52 @@
+// Pure preview helpers: size limits and RGBA downscaling for preview frames.
+use crate::error::BitrotError;
+
+const MAX_PREVIEW_DIMENSION: u32 = 1280;
+
+// Validates preview dimensions and returns the expected RGBA byte length.
+pub fn preview_expected_len(width: u32, height: u32) -> Result<usize, BitrotError> {
+  if width < 2 || height < 2 {
+    return Err(BitrotError::invalid("Preview dimensions are invalid."));
+  }
+  (width as usize)
+    .checked_mul(height as usize)
+    .and_then(|value| value.checked_mul(4))
+    .ok_or_else(|| BitrotError::invalid("Preview dimensions are too large."))
+}
+
+pub fn resolve_preview_size(width: u32, height: u32) -> (u32, u32) {
+  let max_dim = width.max(height);
+  if max_dim <= MAX_PREVIEW_DIMENSION {
+    return (width, height);
+  }
+
+  let scale = MAX_PREVIEW_DIMENSION as f64 / max_dim as f64;
+  let scaled_width = ((width as f64) * scale).round().max(1.0) as u32;
+  let scaled_height = ((height as f64) * scale).round().max(1.0) as u32;
+  (scaled_width, scaled_height)
+}
+
+// Simple nearest-neighbor resize for preview buffers.
+pub fn downscale_rgba_nearest(
+  src: &[u8],
+  src_width: u32,
+  src_height: u32,
+  dst_width: u32,
+  dst_height: u32
+) -> Vec<u8> {
+  if src_width == dst_width && src_height == dst_height {
+    return src.to_vec();
+  }
+
+  let mut dst = vec![0u8; (dst_width as usize) * (dst_height as usize) * 4];
+  for y in 0..dst_height {
+    let src_y = (y as u64 * src_height as u64 / dst_height as u64) as u32;
+    for x in 0..dst_width {
+      let src_x = (x as u64 * src_width as u64 / dst_width as u64) as u32;
+      let src_idx = ((src_y * src_width + src_x) * 4) as usize;
+      let dst_idx = ((y * dst_width + x) * 4) as usize;
+      dst[dst_idx..dst_idx + 4].copy_from_slice(&src[src_idx..src_idx + 4]);
+    }
+  }
+  dst
+}
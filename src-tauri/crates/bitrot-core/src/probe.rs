@@ -0,0 +1,104 @@
+// ffprobe geometry/timing lookup for jobs that don't carry their own metadata.
+use std::process::Stdio;
+
+use serde::Deserialize;
+
+use crate::error::{BitrotError, ErrorCode};
+use crate::pipeline::FfmpegLauncher;
+
+#[derive(Deserialize)]
+struct ProbeStream {
+  width: Option<u32>,
+  height: Option<u32>,
+  avg_frame_rate: Option<String>,
+  r_frame_rate: Option<String>
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+  duration: Option<String>
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+  #[serde(default)]
+  streams: Vec<ProbeStream>,
+  format: Option<ProbeFormat>
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbedVideo {
+  pub width: u32,
+  pub height: u32,
+  pub fps: f64,
+  pub duration_seconds: Option<f64>
+}
+
+fn parse_rate(rate: Option<&str>) -> Option<f64> {
+  let (num, den) = rate?.split_once('/')?;
+  let num = num.trim().parse::<f64>().ok()?;
+  let den = den.trim().parse::<f64>().ok()?;
+  let value = num / den;
+  (den != 0.0 && value.is_finite() && value > 0.0).then_some(value)
+}
+
+// `ffprobe` builds a fresh ffprobe command, resolved the same way as ffmpeg.
+// `input_options` carry an image sequence's rate and start number, as for decoding.
+pub fn probe_video(
+  ffprobe: FfmpegLauncher,
+  input_path: &str,
+  input_options: &[String]
+) -> Result<ProbedVideo, BitrotError> {
+  let output = ffprobe()?
+    .args(input_options)
+    .args([
+      "-v",
+      "error",
+      "-select_streams",
+      "v:0",
+      "-show_entries",
+      "stream=width,height,avg_frame_rate,r_frame_rate:format=duration",
+      "-of",
+      "json",
+      "--",
+      input_path
+    ])
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to run ffprobe: {error}"))
+    })?;
+  if !output.status.success() {
+    return Err(
+      BitrotError::new(ErrorCode::FfmpegFailed, format!("ffprobe could not read {input_path}."))
+        .with_stderr(&String::from_utf8_lossy(&output.stderr))
+    );
+  }
+  let parsed: ProbeOutput = serde_json::from_slice(&output.stdout).map_err(|error| {
+    BitrotError::new(ErrorCode::FfmpegFailed, format!("Unable to parse ffprobe output: {error}"))
+  })?;
+  let stream = parsed
+    .streams
+    .first()
+    .ok_or_else(|| BitrotError::invalid("Input has no video stream."))?;
+  let (Some(width), Some(height)) = (stream.width, stream.height) else {
+    return Err(BitrotError::new(
+      ErrorCode::FfmpegFailed,
+      "ffprobe did not report video dimensions."
+    ));
+  };
+  let fps = parse_rate(stream.avg_frame_rate.as_deref())
+    .or_else(|| parse_rate(stream.r_frame_rate.as_deref()))
+    .unwrap_or(0.0);
+  let duration_seconds = parsed
+    .format
+    .and_then(|format| format.duration)
+    .and_then(|duration| duration.trim().parse::<f64>().ok())
+    .filter(|duration| duration.is_finite());
+  Ok(ProbedVideo {
+    width,
+    height,
+    fps,
+    duration_seconds
+  })
+}
@@ -0,0 +1,128 @@
+// Progress math + the reporter trait native hosts use to surface job status.
+// Kept free of tauri types so headless callers can implement it too.
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::events::{JobStage, LogLevel};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressSnapshot {
+  pub frame: u64,
+  pub total_frames: Option<u64>,
+  pub percent: f64,
+  pub fps: Option<f64>,
+  pub speed: Option<f64>,
+  pub out_time_seconds: Option<f64>,
+  pub elapsed_seconds: Option<f64>,
+  pub eta_seconds: Option<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub stage_waits: Option<StageWaits>
+}
+
+// Seconds each pipeline stage has spent blocked on its neighbours so far.
+// A large wait on one side points at the stage on the other side as the bottleneck.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StageWaits {
+  // Decoder blocked on a full effect queue.
+  pub decode_seconds: f64,
+  // Effect idle, waiting for decoded frames.
+  pub process_input_seconds: f64,
+  // Effect blocked on a full encoder queue.
+  pub process_output_seconds: f64,
+  // Encoder idle, waiting for processed frames.
+  pub encode_seconds: f64
+}
+
+impl ProgressSnapshot {
+  pub fn with_stage_waits(mut self, stage_waits: StageWaits) -> Self {
+    self.stage_waits = Some(stage_waits);
+    self
+  }
+}
+
+// Timing snapshot used to derive fps/speed/ETA for progress events.
+pub struct ProgressClock {
+  start: Instant,
+  source_fps: f64,
+  total_frames: Option<u64>,
+  start_frame: u64
+}
+
+impl ProgressClock {
+  pub fn start(source_fps: f64, total_frames: Option<u64>) -> Self {
+    Self {
+      start: Instant::now(),
+      source_fps,
+      total_frames,
+      start_frame: 0
+    }
+  }
+
+  // Resumed renders count progress from frame zero but rates only from this run's frames.
+  pub fn resumed_at(mut self, start_frame: u64) -> Self {
+    self.start_frame = start_frame;
+    self
+  }
+
+  pub fn snapshot(&self, frame: u64) -> ProgressSnapshot {
+    let elapsed_seconds = self.start.elapsed().as_secs_f64();
+    let processing_fps = if elapsed_seconds > 0.0 {
+      frame.saturating_sub(self.start_frame) as f64 / elapsed_seconds
+    } else {
+      0.0
+    };
+    let fps = (processing_fps > 0.0).then_some(processing_fps);
+    let speed = (self.source_fps > 0.0 && processing_fps > 0.0)
+      .then(|| processing_fps / self.source_fps);
+    let out_time_seconds = (self.source_fps > 0.0).then(|| frame as f64 / self.source_fps);
+    let eta_seconds = self.total_frames.and_then(|total| {
+      if processing_fps > 0.0 {
+        Some(total.saturating_sub(frame) as f64 / processing_fps)
+      } else {
+        None
+      }
+    });
+    let percent = self
+      .total_frames
+      .filter(|total| *total > 0)
+      .map(|total| (frame as f64 / total as f64) * 100.0)
+      .unwrap_or(0.0);
+    ProgressSnapshot {
+      frame,
+      total_frames: self.total_frames,
+      percent,
+      fps,
+      speed,
+      out_time_seconds,
+      elapsed_seconds: Some(elapsed_seconds),
+      eta_seconds,
+      stage_waits: None
+    }
+  }
+}
+
+// Receives log lines, progress and processed frames from a running native job.
+// Called from worker threads, so implementations must be Sync.
+pub trait JobReporter: Sync {
+  fn log(&self, message: &str);
+
+  // Lines that need attention (e.g. ffmpeg stderr); plain `log` is info.
+  fn log_at(&self, _level: LogLevel, message: &str) {
+    self.log(message);
+  }
+
+  fn progress(&self, progress: &ProgressSnapshot);
+
+  fn stage(&self, _stage: JobStage) {}
+
+  // Whether `frame` will use this frame; the host skips converting frames nobody reads.
+  fn wants_frame(&self, _frame_index: u64) -> bool {
+    false
+  }
+
+  // Processed RGBA frames the reporter asked for; live previews sample from them.
+  fn frame(&self, _frame_index: u64, _rgba: &[u8], _width: u32, _height: u32) {}
+}
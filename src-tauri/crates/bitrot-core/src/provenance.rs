@@ -0,0 +1,261 @@
+// Render provenance: the settings behind an output are embedded in its container metadata
+// and written in full to a `<output>.bitrot.json` sidecar, which can be replayed later to
+// produce the same render again.
+use std::{
+  fs,
+  io::{Read, Seek, SeekFrom},
+  path::{Path, PathBuf},
+  process::Stdio,
+  time::{SystemTime, UNIX_EPOCH}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BitrotError;
+use crate::events::LogLevel;
+use crate::ffmpeg::{resolve_ffmpeg_program, CommandSource};
+use crate::job::JobSpec;
+use crate::pipeline::FfmpegLauncher;
+use crate::progress::JobReporter;
+use crate::sequence::first_frame_path;
+use crate::toolchain::parse_version;
+
+pub const SIDECAR_VERSION: u32 = 1;
+const SIDECAR_SUFFIX: &str = ".bitrot.json";
+// Hashing whole inputs would take longer than some renders; the head and tail are enough
+// to tell a re-exported file apart.
+const FINGERPRINT_SAMPLE_BYTES: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InputFingerprint {
+  pub size_bytes: u64,
+  pub modified_unix: Option<u64>,
+  // FNV-1a (64-bit, hex) over the first and last MiB.
+  pub sample_hash: String
+}
+
+impl InputFingerprint {
+  // Image sequences are fingerprinted by their first frame.
+  pub fn of(path: &str) -> Result<Self, BitrotError> {
+    let context = format!("Failed to fingerprint {path}");
+    let file_path = first_frame_path(path).unwrap_or_else(|| PathBuf::from(path));
+    let mut file = fs::File::open(file_path).map_err(|error| BitrotError::io(&context, error))?;
+    let metadata = file
+      .metadata()
+      .map_err(|error| BitrotError::io(&context, error))?;
+    let size_bytes = metadata.len();
+    let modified_unix = metadata
+      .modified()
+      .ok()
+      .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+      .map(|elapsed| elapsed.as_secs());
+
+    let mut hash = Fnv1a::default();
+    let mut sample = Vec::new();
+    (&mut file)
+      .take(FINGERPRINT_SAMPLE_BYTES)
+      .read_to_end(&mut sample)
+      .map_err(|error| BitrotError::io(&context, error))?;
+    hash.update(&sample);
+    let tail_start = size_bytes.saturating_sub(FINGERPRINT_SAMPLE_BYTES);
+    if tail_start > FINGERPRINT_SAMPLE_BYTES {
+      sample.clear();
+      file
+        .seek(SeekFrom::Start(tail_start))
+        .and_then(|_| file.read_to_end(&mut sample))
+        .map_err(|error| BitrotError::io(&context, error))?;
+      hash.update(&sample);
+    }
+    Ok(Self {
+      size_bytes,
+      modified_unix,
+      sample_hash: format!("{:016x}", hash.0)
+    })
+  }
+}
+
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+  fn default() -> Self {
+    Self(0xcbf2_9ce4_8422_2325)
+  }
+}
+
+impl Fnv1a {
+  fn update(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderProvenance {
+  pub version: u32,
+  pub bitrot_version: String,
+  pub rendered_at: u64,
+  pub spec: JobSpec,
+  // First token after "ffmpeg version", e.g. "7.0.2"; missing when `-version` failed.
+  pub ffmpeg_version: Option<String>,
+  pub ffmpeg_source: CommandSource,
+  pub input: InputFingerprint
+}
+
+// Sidecar next to the output: "clip.mp4" -> "clip.mp4.bitrot.json".
+pub fn sidecar_path(output_path: &str) -> PathBuf {
+  PathBuf::from(format!("{}{SIDECAR_SUFFIX}", output_path.trim()))
+}
+
+// Settings summary for the output's `comment` tag. Input/output paths stay out of the file.
+pub fn container_metadata(spec: &JobSpec) -> Vec<(String, String)> {
+  let mut settings = serde_json::to_value(spec).unwrap_or_default();
+  if let Some(fields) = settings.as_object_mut() {
+    fields.remove("input");
+    fields.remove("output");
+    fields.insert("bitrot".into(), env!("CARGO_PKG_VERSION").into());
+  }
+  vec![("comment".into(), settings.to_string())]
+}
+
+fn ffmpeg_version(ffmpeg: FfmpegLauncher) -> Option<String> {
+  let output = ffmpeg()
+    .ok()?
+    .arg("-version")
+    .stdin(Stdio::null())
+    .output()
+    .ok()?;
+  parse_version(&String::from_utf8_lossy(&output.stdout))
+}
+
+impl RenderProvenance {
+  pub fn capture(ffmpeg: FfmpegLauncher, spec: &JobSpec) -> Result<Self, BitrotError> {
+    Ok(Self {
+      version: SIDECAR_VERSION,
+      bitrot_version: env!("CARGO_PKG_VERSION").into(),
+      rendered_at: SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0),
+      spec: spec.clone(),
+      ffmpeg_version: ffmpeg_version(ffmpeg),
+      // Every host resolves ffmpeg through `resolve_ffmpeg_program`, so this matches the launcher.
+      ffmpeg_source: resolve_ffmpeg_program("ffmpeg").source,
+      input: InputFingerprint::of(&spec.input)?
+    })
+  }
+
+  pub fn load(path: &Path) -> Result<Self, BitrotError> {
+    let raw = fs::read_to_string(path).map_err(|error| {
+      BitrotError::io(&format!("Failed to read render settings {}", path.display()), error)
+    })?;
+    let provenance: Self = serde_json::from_str(&raw)
+      .map_err(|error| BitrotError::invalid(format!("Invalid render settings file: {error}")))?;
+    if provenance.version != SIDECAR_VERSION {
+      return Err(BitrotError::invalid(format!(
+        "Render settings version {} is not supported (expected {SIDECAR_VERSION}).",
+        provenance.version
+      )));
+    }
+    Ok(provenance)
+  }
+
+  pub fn save(&self) -> Result<PathBuf, BitrotError> {
+    let path = sidecar_path(&self.spec.output);
+    let raw = serde_json::to_string_pretty(self).map_err(|error| {
+      BitrotError::internal(format!("Failed to serialize render settings: {error}"))
+    })?;
+    fs::write(&path, raw).map_err(|error| BitrotError::io("Failed to write render settings", error))?;
+    Ok(path)
+  }
+
+  // Why the input no longer matches the recorded one, if it doesn't.
+  pub fn input_mismatch(&self) -> Option<String> {
+    match InputFingerprint::of(&self.spec.input) {
+      Ok(current) if current.size_bytes != self.input.size_bytes => Some(format!(
+        "Input size changed ({} -> {} bytes).",
+        self.input.size_bytes, current.size_bytes
+      )),
+      Ok(current) if current.sample_hash != self.input.sample_hash => {
+        Some("Input contents changed since the original render.".into())
+      }
+      Ok(_) => None,
+      Err(error) => Some(error.message)
+    }
+  }
+}
+
+// Writes the sidecar for a finished render. A failure here is logged; the render itself
+// already succeeded.
+pub fn record_render(ffmpeg: FfmpegLauncher, spec: &JobSpec, reporter: &dyn JobReporter) {
+  match RenderProvenance::capture(ffmpeg, spec).and_then(|provenance| provenance.save()) {
+    Ok(path) => reporter.log(&format!("Render settings saved to {}.", path.display())),
+    Err(error) => reporter.log_at(
+      LogLevel::Warn,
+      &format!("Failed to save render settings: {error}")
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spec(input: &Path, output: &Path) -> JobSpec {
+    serde_json::from_value(serde_json::json!({
+      "mode": "block-shift",
+      "config": {
+        "blockSize": 16,
+        "maxOffset": 24,
+        "offsetStep": 4,
+        "intensity": 100.0,
+        "seed": 42
+      },
+      "input": input,
+      "output": output,
+      "encoding": {
+        "encoder": "libx264",
+        "preset": "medium",
+        "format": "mp4",
+        "audioEnabled": false
+      },
+      "trimStartSeconds": 1.5
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn sidecar_round_trips_and_notices_changed_inputs() {
+    let dir = std::env::temp_dir().join(format!("bitrot-provenance-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("in.mp4");
+    let output = dir.join("out.mp4");
+    fs::write(&input, vec![7u8; 3 << 20]).unwrap();
+    let spec = spec(&input, &output);
+
+    let metadata = container_metadata(&spec);
+    assert_eq!(metadata[0].0, "comment");
+    assert!(metadata[0].1.contains("\"seed\":42"));
+    assert!(metadata[0].1.contains("\"trimStartSeconds\":1.5"));
+    assert!(!metadata[0].1.contains("in.mp4"));
+
+    let failing_ffmpeg = || -> Result<std::process::Command, BitrotError> {
+      Err(BitrotError::internal("no ffmpeg in tests"))
+    };
+    let provenance = RenderProvenance::capture(&failing_ffmpeg, &spec).unwrap();
+    let path = provenance.save().unwrap();
+    assert!(path.ends_with("out.mp4.bitrot.json"));
+    let loaded = RenderProvenance::load(&path).unwrap();
+    assert_eq!(loaded.input, provenance.input);
+    assert!(loaded.ffmpeg_version.is_none());
+    assert!(loaded.input_mismatch().is_none());
+
+    let mut changed = vec![7u8; 3 << 20];
+    changed[(3 << 20) - 1] = 8;
+    fs::write(&input, changed).unwrap();
+    assert!(loaded.input_mismatch().is_some());
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
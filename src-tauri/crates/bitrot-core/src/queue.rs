@@ -0,0 +1,314 @@
+// Persistent render queue: ordered job specs with status, saved as JSON so the queue
+// survives restarts. Scheduling is pure bookkeeping; embedders run the jobs it hands out.
+use std::{
+  fs,
+  path::Path,
+  time::{SystemTime, UNIX_EPOCH}
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BitrotError;
+use crate::job::JobSpec;
+
+pub const MAX_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueueStatus {
+  Queued,
+  Running,
+  Completed,
+  Failed,
+  Canceled
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueEntry {
+  pub id: String,
+  pub spec: JobSpec,
+  pub status: QueueStatus,
+  pub error: Option<BitrotError>,
+  pub attempts: u32,
+  pub added_at: u64,
+  pub finished_at: Option<u64>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderQueue {
+  pub concurrency: usize,
+  pub entries: Vec<QueueEntry>,
+  next_id: u64
+}
+
+impl Default for RenderQueue {
+  fn default() -> Self {
+    Self {
+      concurrency: 1,
+      entries: Vec::new(),
+      next_id: 1
+    }
+  }
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_secs())
+    .unwrap_or(0)
+}
+
+impl RenderQueue {
+  // A missing file is an empty queue. Entries that were running when the app went
+  // away are queued again, since their partial output was never finished.
+  pub fn load(path: &Path) -> Result<Self, BitrotError> {
+    let raw = match fs::read_to_string(path) {
+      Ok(raw) => raw,
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+      Err(error) => return Err(BitrotError::io("Failed to read render queue", error))
+    };
+    let mut queue: Self = serde_json::from_str(&raw)
+      .map_err(|error| BitrotError::invalid(format!("Invalid render queue file: {error}")))?;
+    for entry in &mut queue.entries {
+      if entry.status == QueueStatus::Running {
+        entry.status = QueueStatus::Queued;
+      }
+    }
+    queue.concurrency = queue.concurrency.clamp(1, MAX_CONCURRENCY);
+    Ok(queue)
+  }
+
+  // Writes through a temp file so a crash mid-save never leaves a truncated queue.
+  pub fn save(&self, path: &Path) -> Result<(), BitrotError> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)
+        .map_err(|error| BitrotError::io("Failed to create queue directory", error))?;
+    }
+    let raw = serde_json::to_string_pretty(self).map_err(|error| {
+      BitrotError::internal(format!("Failed to serialize render queue: {error}"))
+    })?;
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, raw)
+      .map_err(|error| BitrotError::io("Failed to write render queue", error))?;
+    fs::rename(&temp_path, path).map_err(|error| BitrotError::io("Failed to save render queue", error))
+  }
+
+  pub fn add(&mut self, spec: JobSpec) -> String {
+    let id = format!("queue-{}", self.next_id);
+    self.next_id += 1;
+    self.entries.push(QueueEntry {
+      id: id.clone(),
+      spec,
+      status: QueueStatus::Queued,
+      error: None,
+      attempts: 0,
+      added_at: unix_now(),
+      finished_at: None
+    });
+    id
+  }
+
+  pub fn set_concurrency(&mut self, concurrency: usize) {
+    self.concurrency = concurrency.clamp(1, MAX_CONCURRENCY);
+  }
+
+  fn position(&self, id: &str) -> Result<usize, BitrotError> {
+    self
+      .entries
+      .iter()
+      .position(|entry| entry.id == id)
+      .ok_or_else(|| BitrotError::not_found(format!("Unknown queue entry {id}")))
+  }
+
+  // Moves an entry to `index` (clamped), shifting the others; order decides what runs next.
+  pub fn move_entry(&mut self, id: &str, index: usize) -> Result<(), BitrotError> {
+    let from = self.position(id)?;
+    let entry = self.entries.remove(from);
+    let to = index.min(self.entries.len());
+    self.entries.insert(to, entry);
+    Ok(())
+  }
+
+  // Puts a finished, failed or canceled entry back in line.
+  pub fn retry(&mut self, id: &str) -> Result<(), BitrotError> {
+    let index = self.position(id)?;
+    let entry = &mut self.entries[index];
+    if matches!(entry.status, QueueStatus::Queued | QueueStatus::Running) {
+      return Err(BitrotError::invalid("Queue entry is already queued or running."));
+    }
+    entry.status = QueueStatus::Queued;
+    entry.error = None;
+    entry.finished_at = None;
+    Ok(())
+  }
+
+  // A queued entry is canceled on the spot; true means it is running and the caller has to
+  // stop the job, which `finish` then records as canceled.
+  pub fn cancel(&mut self, id: &str) -> Result<bool, BitrotError> {
+    let index = self.position(id)?;
+    let entry = &mut self.entries[index];
+    match entry.status {
+      QueueStatus::Running => Ok(true),
+      QueueStatus::Queued => {
+        entry.status = QueueStatus::Canceled;
+        entry.finished_at = Some(unix_now());
+        Ok(false)
+      }
+      _ => Err(BitrotError::invalid("Queue entry is not queued or running."))
+    }
+  }
+
+  // Running entries are canceled first and removed once they have stopped.
+  pub fn remove(&mut self, id: &str) -> Result<QueueEntry, BitrotError> {
+    let index = self.position(id)?;
+    if self.entries[index].status == QueueStatus::Running {
+      return Err(BitrotError::invalid("Cancel the running entry before removing it."));
+    }
+    Ok(self.entries.remove(index))
+  }
+
+  pub fn running_count(&self) -> usize {
+    self
+      .entries
+      .iter()
+      .filter(|entry| entry.status == QueueStatus::Running)
+      .count()
+  }
+
+  // Claims the first queued entry if a slot is free.
+  pub fn start_next(&mut self) -> Option<QueueEntry> {
+    if self.running_count() >= self.concurrency {
+      return None;
+    }
+    let entry = self
+      .entries
+      .iter_mut()
+      .find(|entry| entry.status == QueueStatus::Queued)?;
+    entry.status = QueueStatus::Running;
+    entry.attempts += 1;
+    Some(entry.clone())
+  }
+
+  // Records a job result.
+  pub fn finish(&mut self, id: &str, result: &Result<(), BitrotError>) {
+    let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) else {
+      return;
+    };
+    let (status, error) = match result {
+      Ok(()) => (QueueStatus::Completed, None),
+      Err(error) if error.is_canceled() => (QueueStatus::Canceled, None),
+      Err(error) => (QueueStatus::Failed, Some(error.clone()))
+    };
+    entry.status = status;
+    entry.error = error;
+    entry.finished_at = Some(unix_now());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::checkpoint::{checkpoint_dir, resumable_checkpoint, Checkpoint};
+  use crate::error::ErrorCode;
+
+  fn spec(output: &str) -> JobSpec {
+    serde_json::from_value(serde_json::json!({
+      "mode": "vaporwave",
+      "config": {
+        "intensity": 100.0,
+        "blackThreshold": 20,
+        "cyanMax": 60,
+        "magentaMax": 110,
+        "purpleMax": 160,
+        "tealMax": 210,
+        "whiteThreshold": 240
+      },
+      "input": "in.mp4",
+      "output": output
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn queue_respects_concurrency_and_order() {
+    let mut queue = RenderQueue::default();
+    let first = queue.add(spec("a.mp4"));
+    let second = queue.add(spec("b.mp4"));
+    let third = queue.add(spec("c.mp4"));
+    queue.move_entry(&third, 0).unwrap();
+    queue.set_concurrency(2);
+
+    assert_eq!(queue.start_next().unwrap().id, third);
+    assert_eq!(queue.start_next().unwrap().id, first);
+    assert!(queue.start_next().is_none());
+    assert!(queue.remove(&first).is_err());
+    assert!(queue.cancel(&first).unwrap());
+
+    queue.finish(&third, &Err(BitrotError::new(ErrorCode::EncodeFailed, "boom")));
+    assert_eq!(queue.entries[0].status, QueueStatus::Failed);
+    assert_eq!(queue.start_next().unwrap().id, second);
+
+    queue.finish(&first, &Ok(()));
+    queue.finish(&second, &Err(BitrotError::canceled()));
+    assert!(queue.retry(&first).is_ok());
+    assert!(queue.start_next().is_some_and(|entry| entry.id == first && entry.attempts == 2));
+    assert!(queue.retry(&first).is_err());
+    assert_eq!(queue.remove(&second).unwrap().status, QueueStatus::Canceled);
+
+    let fourth = queue.add(spec("d.mp4"));
+    assert!(!queue.cancel(&fourth).unwrap());
+    assert_eq!(queue.entries.last().unwrap().status, QueueStatus::Canceled);
+    assert!(queue.cancel(&fourth).is_err());
+  }
+
+  #[test]
+  fn reloaded_queue_requeues_interrupted_entries() {
+    let path = std::env::temp_dir().join(format!("bitrot-queue-test-{}.json", std::process::id()));
+    let mut queue = RenderQueue::default();
+    let id = queue.add(spec("a.mp4"));
+    queue.add(spec("b.mp4"));
+    queue.start_next();
+    queue.save(&path).unwrap();
+
+    let mut reloaded = RenderQueue::load(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(reloaded.entries.len(), 2);
+    assert_eq!(reloaded.entries[0].status, QueueStatus::Queued);
+    assert_eq!(reloaded.entries[0].spec.output, "a.mp4");
+    assert_eq!(reloaded.start_next().unwrap().id, id);
+    // New ids keep counting from where the saved queue left off.
+    assert_eq!(reloaded.add(spec("c.mp4")), "queue-3");
+  }
+
+  #[test]
+  fn restarted_and_retried_entries_resume_their_checkpoint() {
+    let dir = std::env::temp_dir().join(format!("bitrot-queue-resume-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("a.mp4").to_string_lossy().into_owned();
+    let path = dir.join("render-queue.json");
+    let mut queue = RenderQueue::default();
+    let id = queue.add(spec(&output));
+    let running = queue.start_next().unwrap();
+    // The render got through some segments before the app went away.
+    let checkpoint_path = checkpoint_dir(&output, running.spec.mode.tag());
+    Checkpoint::start(checkpoint_path.clone(), running.spec.clone(), 24.0).unwrap();
+    queue.save(&path).unwrap();
+
+    let mut reloaded = RenderQueue::load(&path).unwrap();
+    let restarted = reloaded.start_next().unwrap();
+    assert_eq!(resumable_checkpoint(&restarted.spec), Some(checkpoint_path.clone()));
+
+    reloaded.finish(&id, &Err(BitrotError::new(ErrorCode::EncodeFailed, "boom")));
+    reloaded.retry(&id).unwrap();
+    let retried = reloaded.start_next().unwrap();
+    assert_eq!(resumable_checkpoint(&retried.spec), Some(checkpoint_path));
+
+    // A different spec for the same output starts fresh.
+    let mut changed = spec(&output);
+    changed.input = "other.mp4".into();
+    assert_eq!(resumable_checkpoint(&changed), None);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+}
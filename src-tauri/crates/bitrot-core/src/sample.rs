@@ -0,0 +1,163 @@
+// Channel sample types the frame processors run on: 8-bit `rgba` or 16-bit `rgba64le`.
+// Effect math works on 0..255 levels as f32, so 8-bit output is unchanged while 16-bit
+// frames keep their extra precision through blends and grades.
+
+pub trait Sample: Copy + Default + PartialEq + PartialOrd + Send + Sync + Into<u32> + 'static {
+  // Raw pipe format for RGBA frames at this depth.
+  const PIX_FMT: &'static str;
+  // Largest sample value (255 or 65535).
+  const MAX: u32;
+
+  // Value on the 8-bit 0..255 scale; fractional for deeper samples.
+  fn level(self) -> f32;
+
+  // Rounds and clamps a 0..255 level back to a sample.
+  fn from_level(level: f32) -> Self;
+
+  // An 8-bit value (threshold, palette colour) scaled to this depth.
+  fn from_u8(value: u8) -> Self;
+
+  // Nearest 8-bit value, for luma maps and previews.
+  fn to_u8(self) -> u8;
+
+  // Clamps an integer already on this depth's scale.
+  fn from_u32(value: u32) -> Self;
+
+  // The samples as pipe bytes when they already are bytes; None means convert.
+  fn bytes(samples: &[Self]) -> Option<&[u8]>;
+
+  fn bytes_mut(samples: &mut [Self]) -> Option<&mut [u8]>;
+
+  // Little-endian pipe bytes into samples (`bytes` is `samples.len() * size_of::<Self>()` long).
+  fn read_le(bytes: &[u8], samples: &mut [Self]);
+
+  fn write_le(samples: &[Self], bytes: &mut Vec<u8>);
+}
+
+impl Sample for u8 {
+  const PIX_FMT: &'static str = "rgba";
+  const MAX: u32 = 255;
+
+  fn level(self) -> f32 {
+    self as f32
+  }
+
+  fn from_level(level: f32) -> Self {
+    level.round().clamp(0.0, 255.0) as u8
+  }
+
+  fn from_u8(value: u8) -> Self {
+    value
+  }
+
+  fn to_u8(self) -> u8 {
+    self
+  }
+
+  fn from_u32(value: u32) -> Self {
+    value.min(255) as u8
+  }
+
+  fn bytes(samples: &[Self]) -> Option<&[u8]> {
+    Some(samples)
+  }
+
+  fn bytes_mut(samples: &mut [Self]) -> Option<&mut [u8]> {
+    Some(samples)
+  }
+
+  fn read_le(bytes: &[u8], samples: &mut [Self]) {
+    samples.copy_from_slice(bytes);
+  }
+
+  fn write_le(samples: &[Self], bytes: &mut Vec<u8>) {
+    bytes.clear();
+    bytes.extend_from_slice(samples);
+  }
+}
+
+impl Sample for u16 {
+  const PIX_FMT: &'static str = "rgba64le";
+  const MAX: u32 = 65535;
+
+  // 257 maps 255 onto 65535 exactly, so 8-bit constants land on full-range values.
+  fn level(self) -> f32 {
+    self as f32 / 257.0
+  }
+
+  fn from_level(level: f32) -> Self {
+    (level * 257.0).round().clamp(0.0, 65535.0) as u16
+  }
+
+  fn from_u8(value: u8) -> Self {
+    u16::from(value) * 257
+  }
+
+  fn to_u8(self) -> u8 {
+    ((u32::from(self) * 255 + 32767) / 65535) as u8
+  }
+
+  fn from_u32(value: u32) -> Self {
+    value.min(65535) as u16
+  }
+
+  fn bytes(_samples: &[Self]) -> Option<&[u8]> {
+    None
+  }
+
+  fn bytes_mut(_samples: &mut [Self]) -> Option<&mut [u8]> {
+    None
+  }
+
+  fn read_le(bytes: &[u8], samples: &mut [Self]) {
+    for (sample, pair) in samples.iter_mut().zip(bytes.chunks_exact(2)) {
+      *sample = u16::from_le_bytes([pair[0], pair[1]]);
+    }
+  }
+
+  fn write_le(samples: &[Self], bytes: &mut Vec<u8>) {
+    bytes.clear();
+    bytes.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+  }
+}
+
+// Linear mix of two samples; `mix` 0 keeps `a`, 1 takes `b`.
+pub(crate) fn blend_channel<S: Sample>(a: S, b: S, mix: f32) -> S {
+  let inv = 1.0 - mix;
+  S::from_level((a.level() * inv) + (b.level() * mix))
+}
+
+// 8-bit view of a frame for reporters and previews; 8-bit frames are borrowed as-is.
+pub fn to_rgba8<'a, S: Sample>(samples: &'a [S], scratch: &'a mut Vec<u8>) -> &'a [u8] {
+  if let Some(bytes) = S::bytes(samples) {
+    return bytes;
+  }
+  scratch.clear();
+  scratch.extend(samples.iter().map(|sample| sample.to_u8()));
+  scratch
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sixteen_bit_samples_keep_precision_and_round_trip() {
+    assert_eq!(u16::from_u8(255), 65535);
+    assert_eq!(u16::from_level(u16::from_u8(37).level()), u16::from_u8(37));
+    assert_eq!(u16::from_u8(200).to_u8(), 200);
+
+    // Two 16-bit values inside one 8-bit step stay apart after a blend.
+    let (low, high) = (0x1200u16, 0x1280u16);
+    assert_eq!(low.to_u8(), high.to_u8());
+    assert!(blend_channel(low, high, 0.5) > low);
+    assert_eq!(blend_channel(10u8, 20u8, 0.5), 15);
+
+    let mut bytes = Vec::new();
+    u16::write_le(&[0x0102, 0xfffe], &mut bytes);
+    assert_eq!(bytes, [0x02, 0x01, 0xfe, 0xff]);
+    let mut samples = [0u16; 2];
+    u16::read_le(&bytes, &mut samples);
+    assert_eq!(samples, [0x0102, 0xfffe]);
+  }
+}
@@ -0,0 +1,331 @@
+// Numbered image sequences (`frame_%05d.png`) as native job inputs and outputs.
+// Inputs are read through ffmpeg's image2 demuxer at the job's fps; outputs are written
+// by each segment's encoder straight into the pattern, so no temp video or mux step runs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::BitrotError;
+use crate::pipeline::NativeEncoding;
+
+// A printf-style pattern split around its frame number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+  dir: PathBuf,
+  prefix: String,
+  // Zero-padded width from `%05d`; `%d` has none.
+  width: Option<usize>,
+  suffix: String
+}
+
+impl Pattern {
+  fn parse(path: &str) -> Option<Self> {
+    let path = Path::new(path.trim());
+    let name = path.file_name()?.to_str()?;
+    let start = name.find('%')?;
+    let rest = &name[start + 1..];
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if rest.as_bytes().get(digits) != Some(&b'd') {
+      return None;
+    }
+    let width = rest[..digits].trim_start_matches('0').parse::<usize>().ok();
+    let suffix = &rest[digits + 1..];
+    if suffix.contains('%') {
+      return None;
+    }
+    let dir = match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+      _ => PathBuf::from(".")
+    };
+    Some(Self {
+      dir,
+      prefix: name[..start].into(),
+      width,
+      suffix: suffix.into()
+    })
+  }
+
+  // Frame number of a file in the pattern's directory, if it belongs to the sequence.
+  fn number_of(&self, file_name: &str) -> Option<u64> {
+    let digits = file_name
+      .strip_prefix(&self.prefix)?
+      .strip_suffix(&self.suffix)?;
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+      return None;
+    }
+    if self.width.is_some_and(|width| digits.len() < width) {
+      return None;
+    }
+    digits.parse().ok()
+  }
+
+  fn frame_path(&self, number: u64) -> PathBuf {
+    let width = self.width.unwrap_or(0);
+    self
+      .dir
+      .join(format!("{}{number:0width$}{}", self.prefix, self.suffix))
+  }
+
+  // Frame numbers present on disk, unsorted.
+  fn numbers_on_disk(&self) -> Vec<u64> {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return Vec::new();
+    };
+    entries
+      .filter_map(Result::ok)
+      .filter_map(|entry| entry.file_name().to_str().and_then(|name| self.number_of(name)))
+      .collect()
+  }
+}
+
+// True for paths like `frame_%05d.png`.
+pub fn is_sequence_pattern(path: &str) -> bool {
+  Pattern::parse(path).is_some()
+}
+
+// Lowest frame number on disk; image2 only probes a few numbers from 0 on its own.
+pub fn find_start_number(pattern: &str) -> Result<u64, BitrotError> {
+  Pattern::parse(pattern)
+    .and_then(|parsed| parsed.numbers_on_disk().into_iter().min())
+    .ok_or_else(|| BitrotError::invalid(format!("No frames match the image sequence {pattern}.")))
+}
+
+// First frame's file, for fingerprinting a sequence input.
+pub fn first_frame_path(pattern: &str) -> Option<PathBuf> {
+  let parsed = Pattern::parse(pattern)?;
+  let start = parsed.numbers_on_disk().into_iter().min()?;
+  Some(parsed.frame_path(start))
+}
+
+// Demuxer options that go before `-i`; empty for anything that is not a pattern.
+pub fn input_options(input_path: &str, fps: f64) -> Result<Vec<String>, BitrotError> {
+  if !is_sequence_pattern(input_path) {
+    return Ok(Vec::new());
+  }
+  let start_number = find_start_number(input_path)?;
+  Ok(vec![
+    "-framerate".into(),
+    format!("{fps:.3}"),
+    "-start_number".into(),
+    start_number.to_string(),
+    "-f".into(),
+    "image2".into()
+  ])
+}
+
+// Removes frames numbered `from` and up; how a failed render drops its unfinished tail.
+pub fn remove_frames_from(pattern: &str, from: u64) {
+  let Some(parsed) = Pattern::parse(pattern) else {
+    return;
+  };
+  for number in parsed.numbers_on_disk() {
+    if number >= from {
+      let _ = fs::remove_file(parsed.frame_path(number));
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceFormat {
+  Png,
+  Tiff,
+  Jpeg
+}
+
+impl SequenceFormat {
+  fn from_pattern(pattern: &str) -> Option<Self> {
+    let extension = Path::new(pattern).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+      "png" => Some(Self::Png),
+      "tif" | "tiff" => Some(Self::Tiff),
+      "jpg" | "jpeg" => Some(Self::Jpeg),
+      _ => None
+    }
+  }
+
+  pub fn codec(self) -> &'static str {
+    match self {
+      Self::Png => "png",
+      Self::Tiff => "tiff",
+      Self::Jpeg => "mjpeg"
+    }
+  }
+
+  // 8-bit default first, then the 16-bit one used for `highBitDepth` renders.
+  fn pixel_formats(self, alpha: bool) -> &'static [&'static str] {
+    match (self, alpha) {
+      (Self::Png, false) => &["rgb24", "rgb48be"],
+      (Self::Png, true) => &["rgba", "rgba64be"],
+      (Self::Tiff, false) => &["rgb24", "rgb48le"],
+      (Self::Tiff, true) => &["rgba", "rgba64le"],
+      (Self::Jpeg, false) => &["yuvj444p"],
+      (Self::Jpeg, true) => &[]
+    }
+  }
+}
+
+// Where a native job writes its frames when the output path is a pattern.
+#[derive(Debug, Clone)]
+pub struct OutputSequence {
+  pub pattern: String,
+  pub format: SequenceFormat,
+  // Number of the first rendered frame: the input's start plus the trim offset.
+  pub first_number: u64,
+  pub pixel_format: String
+}
+
+impl OutputSequence {
+  // None when `output_path` is a regular file; errors for unsupported extensions or
+  // pixel formats.
+  pub fn for_output(output_path: &str, encoding: &NativeEncoding) -> Result<Option<Self>, BitrotError> {
+    if !is_sequence_pattern(output_path) {
+      return Ok(None);
+    }
+    let format = SequenceFormat::from_pattern(output_path).ok_or_else(|| {
+      BitrotError::invalid(format!(
+        "Image sequence output {output_path} needs a .png, .tif/.tiff or .jpg extension."
+      ))
+    })?;
+    if encoding.target_size_mb.is_some() {
+      return Err(BitrotError::invalid("Image sequences have no target size; render to a video file."));
+    }
+    let formats = format.pixel_formats(encoding.alpha);
+    if formats.is_empty() {
+      return Err(BitrotError::invalid("JPEG sequences cannot keep alpha; use PNG or TIFF."));
+    }
+    let pixel_format = match encoding.pixel_format.as_deref() {
+      Some(pixel_format) if formats.contains(&pixel_format) => pixel_format,
+      Some(pixel_format) => {
+        return Err(BitrotError::invalid(format!(
+          "{} sequences cannot use {pixel_format}. Use one of: {}.",
+          format.codec(),
+          formats.join(", ")
+        )));
+      }
+      None if encoding.high_bit_depth => formats.last().copied().unwrap_or(formats[0]),
+      None => formats[0]
+    };
+    Ok(Some(Self {
+      pattern: output_path.trim().into(),
+      format,
+      first_number: 0,
+      pixel_format: pixel_format.into()
+    }))
+  }
+
+  // Numbers frames from the input's first frame plus the trimmed-off frame count.
+  pub fn starting_at(mut self, input_start: u64, trim_start_seconds: f64, fps: f64) -> Self {
+    let trimmed = (trim_start_seconds.max(0.0) * fps).round() as u64;
+    self.first_number = input_start + trimmed;
+    self
+  }
+}
+
+// Encodes raw frames from stdin into the pattern, numbered from `start_number`.
+pub fn build_sequence_encode_args(
+  width: u32,
+  height: u32,
+  fps: f64,
+  sequence: &OutputSequence,
+  start_number: u64,
+  encoding: &NativeEncoding,
+  pixel_format: &str
+) -> Vec<String> {
+  let mut args = vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-f".into(),
+    "rawvideo".into(),
+    "-pix_fmt".into(),
+    pixel_format.into(),
+    "-s".into(),
+    format!("{width}x{height}"),
+    "-r".into(),
+    format!("{fps:.3}"),
+    "-i".into(),
+    "-".into(),
+    "-c:v".into(),
+    sequence.format.codec().into()
+  ];
+  if sequence.format == SequenceFormat::Jpeg {
+    args.extend(["-q:v".into(), "2".into()]);
+  }
+  args.extend([
+    "-pix_fmt".into(),
+    sequence.pixel_format.clone(),
+    "-start_number".into(),
+    start_number.to_string(),
+    "-f".into(),
+    "image2".into()
+  ]);
+  args.extend(encoding.extra_encode_args.iter().cloned());
+  args.push(sequence.pattern.clone());
+  args
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn patterns_match_numbered_frames_on_disk() {
+    let dir = std::env::temp_dir().join(format!("bitrot-sequence-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for number in [1001, 1002, 1003] {
+      fs::write(dir.join(format!("shot_{number:05}.png")), b"png").unwrap();
+    }
+    fs::write(dir.join("shot_12.png"), b"too short").unwrap();
+    fs::write(dir.join("shot_01004.tif"), b"other extension").unwrap();
+    let pattern = dir.join("shot_%05d.png").to_string_lossy().into_owned();
+
+    assert!(is_sequence_pattern(&pattern));
+    assert!(!is_sequence_pattern("clip.mp4"));
+    assert!(!is_sequence_pattern("100%.png"));
+    assert_eq!(find_start_number(&pattern).unwrap(), 1001);
+    assert_eq!(first_frame_path(&pattern), Some(dir.join("shot_01001.png")));
+    let options = input_options(&pattern, 24.0).unwrap();
+    assert_eq!(options[..4], ["-framerate", "24.000", "-start_number", "1001"]);
+
+    remove_frames_from(&pattern, 1002);
+    assert_eq!(find_start_number(&pattern).unwrap(), 1001);
+    assert!(!dir.join("shot_01002.png").exists());
+    assert!(dir.join("shot_12.png").exists());
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn output_sequences_number_from_the_trim_start() {
+    let encoding: NativeEncoding = serde_json::from_value(serde_json::json!({
+      "encoder": "libx264",
+      "preset": "medium",
+      "crf": null,
+      "cq": null,
+      "maxBitrateKbps": null,
+      "targetBitrateKbps": null,
+      "vp9Deadline": null,
+      "vp9CpuUsed": null,
+      "format": "mp4",
+      "audioEnabled": false,
+      "audioCodec": null,
+      "audioBitrateKbps": null,
+      "highBitDepth": true
+    }))
+    .unwrap();
+    assert!(OutputSequence::for_output("out.mp4", &encoding).unwrap().is_none());
+    assert!(OutputSequence::for_output("out_%04d.exr", &encoding).is_err());
+
+    let sequence = OutputSequence::for_output("out_%04d.tiff", &encoding)
+      .unwrap()
+      .unwrap()
+      .starting_at(1001, 2.0, 24.0);
+    assert_eq!(sequence.first_number, 1049);
+    assert_eq!(sequence.pixel_format, "rgb48le");
+
+    let args = build_sequence_encode_args(64, 36, 24.0, &sequence, 1049 + 48, &encoding, "rgba64le");
+    let start = args.iter().position(|arg| arg == "-start_number").unwrap() + 1;
+    assert_eq!(args[start], "1097");
+    assert_eq!(args.last().unwrap(), "out_%04d.tiff");
+  }
+}
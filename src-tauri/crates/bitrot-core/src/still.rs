@@ -0,0 +1,330 @@
+// Still image jobs: one photo decoded at full resolution, run through a mode at a chosen
+// frame index and written as PNG, JPEG, WebP or TIFF. No preview size cap applies.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::error::{BitrotError, ErrorCode};
+use crate::events::JobStage;
+use crate::pipeline::{cleanup_file, FfmpegLauncher};
+use crate::probe::probe_video;
+use crate::progress::JobReporter;
+use crate::sample::Sample;
+use crate::toolchain::toolchain_report;
+
+// Still image jobs have no timeline, so automation and windows read time at this rate
+// unless the spec sets `fps`.
+pub const STILL_FPS: f64 = 30.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StillOptions {
+  // Frame the effect renders at; seeded and animated modes change with it.
+  #[serde(default)]
+  pub frame_index: u64,
+  // JPEG/WebP quality 1..100.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub quality: Option<u32>,
+  // Decode and process at 16 bits per channel; PNG and TIFF keep the extra depth.
+  #[serde(default)]
+  pub high_bit_depth: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StillFormat {
+  Png,
+  Jpeg,
+  Webp,
+  Tiff
+}
+
+impl StillFormat {
+  pub fn from_path(path: &str) -> Option<Self> {
+    let extension = Path::new(path.trim()).extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+      "png" => Some(Self::Png),
+      "jpg" | "jpeg" => Some(Self::Jpeg),
+      "webp" => Some(Self::Webp),
+      "tif" | "tiff" => Some(Self::Tiff),
+      _ => None
+    }
+  }
+
+  pub fn codec(self) -> &'static str {
+    match self {
+      Self::Png => "png",
+      Self::Tiff => "tiff",
+      Self::Jpeg => "mjpeg",
+      Self::Webp => "libwebp"
+    }
+  }
+}
+
+// True when a job input is a single image rather than a video or sequence pattern.
+pub fn is_still_image(path: &str) -> bool {
+  !path.contains('%') && StillFormat::from_path(path).is_some()
+}
+
+impl StillOptions {
+  pub fn validate(&self, output_path: &str) -> Result<(), BitrotError> {
+    if StillFormat::from_path(output_path).is_none() {
+      return Err(BitrotError::invalid(format!(
+        "Still image output {output_path} needs a .png, .jpg, .webp or .tif extension."
+      )));
+    }
+    if self.quality.is_some_and(|quality| !(1..=100).contains(&quality)) {
+      return Err(BitrotError::invalid("Still image quality must be between 1 and 100."));
+    }
+    Ok(())
+  }
+}
+
+// Decodes the first frame of `input_path` as raw RGBA at its own size.
+pub fn build_still_decode_args(input_path: &str, pixel_format: &str) -> Vec<String> {
+  vec![
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-i".into(),
+    input_path.into(),
+    "-frames:v".into(),
+    "1".into(),
+    "-an".into(),
+    "-f".into(),
+    "rawvideo".into(),
+    "-pix_fmt".into(),
+    pixel_format.into(),
+    "-".into()
+  ]
+}
+
+pub fn build_still_encode_args(
+  width: u32,
+  height: u32,
+  output_path: &str,
+  options: &StillOptions,
+  pixel_format: &str
+) -> Result<Vec<String>, BitrotError> {
+  let format = StillFormat::from_path(output_path)
+    .ok_or_else(|| BitrotError::invalid(format!("Unsupported still image output {output_path}.")))?;
+  let deep = options.high_bit_depth;
+  let quality = options.quality.unwrap_or(90).clamp(1, 100);
+  let mut args = vec![
+    "-y".into(),
+    "-hide_banner".into(),
+    "-loglevel".into(),
+    "error".into(),
+    "-f".into(),
+    "rawvideo".into(),
+    "-pix_fmt".into(),
+    pixel_format.into(),
+    "-s".into(),
+    format!("{width}x{height}"),
+    "-i".into(),
+    "-".into(),
+    "-frames:v".into(),
+    "1".into()
+  ];
+  let (output_format, muxer) = match format {
+    StillFormat::Png => (if deep { "rgba64be" } else { "rgba" }, "image2"),
+    StillFormat::Tiff => (if deep { "rgba64le" } else { "rgba" }, "image2"),
+    StillFormat::Jpeg => ("yuvj444p", "image2"),
+    StillFormat::Webp => ("yuva420p", "webp")
+  };
+  args.extend(["-c:v".into(), format.codec().into(), "-pix_fmt".into(), output_format.into()]);
+  match format {
+    // mjpeg's qscale runs 2 (best) to 31.
+    StillFormat::Jpeg => {
+      let qscale = 2 + (100 - quality) * 29 / 99;
+      args.extend(["-q:v".into(), qscale.to_string()]);
+    }
+    StillFormat::Webp => args.extend(["-quality".into(), quality.to_string()]),
+    StillFormat::Png | StillFormat::Tiff => {}
+  }
+  args.extend(["-f".into(), muxer.into()]);
+  if muxer == "image2" {
+    args.extend(["-update".into(), "1".into()]);
+  }
+  args.push(output_path.into());
+  Ok(args)
+}
+
+// Everything a still job needs besides the effect; the effect already carries the
+// spec's automation, mask and windows.
+pub struct StillRequest<'a> {
+  pub input_path: &'a str,
+  pub output_path: &'a str,
+  // Spec geometry, probed when missing.
+  pub size: Option<(u32, u32)>,
+  pub options: &'a StillOptions
+}
+
+// Decodes, processes and writes one image; blocking, like `run_native_job`.
+pub fn run_still_job<E: NativeEffect>(
+  ffmpeg: FfmpegLauncher,
+  ffprobe: FfmpegLauncher,
+  request: &StillRequest,
+  effect: &E,
+  reporter: &dyn JobReporter
+) -> Result<(), BitrotError> {
+  request.options.validate(request.output_path)?;
+  if let Some(format) = StillFormat::from_path(request.output_path) {
+    toolchain_report(ffmpeg)?.require_encoder(format.codec())?;
+  }
+  reporter.log(&format!("{} image started.", E::LABEL));
+  reporter.stage(JobStage::Render);
+  let (width, height) = match request.size {
+    Some(size) => size,
+    None => {
+      let probed = probe_video(ffprobe, request.input_path, &[])?;
+      (probed.width, probed.height)
+    }
+  };
+  if width == 0 || height == 0 {
+    return Err(BitrotError::invalid("Invalid image dimensions."));
+  }
+  let result = if request.options.high_bit_depth {
+    render_still::<E, u16>(ffmpeg, request, (width, height), effect)
+  } else {
+    render_still::<E, u8>(ffmpeg, request, (width, height), effect)
+  };
+  match &result {
+    Ok(()) => reporter.log(&format!(
+      "{} image completed ({width}x{height}, frame {}).",
+      E::LABEL,
+      request.options.frame_index
+    )),
+    Err(_) => cleanup_file(Path::new(request.output_path))
+  }
+  result
+}
+
+fn render_still<E: NativeEffect, S: Sample>(
+  ffmpeg: FfmpegLauncher,
+  request: &StillRequest,
+  (width, height): (u32, u32),
+  effect: &E
+) -> Result<(), BitrotError> {
+  let output = ffmpeg()?
+    .args(build_still_decode_args(request.input_path, S::PIX_FMT))
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start the image decoder: {error}"))
+    })?;
+  let samples = (width as usize) * (height as usize) * 4;
+  let expected = samples * std::mem::size_of::<S>();
+  if !output.status.success() || output.stdout.len() < expected {
+    return Err(
+      BitrotError::new(
+        ErrorCode::DecodeFailed,
+        format!("Could not decode {} as a {width}x{height} image.", request.input_path)
+      )
+      .with_stderr(&String::from_utf8_lossy(&output.stderr))
+    );
+  }
+  let mut frame = vec![S::default(); samples];
+  S::read_le(&output.stdout[..expected], &mut frame);
+
+  let frame_index = request.options.frame_index;
+  let processed = process_still(effect, &frame, width as usize, height as usize, frame_index);
+  let mut bytes = Vec::new();
+  S::write_le(&processed, &mut bytes);
+
+  let mut child = ffmpeg()?
+    .args(build_still_encode_args(
+      width,
+      height,
+      request.output_path,
+      request.options,
+      S::PIX_FMT
+    )?)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start the image encoder: {error}"))
+    })?;
+  // A failed write shows up as a failed exit below, with ffmpeg's reason on stderr.
+  if let Some(mut stdin) = child.stdin.take() {
+    let _ = stdin.write_all(&bytes);
+  }
+  let encoded = child.wait_with_output().map_err(|error| {
+    BitrotError::new(ErrorCode::EncodeFailed, format!("Image encoder failed: {error}"))
+  })?;
+  if !encoded.status.success() {
+    return Err(
+      BitrotError::new(
+        ErrorCode::EncodeFailed,
+        format!("Image encoder failed with exit code {}", encoded.status.code().unwrap_or(-1))
+      )
+      .with_stderr(&String::from_utf8_lossy(&encoded.stderr))
+    );
+  }
+  Ok(())
+}
+
+// Runs the effect at `frame_index`, replaying the image through its warmup frames first
+// so temporal modes see the same history a video render would give a static shot.
+pub fn process_still<E: NativeEffect, S: Sample>(
+  effect: &E,
+  frame: &[S],
+  width: usize,
+  height: usize,
+  frame_index: u64
+) -> Vec<S> {
+  let mut workspace = effect.workspace::<S>(width, height);
+  let warmup_start = frame_index - effect.warmup_frames().min(frame_index);
+  for index in warmup_start..frame_index {
+    effect.process_frame(frame, &mut workspace, index);
+  }
+  effect.process_frame(frame, &mut workspace, frame_index).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::modes::block_shift::BlockShiftConfig;
+
+  #[test]
+  fn still_args_follow_the_output_format() {
+    assert!(is_still_image("photo.JPG"));
+    assert!(!is_still_image("clip.mp4"));
+    assert!(!is_still_image("frame_%04d.png"));
+
+    let deep = StillOptions { high_bit_depth: true, ..StillOptions::default() };
+    let png = build_still_encode_args(4000, 3000, "out.png", &deep, u16::PIX_FMT).unwrap();
+    assert_eq!(png[7], "rgba64le");
+    assert_eq!(png[9], "4000x3000");
+    assert!(png.windows(2).any(|pair| pair == ["-pix_fmt", "rgba64be"]));
+
+    let best = StillOptions { quality: Some(100), ..StillOptions::default() };
+    let jpeg = build_still_encode_args(64, 36, "out.jpg", &best, "rgba").unwrap();
+    assert!(jpeg.windows(2).any(|pair| pair == ["-q:v", "2"]));
+    let webp = build_still_encode_args(64, 36, "out.webp", &best, "rgba").unwrap();
+    assert!(webp.windows(2).any(|pair| pair == ["-f", "webp"]));
+
+    assert!(StillOptions::default().validate("out.gif").is_err());
+    let unusable = StillOptions { quality: Some(0), ..StillOptions::default() };
+    assert!(unusable.validate("out.png").is_err());
+  }
+
+  #[test]
+  fn frame_index_changes_seeded_still_output() {
+    let config: BlockShiftConfig = serde_json::from_value(serde_json::json!({
+      "blockSize": 4, "maxOffset": 6, "offsetStep": 2, "intensity": 100.0, "seed": 7
+    }))
+    .unwrap();
+    let (width, height) = (16, 12);
+    let frame: Vec<u8> = (0..width * height * 4).map(|index| (index * 37 % 251) as u8).collect();
+    let first = process_still(&config, &frame, width, height, 0);
+    assert_eq!(first, process_still(&config, &frame, width, height, 0));
+    let later = (1..8).map(|index| process_still(&config, &frame, width, height, index));
+    assert!(later.into_iter().any(|output| output != first));
+  }
+}
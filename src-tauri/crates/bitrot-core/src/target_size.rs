@@ -0,0 +1,236 @@
+// Target-size renders ("under 8 MB"). The video bitrate is whatever the size budget
+// leaves after audio; pass 1 reads the frames as they are produced and pass 2 re-encodes
+// the lossless intermediate the segments were written to.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::BitrotError;
+use crate::events::LogLevel;
+use crate::pipeline::{push_video_codec_args, NativeEncoding};
+use crate::progress::JobReporter;
+
+// Targets are decimal megabytes, so an "8 MB" budget also fits an 8 MiB limit.
+pub const BYTES_PER_MB: f64 = 1_000_000.0;
+// Share of the budget kept back for container overhead and rate-control drift.
+const SIZE_HEADROOM: f64 = 0.03;
+// Below this a budget is too small for anything worth watching.
+const MIN_VIDEO_KBPS: f64 = 32.0;
+
+// Encoders whose ffmpeg wrappers write and read pass statistics.
+pub const TWO_PASS_ENCODERS: &[&str] = &["libx264", "libx265", "libvpx-vp9", "libaom-av1"];
+
+// Stats file prefix, kept in the job's checkpoint directory.
+pub const PASS_LOG_NAME: &str = "passlog";
+
+pub(crate) fn validate_target_size(encoding: &NativeEncoding, target_mb: f64) -> Result<(), BitrotError> {
+  if encoding.animated_format().is_some() {
+    return Err(BitrotError::invalid(
+      "GIF and WebP outputs have no bitrate to aim for; cap their size with maxWidth, maxFps or quality."
+    ));
+  }
+  if !target_mb.is_finite() || target_mb <= 0.0 {
+    return Err(BitrotError::invalid("Target size must be a positive number of megabytes."));
+  }
+  if !TWO_PASS_ENCODERS.contains(&encoding.encoder.as_str()) {
+    return Err(BitrotError::invalid(format!(
+      "{} has no two-pass mode; use one of: {} for a target size.",
+      encoding.encoder,
+      TWO_PASS_ENCODERS.join(", ")
+    )));
+  }
+  if encoding.target_bitrate_kbps.is_some() {
+    return Err(BitrotError::invalid("Set either a target size or a target bitrate, not both."));
+  }
+  let audio_codec = encoding.audio_codec_name();
+  if encoding.audio_enabled && matches!(audio_codec.as_str(), "copy" | "pcm") {
+    return Err(BitrotError::invalid(
+      "Copied and PCM audio have no fixed bitrate; use aac or opus with a target size."
+    ));
+  }
+  Ok(())
+}
+
+// Seconds the output will run: the trim window, cut short by the end of the input.
+pub fn encoded_duration(trim: Option<(f64, f64)>, duration_seconds: Option<f64>) -> Option<f64> {
+  let seconds = match (trim, duration_seconds) {
+    (Some((start, end)), Some(total)) => end.min(total) - start,
+    (Some((start, end)), None) => end - start,
+    (None, total) => total?
+  };
+  Some(seconds).filter(|seconds| *seconds > 0.0)
+}
+
+// Video bitrate that lands `target_mb` over `duration_seconds` next to the audio stream.
+pub fn video_bitrate_kbps(
+  target_mb: f64,
+  duration_seconds: Option<f64>,
+  audio_kbps: u32
+) -> Result<u32, BitrotError> {
+  let Some(seconds) = duration_seconds else {
+    return Err(BitrotError::invalid("A target size needs the input's duration."));
+  };
+  let total_kbps = target_mb * BYTES_PER_MB * 8.0 * (1.0 - SIZE_HEADROOM) / seconds / 1000.0;
+  let video_kbps = total_kbps - f64::from(audio_kbps);
+  if video_kbps < MIN_VIDEO_KBPS {
+    return Err(BitrotError::invalid(format!(
+      "{target_mb} MB over {seconds:.1}s leaves {:.0} kbps for video; raise the target, trim the clip or lower the audio bitrate.",
+      video_kbps.max(0.0)
+    )));
+  }
+  Ok(video_kbps.floor() as u32)
+}
+
+// Where a pass reads its frames: the render's raw frames on stdin, or the intermediate.
+pub enum PassInput<'a> {
+  Raw {
+    width: u32,
+    height: u32,
+    fps: f64,
+    pixel_format: &'a str
+  },
+  File(&'a Path)
+}
+
+fn pass_args(input: &PassInput, encoding: &NativeEncoding, pass: u8, pass_log: &Path) -> Vec<String> {
+  let mut args: Vec<String> = vec!["-y".into(), "-hide_banner".into(), "-loglevel".into(), "error".into()];
+  match input {
+    PassInput::Raw {
+      width,
+      height,
+      fps,
+      pixel_format
+    } => args.extend([
+      "-f".into(),
+      "rawvideo".into(),
+      "-pix_fmt".into(),
+      (*pixel_format).into(),
+      "-s".into(),
+      format!("{width}x{height}"),
+      "-r".into(),
+      format!("{fps:.3}"),
+      "-i".into(),
+      "-".into()
+    ]),
+    PassInput::File(path) => args.extend([
+      "-i".into(),
+      path.to_string_lossy().into_owned(),
+      "-map".into(),
+      "0:v:0".into()
+    ])
+  }
+  push_video_codec_args(&mut args, encoding, Some((pass, pass_log)));
+  args.extend(["-pix_fmt".into(), encoding.output_pixel_format().into()]);
+  args.extend(encoding.extra_encode_args.iter().cloned());
+  args.push("-an".into());
+  args
+}
+
+// Pass 1 only writes statistics; its video goes to the null muxer.
+pub fn build_first_pass_args(input: &PassInput, encoding: &NativeEncoding, pass_log: &Path) -> Vec<String> {
+  let mut args = pass_args(input, encoding, 1, pass_log);
+  args.extend(["-f".into(), "null".into(), "-".into()]);
+  args
+}
+
+// `encoding` carries the budgeted bitrate (see `NativeEncoding::with_video_bitrate`).
+pub fn build_second_pass_args(
+  intermediate: &Path,
+  encoding: &NativeEncoding,
+  pass_log: &Path,
+  output_path: &Path
+) -> Vec<String> {
+  let mut args = pass_args(&PassInput::File(intermediate), encoding, 2, pass_log);
+  args.push(output_path.to_string_lossy().into_owned());
+  args
+}
+
+// Logs the finished file against its budget; going over is a warning, not a failure.
+pub fn report_size(output_path: &Path, target_mb: f64, reporter: &dyn JobReporter) {
+  let Ok(metadata) = fs::metadata(output_path) else {
+    return;
+  };
+  let size_mb = metadata.len() as f64 / BYTES_PER_MB;
+  let message = format!(
+    "Final size {size_mb:.2} MB of the {target_mb:.2} MB target ({:.0}%).",
+    size_mb / target_mb * 100.0
+  );
+  if size_mb > target_mb {
+    reporter.log_at(LogLevel::Warn, &format!("{message} Over target; try a lower audio bitrate."));
+  } else {
+    reporter.log(&message);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn encoding(encoder: &str) -> NativeEncoding {
+    serde_json::from_value(serde_json::json!({
+      "encoder": encoder,
+      "preset": "medium",
+      "crf": 18,
+      "cq": null,
+      "maxBitrateKbps": null,
+      "targetBitrateKbps": null,
+      "vp9Deadline": null,
+      "vp9CpuUsed": null,
+      "format": "mp4",
+      "audioEnabled": true,
+      "audioCodec": "aac",
+      "audioBitrateKbps": 128,
+      "targetSizeMb": 8.0
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn budget_leaves_room_for_audio_and_overhead() {
+    assert_eq!(encoded_duration(Some((10.0, 90.0)), Some(70.0)), Some(60.0));
+    assert_eq!(encoded_duration(None, Some(60.0)), Some(60.0));
+    assert_eq!(encoded_duration(None, None), None);
+
+    // 8 MB over 60 s is 1066.7 kbps; 3% headroom and 128 kbps of audio leave 906.
+    let x264 = encoding("libx264");
+    assert_eq!(video_bitrate_kbps(8.0, Some(60.0), x264.audio_kbps()).unwrap(), 906);
+    assert!(video_bitrate_kbps(8.0, None, 128).is_err());
+    assert!(video_bitrate_kbps(1.0, Some(600.0), 128).is_err());
+
+    assert!(x264.validate().is_ok());
+    assert!(encoding("h264_nvenc").validate().is_err());
+    let both = NativeEncoding { target_bitrate_kbps: Some(900), ..x264.clone() };
+    assert!(both.validate().is_err());
+    let copied = NativeEncoding { audio_codec: Some("copy".into()), ..x264 };
+    assert!(copied.validate().is_err());
+  }
+
+  #[test]
+  fn passes_share_the_budgeted_rate_and_stats_file() {
+    let pass_log = Path::new("/jobs/abc/passlog");
+    let rated = encoding("libx264").with_video_bitrate(906);
+    let raw = PassInput::Raw { width: 64, height: 36, fps: 24.0, pixel_format: "rgba" };
+    let first = build_first_pass_args(&raw, &rated, pass_log);
+    assert!(first.windows(2).any(|pair| pair == ["-b:v", "906k"]));
+    assert!(!first.iter().any(|arg| arg == "-crf"));
+    assert!(first.windows(2).any(|pair| pair == ["-pass", "1"]));
+    assert!(first.ends_with(&["-an".into(), "-f".into(), "null".into(), "-".into()]));
+
+    let second = build_second_pass_args(
+      Path::new("clip.video.mkv"),
+      &rated,
+      pass_log,
+      Path::new("clip.pass2.video.mp4")
+    );
+    assert_eq!(second[4..6], ["-i", "clip.video.mkv"]);
+    assert!(second.windows(2).any(|pair| pair == ["-passlogfile", "/jobs/abc/passlog"]));
+    assert_eq!(second.last().unwrap(), "clip.pass2.video.mp4");
+
+    // x265 takes its pass through -x265-params rather than -pass.
+    let hevc = encoding("libx265").with_video_bitrate(906);
+    let args = build_second_pass_args(Path::new("in.mkv"), &hevc, pass_log, Path::new("out.mp4"));
+    let params = args.iter().position(|arg| arg == "-x265-params").unwrap() + 1;
+    assert_eq!(args[params], "log-level=error:pass=2:stats='/jobs/abc/passlog'");
+    assert!(!args.iter().any(|arg| arg == "-pass"));
+  }
+}
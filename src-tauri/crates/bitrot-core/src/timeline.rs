@@ -0,0 +1,220 @@
+// Active time windows for native effects: frames outside every window pass through untouched,
+// and each window fades the effect in and out by crossfading with the source frame.
+use serde::{Deserialize, Serialize};
+
+use crate::effect::NativeEffect;
+use crate::error::BitrotError;
+use crate::mask::blend;
+use crate::parallel::for_each_row_band;
+use crate::sample::Sample;
+
+// Times are seconds from the start of the rendered range, like automation keyframes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveWindow {
+  pub start: f64,
+  pub end: f64,
+  // Ramps run inside the window: full strength from `start + fade_in` to `end - fade_out`.
+  #[serde(default)]
+  pub fade_in: f64,
+  #[serde(default)]
+  pub fade_out: f64
+}
+
+impl ActiveWindow {
+  // Effect strength at `time` in 0..1.
+  fn weight_at(&self, time: f64) -> f64 {
+    if time < self.start || time >= self.end {
+      return 0.0;
+    }
+    let ramp = |elapsed: f64, duration: f64| {
+      if duration > 0.0 {
+        (elapsed / duration).min(1.0)
+      } else {
+        1.0
+      }
+    };
+    ramp(time - self.start, self.fade_in).min(ramp(self.end - time, self.fade_out))
+  }
+}
+
+pub fn validate_windows(windows: &[ActiveWindow]) -> Result<(), BitrotError> {
+  for (index, window) in windows.iter().enumerate() {
+    let values = [window.start, window.end, window.fade_in, window.fade_out];
+    if values.iter().any(|value| !value.is_finite() || *value < 0.0) || window.end <= window.start {
+      return Err(BitrotError::invalid(format!(
+        "Active window {} needs 0 <= start < end and non-negative fades.",
+        index + 1
+      )));
+    }
+  }
+  Ok(())
+}
+
+// Wraps an effect so it only shows inside its windows; without windows it runs on every frame.
+pub struct Windowed<E> {
+  base: E,
+  windows: Vec<ActiveWindow>,
+  fps: f64
+}
+
+impl<E> Windowed<E> {
+  pub fn new(base: E, windows: Option<Vec<ActiveWindow>>, fps: f64) -> Result<Self, BitrotError> {
+    let windows = windows.unwrap_or_default();
+    validate_windows(&windows)?;
+    Ok(Self {
+      base,
+      windows,
+      fps: if fps > 0.0 { fps } else { 30.0 }
+    })
+  }
+
+  // Overlapping windows take the stronger weight.
+  fn weight_at(&self, time: f64) -> f64 {
+    self
+      .windows
+      .iter()
+      .map(|window| window.weight_at(time))
+      .fold(0.0, f64::max)
+  }
+}
+
+pub struct WindowedWorkspace<W, S> {
+  inner: W,
+  width: usize,
+  output: Vec<S>
+}
+
+impl<E: NativeEffect> NativeEffect for Windowed<E> {
+  const LABEL: &'static str = E::LABEL;
+  const TAG: &'static str = E::TAG;
+
+  type Workspace<S: Sample> = WindowedWorkspace<E::Workspace<S>, S>;
+
+  fn workspace<S: Sample>(&self, width: usize, height: usize) -> Self::Workspace<S> {
+    WindowedWorkspace {
+      inner: self.base.workspace(width, height),
+      width,
+      output: Vec::new()
+    }
+  }
+
+  fn process_frame<'a, S: Sample>(
+    &self,
+    frame: &[S],
+    workspace: &'a mut Self::Workspace<S>,
+    frame_index: u64
+  ) -> &'a [S] {
+    if self.windows.is_empty() {
+      return self.base.process_frame(frame, &mut workspace.inner, frame_index);
+    }
+    let weight = self.weight_at(frame_index as f64 / self.fps);
+    let weight = (weight * 255.0).round() as u32;
+    if weight == 0 {
+      // Effects with temporal state still see every frame, so a window (or a resume) lands
+      // on the same state an unwindowed render would have.
+      if self.base.warmup_frames() > 0 {
+        self.base.process_frame(frame, &mut workspace.inner, frame_index);
+      }
+      workspace.output.clear();
+      workspace.output.extend_from_slice(frame);
+      return &workspace.output;
+    }
+    let processed = self.base.process_frame(frame, &mut workspace.inner, frame_index);
+    if weight == 255 {
+      return processed;
+    }
+    workspace.output.resize(processed.len(), S::default());
+    let row_len = workspace.width * 4;
+    for_each_row_band(&mut workspace.output, row_len, 1, |first_row, band| {
+      let start = first_row * row_len;
+      for (offset, out) in band.chunks_exact_mut(4).enumerate() {
+        let byte = start + offset * 4;
+        blend(&frame[byte..byte + 4], &processed[byte..byte + 4], weight, out);
+      }
+    });
+    &workspace.output
+  }
+
+  fn warmup_frames(&self) -> u64 {
+    self.base.warmup_frames()
+  }
+
+  fn take_warning(&self) -> Option<String> {
+    self.base.take_warning()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Fill;
+
+  impl NativeEffect for Fill {
+    const LABEL: &'static str = "Fill";
+    const TAG: &'static str = "fill";
+    type Workspace<S: Sample> = Vec<S>;
+
+    fn workspace<S: Sample>(&self, width: usize, height: usize) -> Vec<S> {
+      vec![S::default(); width * height * 4]
+    }
+
+    fn process_frame<'a, S: Sample>(&self, _frame: &[S], workspace: &'a mut Vec<S>, _index: u64) -> &'a [S] {
+      workspace.fill(S::from_u8(255));
+      workspace
+    }
+  }
+
+  #[test]
+  fn windows_pass_frames_through_and_fade_the_effect() {
+    let windows = vec![
+      ActiveWindow {
+        start: 1.0,
+        end: 3.0,
+        fade_in: 1.0,
+        fade_out: 0.0
+      },
+      ActiveWindow {
+        start: 5.0,
+        end: 6.0,
+        fade_in: 0.0,
+        fade_out: 0.0
+      }
+    ];
+    // One frame per second keeps frame indices and times equal.
+    let windowed = Windowed::new(Fill, Some(windows), 1.0).unwrap();
+    let mut workspace = windowed.workspace(2, 1);
+    let frame = [0u8; 8];
+    let mut at = |index| windowed.process_frame(&frame, &mut workspace, index)[0];
+
+    assert_eq!(at(0), 0);
+    assert_eq!(at(1), 0);
+    assert_eq!(at(2), 255);
+    assert_eq!(at(3), 0);
+    assert_eq!(at(5), 255);
+
+    let faded = Windowed::new(
+      Fill,
+      Some(vec![ActiveWindow {
+        start: 0.0,
+        end: 4.0,
+        fade_in: 2.0,
+        fade_out: 2.0
+      }]),
+      1.0
+    )
+    .unwrap();
+    let mut workspace = faded.workspace(2, 1);
+    assert_eq!(faded.process_frame(&frame, &mut workspace, 1)[0], 128);
+    assert_eq!(faded.process_frame(&frame, &mut workspace, 3)[0], 128);
+
+    let reversed = ActiveWindow {
+      start: 2.0,
+      end: 1.0,
+      fade_in: 0.0,
+      fade_out: 0.0
+    };
+    assert!(Windowed::new(Fill, Some(vec![reversed]), 30.0).is_err());
+  }
+}
@@ -0,0 +1,337 @@
+// What the resolved ffmpeg can actually do. `resolve_ffmpeg_program` only says where the
+// binary came from; builds differ in which encoders, filters and pixel formats they ship,
+// so jobs check their settings against this report before spawning anything.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::animated::{AnimatedFormat, PaletteMode};
+use crate::error::{BitrotError, ErrorCode};
+use crate::ffmpeg::{resolve_ffmpeg_program, CommandSource};
+use crate::pipeline::{alpha_capable_encoders, FfmpegLauncher, NativeEncoding};
+use crate::sequence::OutputSequence;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolchainReport {
+  // From the `ffmpeg version <x>` banner; "unknown" when a build omits it.
+  pub version: String,
+  pub has_libx265: bool,
+  pub has_libsvtav1: bool,
+  // libvpx-vp9 specifically; the VP8 wrapper is of no use to native jobs.
+  pub has_libvpx: bool,
+  // Native encoders that keep alpha and are present in this build.
+  pub alpha_encoders: Vec<String>,
+  pub encoders: BTreeSet<String>,
+  pub filters: BTreeSet<String>,
+  pub pixel_formats: BTreeSet<String>
+}
+
+// Reports per resolved binary. Local and PATH builds are both spawned as a bare "ffmpeg",
+// so the key is the absolute path the name resolves to, plus where it was found.
+type CacheKey = (Option<PathBuf>, CommandSource);
+
+fn cache() -> &'static Mutex<HashMap<CacheKey, ToolchainReport>> {
+  static REPORTS: OnceLock<Mutex<HashMap<CacheKey, ToolchainReport>>> = OnceLock::new();
+  REPORTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// Cached report for the launcher's binary; probes ffmpeg on first use. Every host builds
+// its launcher from `resolve_ffmpeg_program`, so the key matches what the launcher spawns.
+pub fn toolchain_report(ffmpeg: FfmpegLauncher) -> Result<ToolchainReport, BitrotError> {
+  let resolved = resolve_ffmpeg_program("ffmpeg");
+  let program = (resolved.absolute_program(), resolved.source);
+  if let Some(report) = cache()
+    .lock()
+    .map_err(|_| BitrotError::internal("Toolchain cache lock poisoned."))?
+    .get(&program)
+  {
+    return Ok(report.clone());
+  }
+  let report = probe_toolchain(ffmpeg)?;
+  cache()
+    .lock()
+    .map_err(|_| BitrotError::internal("Toolchain cache lock poisoned."))?
+    .insert(program, report.clone());
+  Ok(report)
+}
+
+// Drops cached reports, e.g. after the user swaps their ffmpeg build.
+pub fn clear_toolchain_cache() {
+  if let Ok(mut reports) = cache().lock() {
+    reports.clear();
+  }
+}
+
+fn run_listing(ffmpeg: FfmpegLauncher, flag: &str) -> Result<String, BitrotError> {
+  let mut command = ffmpeg()?;
+  if flag != "-version" {
+    command.arg("-hide_banner");
+  }
+  let output = command
+    .arg(flag)
+    .stdin(Stdio::null())
+    .output()
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::FfmpegUnavailable, format!("ffmpeg could not be started: {error}"))
+    })?;
+  if !output.status.success() {
+    return Err(
+      BitrotError::new(ErrorCode::FfmpegFailed, format!("ffmpeg {flag} failed."))
+        .with_stderr(&String::from_utf8_lossy(&output.stderr))
+    );
+  }
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Runs `-version`, `-encoders`, `-filters` and `-pix_fmts`; uncached.
+pub fn probe_toolchain(ffmpeg: FfmpegLauncher) -> Result<ToolchainReport, BitrotError> {
+  Ok(ToolchainReport::from_listings(
+    &run_listing(ffmpeg, "-version")?,
+    &run_listing(ffmpeg, "-encoders")?,
+    &run_listing(ffmpeg, "-filters")?,
+    &run_listing(ffmpeg, "-pix_fmts")?
+  ))
+}
+
+// Version from the first line of `ffmpeg -version`.
+pub fn parse_version(stdout: &str) -> Option<String> {
+  let first_line = stdout.lines().next()?;
+  let version = first_line.trim().strip_prefix("ffmpeg version ")?;
+  version.split_whitespace().next().map(str::to_string)
+}
+
+// Second column of every row below the `------` rule (`-encoders`, `-pix_fmts`).
+fn names_below_rule(stdout: &str) -> BTreeSet<String> {
+  stdout
+    .lines()
+    .skip_while(|line| !line.trim_start().starts_with("---"))
+    .skip(1)
+    .filter_map(|line| line.split_whitespace().nth(1))
+    .map(str::to_string)
+    .collect()
+}
+
+// `-filters` has no rule; filter rows are the ones with an `A->V`-style column.
+fn filter_names(stdout: &str) -> BTreeSet<String> {
+  stdout
+    .lines()
+    .filter_map(|line| {
+      let columns: Vec<&str> = line.split_whitespace().collect();
+      (columns.len() > 2 && columns[2].contains("->")).then(|| columns[1].to_string())
+    })
+    .collect()
+}
+
+impl ToolchainReport {
+  pub fn from_listings(version: &str, encoders: &str, filters: &str, pixel_formats: &str) -> Self {
+    let encoders = names_below_rule(encoders);
+    let alpha_encoders = alpha_capable_encoders()
+      .into_iter()
+      .filter(|name| encoders.contains(*name))
+      .map(str::to_string)
+      .collect();
+    Self {
+      version: parse_version(version).unwrap_or_else(|| "unknown".into()),
+      has_libx265: encoders.contains("libx265"),
+      has_libsvtav1: encoders.contains("libsvtav1"),
+      has_libvpx: encoders.contains("libvpx-vp9"),
+      alpha_encoders,
+      encoders,
+      filters: filter_names(filters),
+      pixel_formats: names_below_rule(pixel_formats)
+    }
+  }
+
+  fn unsupported(&self, kind: &str, name: &str) -> BitrotError {
+    BitrotError::new(
+      ErrorCode::FfmpegUnsupported,
+      format!(
+        "This ffmpeg ({}) has no {name} {kind}. Pick a different setting or install an ffmpeg build that includes it.",
+        self.version
+      )
+    )
+  }
+
+  pub fn require_encoder(&self, name: &str) -> Result<(), BitrotError> {
+    if self.encoders.contains(name) {
+      Ok(())
+    } else {
+      Err(self.unsupported("encoder", name))
+    }
+  }
+
+  pub fn require_filter(&self, name: &str) -> Result<(), BitrotError> {
+    if self.filters.contains(name) {
+      Ok(())
+    } else {
+      Err(self.unsupported("filter", name))
+    }
+  }
+
+  pub fn require_pixel_format(&self, name: &str) -> Result<(), BitrotError> {
+    if self.pixel_formats.contains(name) {
+      Ok(())
+    } else {
+      Err(self.unsupported("pixel format", name))
+    }
+  }
+
+  // Everything a native render will ask of ffmpeg: the segment encoder, the final encoder
+  // or animation filters, the output pixel format and the audio encoder.
+  pub fn check_encoding(
+    &self,
+    encoding: &NativeEncoding,
+    sequence: Option<&OutputSequence>
+  ) -> Result<(), BitrotError> {
+    if let Some(sequence) = sequence {
+      self.require_encoder(sequence.format.codec())?;
+      return self.require_pixel_format(&sequence.pixel_format);
+    }
+    self.require_encoder(&encoding.segment_encoding().encoder)?;
+    match encoding.animated_format() {
+      Some(AnimatedFormat::Gif) => {
+        self.require_encoder("gif")?;
+        self.require_filter("palettegen")?;
+        self.require_filter("paletteuse")?;
+        let palette = encoding.animation.as_ref().map(|options| options.palette);
+        if palette == Some(PaletteMode::PerScene) {
+          self.require_filter("scdet")?;
+        }
+        Ok(())
+      }
+      Some(AnimatedFormat::Webp) => self.require_encoder("libwebp_anim"),
+      None => {
+        self.require_encoder(&encoding.encoder)?;
+        self.require_pixel_format(encoding.output_pixel_format())?;
+        let audio = encoding.audio_encoder();
+        if encoding.audio_enabled && audio != "copy" {
+          self.require_encoder(audio)?;
+        }
+        Ok(())
+      }
+    }
+  }
+
+  // Output encoders and pixel formats named in a raw ffmpeg command line. Options before
+  // the last `-i` belong to inputs (decoders, raw frame formats) and are skipped.
+  pub fn check_args(&self, args: &[String]) -> Result<(), BitrotError> {
+    let outputs = args.iter().rposition(|arg| arg == "-i").map_or(0, |index| index + 2);
+    for pair in args.get(outputs..).unwrap_or_default().windows(2) {
+      let (option, value) = (pair[0].as_str(), pair[1].as_str());
+      let codec = matches!(option, "-c" | "-codec" | "-vcodec" | "-acodec")
+        || option.starts_with("-c:")
+        || option.starts_with("-codec:");
+      if codec && value != "copy" {
+        self.require_encoder(value)?;
+      }
+      if option == "-pix_fmt" || option.starts_with("-pix_fmt:") {
+        self.require_pixel_format(value)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const VERSION: &str = "ffmpeg version 7.1-full_build Copyright (c) 2000-2024 the FFmpeg developers\n\
+built with gcc 14.2.0 (Rev1, Built by MSYS2 project)\n";
+
+  const ENCODERS: &str = "Encoders:\n \
+V..... = Video\n \
+A..... = Audio\n \
+------\n \
+V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)\n \
+V....D libvpx-vp9           libvpx VP9 (codec vp9)\n \
+V....D prores_ks            Apple ProRes (iCodec Pro) (codec prores)\n \
+V....D png                  PNG (Portable Network Graphics) image\n \
+V....D gif                  GIF (Graphics Interchange Format)\n \
+A....D aac                  AAC (Advanced Audio Coding)\n";
+
+  const FILTERS: &str = "Filters:\n  \
+T.. = Timeline support\n  \
+| = Source or sink filter\n \
+... palettegen         V->V       Find the optimal palette for a given stream.\n \
+... paletteuse         VV->V      Use a palette to downsample an input video stream.\n \
+TSC scale              V->V       Scale the input video size and/or convert the image format.\n";
+
+  const PIX_FMTS: &str = "Pixel formats:\n\
+I.... = Supported Input  format for conversion\n\
+FLAGS NAME            NB_COMPONENTS BITS_PER_PIXEL BIT_DEPTHS\n\
+-----\n\
+IO... yuv420p                3             12      8-8-8\n\
+IO... yuv444p10le            3             30      10-10-10\n\
+IO... yuva444p10le           4             40      10-10-10-10\n\
+IO... rgb48be                3             48      16-16-16\n";
+
+  fn encoding(encoder: &str, format: &str) -> NativeEncoding {
+    serde_json::from_value(serde_json::json!({
+      "encoder": encoder,
+      "preset": "medium",
+      "crf": 18,
+      "cq": null,
+      "maxBitrateKbps": null,
+      "targetBitrateKbps": null,
+      "vp9Deadline": null,
+      "vp9CpuUsed": null,
+      "format": format,
+      "audioEnabled": true,
+      "audioCodec": null,
+      "audioBitrateKbps": null
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn listings_parse_into_a_typed_report() {
+    let report = ToolchainReport::from_listings(VERSION, ENCODERS, FILTERS, PIX_FMTS);
+    assert_eq!(report.version, "7.1-full_build");
+    assert!(!report.has_libx265);
+    assert!(!report.has_libsvtav1);
+    assert!(report.has_libvpx);
+    assert_eq!(report.alpha_encoders, ["libvpx-vp9", "prores_ks", "png"]);
+    assert!(report.encoders.contains("aac"));
+    assert!(!report.encoders.contains("V....."));
+    assert_eq!(report.filters.len(), 3);
+    assert!(report.pixel_formats.contains("yuva444p10le"));
+    assert!(!report.pixel_formats.contains("NAME"));
+    assert_eq!(ToolchainReport::from_listings("", "", "", "").version, "unknown");
+  }
+
+  #[test]
+  fn missing_encoders_fail_before_spawning() {
+    let report = ToolchainReport::from_listings(VERSION, ENCODERS, FILTERS, PIX_FMTS);
+    assert!(report.check_encoding(&encoding("libx264", "mp4"), None).is_ok());
+    assert!(report.check_encoding(&encoding("libx264", "gif"), None).is_ok());
+
+    let error = report.check_encoding(&encoding("libx265", "mp4"), None).unwrap_err();
+    assert_eq!(error.code, ErrorCode::FfmpegUnsupported);
+    assert!(error.message.contains("7.1-full_build"));
+    assert!(error.message.contains("libx265 encoder"));
+    // Mezzanine audio defaults to PCM, which this build lacks.
+    let prores = report.check_encoding(&encoding("prores_ks", "mov"), None).unwrap_err();
+    assert!(prores.message.contains("pcm_s24le"));
+    assert!(report.check_encoding(&encoding("libx264", "webp"), None).is_err());
+
+    let args: Vec<String> = [
+      "-c:v", "hevc_cuvid", "-i", "in.mp4", "-c:v", "libx264", "-c:a", "copy", "out.mp4"
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    assert!(report.check_args(&args).is_ok());
+    let mut missing = args.clone();
+    missing[5] = "libsvtav1".into();
+    assert!(report.check_args(&missing).is_err());
+    missing[5] = "libx264".into();
+    missing.splice(8..8, ["-pix_fmt".to_string(), "yuv422p".to_string()]);
+    assert!(report.check_args(&missing).is_err());
+  }
+}
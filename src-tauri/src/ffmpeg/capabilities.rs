@@ -0,0 +1,58 @@
+// Tauri command for the resolved ffmpeg's capability report, plus the preflight
+// `ffmpeg_spawn` runs before starting an encode.
+
+use std::process::Command;
+
+use bitrot_core::error::BitrotError;
+use bitrot_core::pipeline::NativeEncoding;
+use bitrot_core::toolchain::{clear_toolchain_cache, toolchain_report, ToolchainReport};
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::ffmpeg::{resolve_ffmpeg_command, resolve_ffmpeg_command_with_source, CommandSource};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FfmpegCapabilitiesResponse {
+  report: ToolchainReport,
+  source: CommandSource
+}
+
+// Probing runs four ffmpeg processes, so it happens on a worker thread; later calls hit
+// the cache unless `refresh` is set.
+async fn load_report(app: AppHandle, refresh: bool) -> Result<ToolchainReport, BitrotError> {
+  tauri::async_runtime::spawn_blocking(move || {
+    if refresh {
+      clear_toolchain_cache();
+    }
+    let ffmpeg = || resolve_ffmpeg_command(&app, "ffmpeg").map(Command::from);
+    toolchain_report(&ffmpeg)
+  })
+  .await
+  .map_err(|error| BitrotError::internal(format!("Toolchain probe failed: {error}")))
+  .and_then(|result| result)
+}
+
+#[tauri::command]
+pub async fn ffmpeg_capabilities(
+  app: AppHandle,
+  refresh: Option<bool>
+) -> Result<FfmpegCapabilitiesResponse, BitrotError> {
+  let source = resolve_ffmpeg_command_with_source(&app, "ffmpeg")?.source;
+  let report = load_report(app, refresh.unwrap_or(false)).await?;
+  Ok(FfmpegCapabilitiesResponse { report, source })
+}
+
+// Checks an ffmpeg command line, and the encoding it was built from when the frontend
+// sends one, against the capability report.
+pub async fn preflight(
+  app: &AppHandle,
+  encoding: Option<&NativeEncoding>,
+  args: &[String]
+) -> Result<(), BitrotError> {
+  let report = load_report(app.clone(), false).await?;
+  if let Some(encoding) = encoding {
+    report.check_encoding(encoding, None)?;
+  }
+  report.check_args(args)
+}
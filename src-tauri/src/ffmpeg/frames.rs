@@ -1,4 +1,5 @@
 // Streaming ffprobe helpers for VFR frame maps (keeps memory bounded).
+use bitrot_core::error::{BitrotError, ErrorCode};
 use serde::Serialize;
 use tauri::AppHandle;
 use tauri_plugin_shell::process::CommandEvent;
@@ -142,10 +143,10 @@ async fn probe_duration(app: &AppHandle, path: &str) -> Option<f64> {
 pub async fn ffprobe_frame_map(
   app: AppHandle,
   path: String,
-) -> Result<FrameMapResponse, String> {
+) -> Result<FrameMapResponse, BitrotError> {
   let path = path.trim().to_string();
   if path.is_empty() {
-    return Err("ffprobe received an empty file path.".into());
+    return Err(BitrotError::invalid("ffprobe received an empty file path."));
   }
 
   let args = [
@@ -164,7 +165,9 @@ pub async fn ffprobe_frame_map(
   let (mut rx, _child) = resolve_ffmpeg_command(&app, "ffprobe")?
     .args(args)
     .spawn()
-    .map_err(|error| error.to_string())?;
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start ffprobe: {error}"))
+    })?;
 
   let mut times = Vec::new();
   let mut keyframe_times = Vec::new();
@@ -202,16 +205,20 @@ pub async fn ffprobe_frame_map(
 
   let code = exit_code.unwrap_or(-1);
   if code != 0 {
-    let message = if stderr_lines.is_empty() {
-      "ffprobe failed to return frame data".to_string()
-    } else {
-      stderr_lines.join("\n")
+    let message = match stderr_lines.last() {
+      Some(line) => format!("ffprobe failed to return frame data: {line}"),
+      None => "ffprobe failed to return frame data".to_string()
     };
-    return Err(message);
+    return Err(
+      BitrotError::new(ErrorCode::FfmpegFailed, message).with_stderr(&stderr_lines.join("\n"))
+    );
   }
 
   if times.is_empty() {
-    return Err("ffprobe did not return frame data.".into());
+    return Err(BitrotError::new(
+      ErrorCode::FfmpegFailed,
+      "ffprobe did not return frame data."
+    ));
   }
 
   let duration_seconds = probe_duration(&app, &path).await;
@@ -5,10 +5,14 @@ use std::{
   sync::{Arc, Mutex}
 };
 
+use bitrot_core::error::{BitrotError, ErrorCode};
+use bitrot_core::events::{JobEvent, JobEventPayload, LogLevel, LogStream, JOB_EVENT_CHANNEL};
+use bitrot_core::pipeline::NativeEncoding;
 use serde::Serialize;
 use tauri::{AppHandle, Emitter, State, Window};
 use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 
+use crate::ffmpeg::capabilities::preflight;
 use crate::ffmpeg::{resolve_ffmpeg_command_with_source, CommandSource};
 
 #[derive(Default)]
@@ -57,19 +61,27 @@ pub struct FfmpegSpawnResponse {
   source: CommandSource
 }
 
-fn validate_program(program: &str) -> Result<(), String> {
+fn validate_program(program: &str) -> Result<(), BitrotError> {
   match program {
     "ffmpeg" | "ffprobe" => Ok(()),
-    _ => Err("Only ffmpeg or ffprobe can be executed.".to_string())
+    _ => Err(BitrotError::invalid("Only ffmpeg or ffprobe can be executed."))
   }
 }
 
+fn spawn_failed(program: &str, error: impl std::fmt::Display) -> BitrotError {
+  BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to start {program}: {error}"))
+}
+
+fn lock_poisoned() -> BitrotError {
+  BitrotError::internal("ffmpeg job lock poisoned")
+}
+
 #[tauri::command]
 pub async fn ffmpeg_execute(
   app: AppHandle,
   program: String,
   args: Vec<String>
-) -> Result<FfmpegExecuteResponse, String> {
+) -> Result<FfmpegExecuteResponse, BitrotError> {
   let program = program.trim().to_lowercase();
   validate_program(&program)?;
   let resolved = resolve_ffmpeg_command_with_source(&app, &program)?;
@@ -78,7 +90,7 @@ pub async fn ffmpeg_execute(
     .args(args)
     .output()
     .await
-    .map_err(|error| error.to_string())?;
+    .map_err(|error| spawn_failed(&program, error))?;
   Ok(FfmpegExecuteResponse {
     output: FfmpegExecuteOutput {
       code: output.status.code(),
@@ -96,33 +108,39 @@ pub async fn ffmpeg_spawn(
   state: State<'_, FfmpegJobs>,
   program: String,
   args: Vec<String>,
-  job_id: String
-) -> Result<FfmpegSpawnResponse, String> {
+  job_id: String,
+  encoding: Option<NativeEncoding>
+) -> Result<FfmpegSpawnResponse, BitrotError> {
   let program = program.trim().to_lowercase();
   validate_program(&program)?;
   let job_id = job_id.trim().to_string();
   if job_id.is_empty() {
-    return Err("ffmpeg job id is required.".to_string());
+    return Err(BitrotError::invalid("ffmpeg job id is required."));
   }
   {
-    let lock = state.0.lock().map_err(|_| "ffmpeg job lock poisoned")?;
+    let lock = state.0.lock().map_err(|_| lock_poisoned())?;
     if lock.contains_key(&job_id) {
-      return Err("ffmpeg job id already exists.".to_string());
+      return Err(BitrotError::invalid("ffmpeg job id already exists."));
     }
   }
 
+  // Missing encoders fail here with a clear message rather than mid-encode.
+  if program == "ffmpeg" {
+    preflight(&app, encoding.as_ref(), &args).await?;
+  }
+
   let resolved = resolve_ffmpeg_command_with_source(&app, &program)?;
   let (mut rx, child) = resolved
     .command
     .args(args)
     .spawn()
-    .map_err(|error| error.to_string())?;
+    .map_err(|error| spawn_failed(&program, error))?;
 
   {
-    let mut lock = state.0.lock().map_err(|_| "ffmpeg job lock poisoned")?;
+    let mut lock = state.0.lock().map_err(|_| lock_poisoned())?;
     if lock.contains_key(&job_id) {
       let _ = child.kill();
-      return Err("ffmpeg job id already exists.".to_string());
+      return Err(BitrotError::invalid("ffmpeg job id already exists."));
     }
     lock.insert(job_id.clone(), child);
   }
@@ -130,8 +148,21 @@ pub async fn ffmpeg_spawn(
   let jobs = state.0.clone();
   let emit_window = window.clone();
   let emit_job_id = job_id.clone();
+  // Unified `job-event` mirror of the ffmpeg-* events; the kind is the program name.
+  let kind = program.clone();
+  let emit_job_event = move |payload: JobEventPayload| {
+    let _ = window.emit(JOB_EVENT_CHANNEL, JobEvent::new(&job_id, &kind, payload));
+  };
+  emit_job_event(JobEventPayload::Started);
 
   tauri::async_runtime::spawn(async move {
+    let stream_log = |stream: LogStream, message: String| JobEventPayload::Log {
+      level: LogLevel::Info,
+      message,
+      stream: Some(stream)
+    };
+    // Recent stderr, attached to the failure event.
+    let mut stderr_lines: Vec<String> = Vec::new();
     while let Some(event) = rx.recv().await {
       match event {
         CommandEvent::Stdout(line) => {
@@ -139,6 +170,7 @@ pub async fn ffmpeg_spawn(
             job_id: emit_job_id.clone(),
             data: String::from_utf8_lossy(&line).to_string()
           };
+          emit_job_event(stream_log(LogStream::Stdout, payload.data.clone()));
           let _ = emit_window.emit("ffmpeg-stdout", payload);
         }
         CommandEvent::Stderr(line) => {
@@ -146,9 +178,15 @@ pub async fn ffmpeg_spawn(
             job_id: emit_job_id.clone(),
             data: String::from_utf8_lossy(&line).to_string()
           };
+          stderr_lines.push(payload.data.clone());
+          if stderr_lines.len() > 64 {
+            stderr_lines.remove(0);
+          }
+          emit_job_event(stream_log(LogStream::Stderr, payload.data.clone()));
           let _ = emit_window.emit("ffmpeg-stderr", payload);
         }
         CommandEvent::Error(message) => {
+          emit_job_event(JobEventPayload::log(LogLevel::Error, &message));
           let payload = FfmpegErrorPayload {
             job_id: emit_job_id.clone(),
             message
@@ -156,6 +194,24 @@ pub async fn ffmpeg_spawn(
           let _ = emit_window.emit("ffmpeg-error", payload);
         }
         CommandEvent::Terminated(payload) => {
+          // ffmpeg_kill drops the child from the registry before killing it.
+          let killed = !jobs
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .contains_key(&emit_job_id);
+          let failure = |message: String| JobEventPayload::Failed {
+            error: BitrotError::new(ErrorCode::FfmpegFailed, message)
+              .with_stderr(&stderr_lines.join("\n"))
+          };
+          emit_job_event(match (killed, payload.code) {
+            (true, _) => JobEventPayload::Canceled,
+            (false, Some(0)) => JobEventPayload::Finished,
+            (false, Some(code)) => failure(format!("{program} exited with code {code}")),
+            (false, None) => failure(format!(
+              "{program} was terminated by signal {}",
+              payload.signal.unwrap_or(-1)
+            ))
+          });
           let payload = FfmpegClosePayload {
             job_id: emit_job_id.clone(),
             code: payload.code,
@@ -178,14 +234,16 @@ pub async fn ffmpeg_spawn(
 }
 
 #[tauri::command]
-pub fn ffmpeg_kill(state: State<'_, FfmpegJobs>, job_id: String) -> Result<(), String> {
+pub fn ffmpeg_kill(state: State<'_, FfmpegJobs>, job_id: String) -> Result<(), BitrotError> {
   let trimmed = job_id.trim();
   if trimmed.is_empty() {
     return Ok(());
   }
-  let mut lock = state.0.lock().map_err(|_| "ffmpeg job lock poisoned")?;
+  let mut lock = state.0.lock().map_err(|_| lock_poisoned())?;
   if let Some(child) = lock.remove(trimmed) {
-    child.kill().map_err(|error| error.to_string())?;
+    child
+      .kill()
+      .map_err(|error| BitrotError::internal(format!("Failed to stop ffmpeg job: {error}")))?;
   }
   Ok(())
 }
@@ -2,12 +2,21 @@ mod ffmpeg;
 mod modes;
 mod native;
 
-use ffmpeg::{frames as ffprobe_frames, jobs as ffmpeg_jobs};
-use modes::{block_shift, byte_range, datamosh, kaleidoscope, pixelsort, vaporwave};
-use native::preview as native_preview;
+use bitrot_core::error::{BitrotError, ErrorCode};
+use bitrot_core::modes::datamosh;
+use ffmpeg::{capabilities as ffmpeg_capabilities, frames as ffprobe_frames, jobs as ffmpeg_jobs};
+use modes::{block_shift, byte_range, chain, kaleidoscope, pixelsort, vaporwave};
+use native::{
+  jobs as native_jobs,
+  pipeline as native_pipeline,
+  preview as native_preview,
+  queue as render_queue
+};
 
 use std::path::{Path, PathBuf};
 
+use tauri::Manager;
+
 #[tauri::command]
 fn datamosh_bitstream(
   input_path: String,
@@ -17,7 +26,7 @@ fn datamosh_bitstream(
   intensity: f64,
   seed: u64,
   extradata_hex: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   datamosh::process_datamosh(
     &input_path,
     &output_path,
@@ -30,7 +39,7 @@ fn datamosh_bitstream(
 }
 
 #[tauri::command]
-async fn cleanup_files(paths: Vec<String>) -> Result<(), String> {
+async fn cleanup_files(paths: Vec<String>) -> Result<(), BitrotError> {
   tauri::async_runtime::spawn_blocking(move || {
     let mut failures = Vec::new();
     for path in paths {
@@ -60,27 +69,27 @@ async fn cleanup_files(paths: Vec<String>) -> Result<(), String> {
     if failures.is_empty() {
       Ok(())
     } else {
-      Err(format!(
-        "Failed to delete temp files: {}",
-        failures.join(", ")
+      Err(BitrotError::new(
+        ErrorCode::Io,
+        format!("Failed to delete temp files: {}", failures.join(", ")),
       ))
     }
   })
   .await
-  .map_err(|error| format!("cleanup join failed: {error}"))?
+  .map_err(|error| BitrotError::internal(format!("cleanup join failed: {error}")))?
 }
 
 #[tauri::command]
-fn get_executable_dir() -> Result<String, String> {
-  let exe = std::env::current_exe().map_err(|error| format!("current_exe: {error}"))?;
+fn get_executable_dir() -> Result<String, BitrotError> {
+  let exe = std::env::current_exe().map_err(|error| BitrotError::io("current_exe", error))?;
   let dir = exe
     .parent()
-    .ok_or_else(|| "Executable has no parent directory".to_string())?;
+    .ok_or_else(|| BitrotError::internal("Executable has no parent directory"))?;
   Ok(dir.to_string_lossy().into_owned())
 }
 
 #[tauri::command]
-fn executable_file_exists(name: String) -> Result<bool, String> {
+fn executable_file_exists(name: String) -> Result<bool, BitrotError> {
   let trimmed = name.trim();
   if trimmed.is_empty() {
     return Ok(false);
@@ -91,7 +100,7 @@ fn executable_file_exists(name: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-fn path_exists(path: String) -> Result<bool, String> {
+fn path_exists(path: String) -> Result<bool, BitrotError> {
   let trimmed = path.trim();
   if trimmed.is_empty() {
     return Ok(false);
@@ -100,14 +109,14 @@ fn path_exists(path: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-fn reveal_in_folder(path: String) -> Result<(), String> {
+fn reveal_in_folder(path: String) -> Result<(), BitrotError> {
   let trimmed = path.trim();
   if trimmed.is_empty() {
-    return Err("Path is empty.".to_string());
+    return Err(BitrotError::invalid("Path is empty."));
   }
   let path_buf = PathBuf::from(trimmed);
   if !path_buf.exists() {
-    return Err("Path does not exist.".to_string());
+    return Err(BitrotError::not_found("Path does not exist."));
   }
   let is_dir = path_buf.is_dir();
   let folder = if is_dir {
@@ -116,7 +125,7 @@ fn reveal_in_folder(path: String) -> Result<(), String> {
     path_buf
       .parent()
       .map(Path::to_path_buf)
-      .ok_or_else(|| "Path has no parent directory.".to_string())?
+      .ok_or_else(|| BitrotError::invalid("Path has no parent directory."))?
   };
 
   #[cfg(windows)]
@@ -125,7 +134,7 @@ fn reveal_in_folder(path: String) -> Result<(), String> {
     command.arg(&folder);
     command
       .spawn()
-      .map_err(|error| format!("Failed to open Explorer: {error}"))?;
+      .map_err(|error| BitrotError::io("Failed to open Explorer", error))?;
     return Ok(());
   }
 
@@ -135,7 +144,7 @@ fn reveal_in_folder(path: String) -> Result<(), String> {
     command.arg(&folder);
     command
       .spawn()
-      .map_err(|error| format!("Failed to open Finder: {error}"))?;
+      .map_err(|error| BitrotError::io("Failed to open Finder", error))?;
     return Ok(());
   }
 
@@ -144,21 +153,20 @@ fn reveal_in_folder(path: String) -> Result<(), String> {
     std::process::Command::new("xdg-open")
       .arg(&folder)
       .spawn()
-      .map_err(|error| format!("Failed to open file manager: {error}"))?;
+      .map_err(|error| BitrotError::io("Failed to open file manager", error))?;
     return Ok(());
   }
 }
 
 #[tauri::command]
-fn file_size(path: String) -> Result<u64, String> {
+fn file_size(path: String) -> Result<u64, BitrotError> {
   let trimmed = path.trim();
   if trimmed.is_empty() {
-    return Err("Path is empty.".to_string());
+    return Err(BitrotError::invalid("Path is empty."));
   }
-  let metadata =
-    std::fs::metadata(trimmed).map_err(|error| format!("metadata: {error}"))?;
+  let metadata = std::fs::metadata(trimmed).map_err(|error| BitrotError::io("metadata", error))?;
   if !metadata.is_file() {
-    return Err("Path is not a file.".to_string());
+    return Err(BitrotError::invalid("Path is not a file."));
   }
   Ok(metadata.len())
 }
@@ -168,15 +176,19 @@ pub fn run() {
   tauri::Builder::default()
     .plugin(tauri_plugin_dialog::init())
     .plugin(tauri_plugin_shell::init())
-    .manage(pixelsort::jobs::PixelsortJobs::default())
-    .manage(byte_range::ModuloMappingJobs::default())
-    .manage(block_shift::BlockShiftJobs::default())
-    .manage(vaporwave::VaporwaveJobs::default())
-    .manage(kaleidoscope::KaleidoscopeJobs::default())
+    .manage(native_jobs::NativeJobs::default())
     .manage(native_preview::PreviewBuffers::default())
     .manage(ffmpeg_jobs::FfmpegJobs::default())
+    .setup(|app| {
+      // Restore the saved render queue and pick up where it left off.
+      let queue = render_queue::RenderQueueState::load(app.handle())?;
+      app.manage(queue);
+      render_queue::pump_render_queue(app.handle());
+      Ok(())
+    })
     .invoke_handler(tauri::generate_handler![
       datamosh_bitstream,
+      ffmpeg_capabilities::ffmpeg_capabilities,
       ffmpeg_jobs::ffmpeg_execute,
       ffmpeg_jobs::ffmpeg_spawn,
       ffmpeg_jobs::ffmpeg_kill,
@@ -187,6 +199,16 @@ pub fn run() {
       path_exists,
       reveal_in_folder,
       file_size,
+      render_queue::render_queue_list,
+      render_queue::render_queue_add,
+      render_queue::render_queue_move,
+      render_queue::render_queue_retry,
+      render_queue::render_queue_cancel,
+      render_queue::render_queue_remove,
+      render_queue::render_queue_set_concurrency,
+      native_pipeline::native_resume,
+      native_pipeline::render_replay,
+      native_pipeline::render_still,
       byte_range::modulo_mapping_process,
       byte_range::modulo_mapping_cancel,
       byte_range::modulo_mapping_preview_start,
@@ -199,6 +221,12 @@ pub fn run() {
       block_shift::block_shift_preview_append,
       block_shift::block_shift_preview_finish,
       block_shift::block_shift_preview_discard,
+      chain::chain_process,
+      chain::chain_cancel,
+      chain::chain_preview_start,
+      chain::chain_preview_append,
+      chain::chain_preview_finish,
+      chain::chain_preview_discard,
       kaleidoscope::kaleidoscope_process,
       kaleidoscope::kaleidoscope_cancel,
       kaleidoscope::kaleidoscope_preview_start,
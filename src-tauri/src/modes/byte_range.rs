@@ -1,25 +1,29 @@
-// Byte-range (modulo mapping) commands, wired to focused submodules.
+// Byte-range (modulo mapping) commands; the pixel math lives in bitrot-core.
 // The command functions live here to keep tauri handler paths stable.
-mod jobs;
-mod math;
-mod pipeline;
-mod preview;
-
+use bitrot_core::modes::byte_range::ModuloMappingConfig;
+use bitrot_core::automation::Automation;
+use bitrot_core::error::BitrotError;
+use bitrot_core::mask::Mask;
+use bitrot_core::pipeline::{NativeEncoding, NativeJobRequest};
+use bitrot_core::timeline::ActiveWindow;
 use tauri::{AppHandle, State, Window};
 
-use crate::native::pipeline::NativeEncoding;
-use crate::native::preview::PreviewBuffers;
-
-pub use jobs::ModuloMappingJobs;
-pub use math::ModuloMappingConfig;
-pub use preview::ModuloMappingPreviewResponse;
+use crate::native::jobs::{cancel_native_job, NativeJobs};
+use crate::native::pipeline::run_effect_job;
+use crate::native::preview::{
+  append_preview_upload,
+  finish_effect_preview,
+  start_preview_upload,
+  PreviewBuffers,
+  PreviewResponse
+};
 
 // Command wrappers keep the IPC surface close to the module boundary.
 #[tauri::command]
 pub async fn modulo_mapping_process(
   window: Window,
   app: AppHandle,
-  state: State<'_, ModuloMappingJobs>,
+  state: State<'_, NativeJobs>,
   job_id: String,
   input_path: String,
   output_path: String,
@@ -30,13 +34,13 @@ pub async fn modulo_mapping_process(
   trim_start_seconds: Option<f64>,
   trim_end_seconds: Option<f64>,
   config: ModuloMappingConfig,
+  automation: Option<Automation>,
+  mask: Option<Mask>,
+  active_windows: Option<Vec<ActiveWindow>>,
   preview_enabled: bool,
   encoding: NativeEncoding
-) -> Result<(), String> {
-  pipeline::modulo_mapping_process(
-    window,
-    app,
-    state,
+) -> Result<(), BitrotError> {
+  let request = NativeJobRequest {
     job_id,
     input_path,
     output_path,
@@ -46,29 +50,30 @@ pub async fn modulo_mapping_process(
     duration_seconds,
     trim_start_seconds,
     trim_end_seconds,
-    config,
     preview_enabled,
-    encoding
-  )
-  .await
+    encoding,
+    matte: mask.as_ref().and_then(|mask| mask.matte.clone())
+  };
+  run_effect_job(window, app, &state, request, config, automation, mask, active_windows).await
 }
 
 #[tauri::command]
 pub async fn modulo_mapping_cancel(
   job_id: String,
-  state: State<'_, ModuloMappingJobs>
-) -> Result<(), String> {
-  jobs::modulo_mapping_cancel(job_id, state).await
+  state: State<'_, NativeJobs>
+) -> Result<(), BitrotError> {
+  cancel_native_job(&state, &job_id, "modulo mapping")
 }
 
+// Chunked manual preview uploads keep IPC payloads well under size limits.
 #[tauri::command]
 pub fn modulo_mapping_preview_start(
   preview_id: String,
   width: u32,
   height: u32,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
-  preview::modulo_mapping_preview_start(preview_id, width, height, state)
+) -> Result<(), BitrotError> {
+  start_preview_upload(&state, &preview_id, width, height)
 }
 
 #[tauri::command]
@@ -76,8 +81,8 @@ pub fn modulo_mapping_preview_append(
   preview_id: String,
   chunk: Vec<u8>,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
-  preview::modulo_mapping_preview_append(preview_id, chunk, state)
+) -> Result<(), BitrotError> {
+  append_preview_upload(&state, &preview_id, chunk)
 }
 
 #[tauri::command]
@@ -86,14 +91,15 @@ pub async fn modulo_mapping_preview_finish(
   preview_id: String,
   config: ModuloMappingConfig,
   state: State<'_, PreviewBuffers>
-) -> Result<ModuloMappingPreviewResponse, String> {
-  preview::modulo_mapping_preview_finish(app, preview_id, config, state).await
+) -> Result<PreviewResponse, BitrotError> {
+  finish_effect_preview(&app, &state, &preview_id, &config).await
 }
 
 #[tauri::command]
 pub fn modulo_mapping_preview_discard(
   preview_id: String,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
-  preview::modulo_mapping_preview_discard(preview_id, state)
+) -> Result<(), BitrotError> {
+  state.discard(&preview_id);
+  Ok(())
 }
@@ -0,0 +1,105 @@
+// Tauri commands for the native effect chain; step dispatch lives in bitrot-core.
+use bitrot_core::modes::chain::ChainConfig;
+use bitrot_core::automation::Automation;
+use bitrot_core::error::BitrotError;
+use bitrot_core::mask::Mask;
+use bitrot_core::pipeline::{NativeEncoding, NativeJobRequest};
+use bitrot_core::timeline::ActiveWindow;
+use tauri::{AppHandle, State, Window};
+
+use crate::native::jobs::{cancel_native_job, NativeJobs};
+use crate::native::pipeline::run_effect_job;
+use crate::native::preview::{
+  append_preview_upload,
+  finish_effect_preview,
+  start_preview_upload,
+  PreviewBuffers,
+  PreviewResponse
+};
+
+#[tauri::command]
+pub fn chain_preview_start(
+  preview_id: String,
+  width: u32,
+  height: u32,
+  state: State<'_, PreviewBuffers>
+) -> Result<(), BitrotError> {
+  start_preview_upload(&state, &preview_id, width, height)
+}
+
+#[tauri::command]
+pub fn chain_preview_append(
+  preview_id: String,
+  chunk: Vec<u8>,
+  state: State<'_, PreviewBuffers>
+) -> Result<(), BitrotError> {
+  append_preview_upload(&state, &preview_id, chunk)
+}
+
+#[tauri::command]
+pub async fn chain_preview_finish(
+  app: AppHandle,
+  preview_id: String,
+  config: ChainConfig,
+  state: State<'_, PreviewBuffers>
+) -> Result<PreviewResponse, BitrotError> {
+  finish_effect_preview(&app, &state, &preview_id, &config).await
+}
+
+#[tauri::command]
+pub fn chain_preview_discard(
+  preview_id: String,
+  state: State<'_, PreviewBuffers>
+) -> Result<(), BitrotError> {
+  state.discard(&preview_id);
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn chain_cancel(
+  job_id: String,
+  state: State<'_, NativeJobs>
+) -> Result<(), BitrotError> {
+  cancel_native_job(&state, &job_id, "effect chain")
+}
+
+#[tauri::command]
+pub async fn chain_process(
+  window: Window,
+  app: AppHandle,
+  state: State<'_, NativeJobs>,
+  job_id: String,
+  input_path: String,
+  output_path: String,
+  width: u32,
+  height: u32,
+  fps: f64,
+  duration_seconds: Option<f64>,
+  trim_start_seconds: Option<f64>,
+  trim_end_seconds: Option<f64>,
+  config: ChainConfig,
+  automation: Option<Automation>,
+  mask: Option<Mask>,
+  active_windows: Option<Vec<ActiveWindow>>,
+  preview_enabled: bool,
+  encoding: NativeEncoding
+) -> Result<(), BitrotError> {
+  if config.steps.is_empty() {
+    return Err(BitrotError::invalid("Effect chain has no steps."));
+  }
+  let request = NativeJobRequest {
+    job_id,
+    input_path,
+    output_path,
+    width,
+    height,
+    fps,
+    duration_seconds,
+    trim_start_seconds,
+    trim_end_seconds,
+    preview_enabled,
+    encoding,
+    matte: mask.as_ref().and_then(|mask| mask.matte.clone())
+  };
+  run_effect_job(window, app, &state, request, config, automation, mask, active_windows).await
+}
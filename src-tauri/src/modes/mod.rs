@@ -1,8 +1,8 @@
-// Effect-specific Rust pipelines and helpers.
+// Tauri command surface per effect mode; the effects themselves live in bitrot-core.
 
 pub mod byte_range;
 pub mod block_shift;
-pub mod datamosh;
+pub mod chain;
 pub mod kaleidoscope;
 pub mod pixelsort;
 pub mod vaporwave;
@@ -1,6 +1,4 @@
-// Pixelsort module split into focused submodules.
+// Pixelsort tauri commands; the sorting algorithm lives in bitrot-core.
 
-pub mod algo;
 pub mod jobs;
 pub mod preview;
-pub mod workspace;
@@ -1,35 +1,12 @@
 // Preview IPC + PNG encoding helpers for the pixelsort pipeline.
 
-use std::path::Path;
-
+use bitrot_core::error::BitrotError;
+use bitrot_core::modes::pixelsort::algo::PixelsortConfig;
+use bitrot_core::preview::preview_expected_len;
 use serde::Serialize;
-use tauri::{AppHandle, Emitter, State, Window};
-
-use super::algo::{pixelsort_frame, PixelsortConfig};
-use super::workspace::FrameWorkspace;
-use crate::native::pipeline::cleanup_file;
-use crate::native::preview::{
-  build_preview_unique_path,
-  downscale_rgba_nearest,
-  encode_preview_frame,
-  preview_expected_len,
-  resolve_preview_size,
-  PreviewBuffers
-};
+use tauri::{AppHandle, Emitter, State};
 
-#[derive(Debug, Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct PixelsortPreviewEvent {
-  job_id: String,
-  frame: u64,
-  path: String
-}
-
-#[derive(Debug, Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct PixelsortPreviewResponse {
-  pub path: String
-}
+use crate::native::preview::{render_effect_preview, PreviewBuffers, PreviewResponse};
 
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -82,60 +59,6 @@ fn emit_preview_debug(app: &AppHandle, payload: PixelsortPreviewDebug) {
   eprintln!("[pixelsort-preview] {}", parts.join(" "));
 }
 
-pub(crate) fn emit_preview(window: &Window, job_id: &str, frame: u64, path: &Path) {
-  let payload = PixelsortPreviewEvent {
-    job_id: job_id.to_string(),
-    frame,
-    path: path.to_string_lossy().into_owned()
-  };
-  let _ = window.emit("pixelsort-preview", payload);
-}
-
-// Processes RGBA bytes into a pixelsorted PNG and returns the output path.
-async fn render_pixelsort_preview(
-  app: &AppHandle,
-  width: u32,
-  height: u32,
-  frame: &[u8],
-  config: &PixelsortConfig
-) -> Result<PixelsortPreviewResponse, String> {
-  let expected = preview_expected_len(width, height)?;
-  if frame.len() < expected {
-    return Err(format!(
-      "Preview buffer size mismatch (expected {expected} bytes, got {}).",
-      frame.len()
-    ));
-  }
-
-  let mut workspace = FrameWorkspace::new(width as usize, height as usize);
-  let processed = pixelsort_frame(&frame[..expected], &mut workspace, config, 0);
-  let (preview_width, preview_height) = resolve_preview_size(width, height);
-
-  let preview_path = build_preview_unique_path("manual");
-  let encode_result = if preview_width == width && preview_height == height {
-    encode_preview_frame(app, processed, width, height, &preview_path).await
-  } else {
-    let preview_frame =
-      downscale_rgba_nearest(processed, width, height, preview_width, preview_height);
-    encode_preview_frame(
-      app,
-      &preview_frame,
-      preview_width,
-      preview_height,
-      &preview_path
-    )
-    .await
-  };
-  if let Err(error) = encode_result {
-    cleanup_file(&preview_path);
-    return Err(error);
-  }
-
-  Ok(PixelsortPreviewResponse {
-    path: preview_path.to_string_lossy().into_owned()
-  })
-}
-
 // Chunked manual preview uploads keep IPC payloads well under size limits.
 #[tauri::command]
 pub fn pixelsort_preview_start(
@@ -144,7 +67,7 @@ pub fn pixelsort_preview_start(
   width: u32,
   height: u32,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   let expected_len = preview_expected_len(width, height)?;
   let result = state.start(&preview_id, width, height, expected_len);
   emit_preview_debug(
@@ -158,7 +81,7 @@ pub fn pixelsort_preview_start(
       received_len: Some(0),
       chunk_len: None,
       chunk_index: None,
-      message: result.as_ref().err().cloned()
+      message: result.as_ref().err().map(|error| error.message.clone())
     }
   );
   result
@@ -170,7 +93,7 @@ pub fn pixelsort_preview_append(
   preview_id: String,
   chunk: Vec<u8>,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   if chunk.is_empty() {
     return Ok(());
   }
@@ -199,7 +122,7 @@ pub async fn pixelsort_preview_finish(
   preview_id: String,
   config: PixelsortConfig,
   state: State<'_, PreviewBuffers>
-) -> Result<PixelsortPreviewResponse, String> {
+) -> Result<PreviewResponse, BitrotError> {
   let buffer = state.finish(&preview_id)?;
   if buffer.data.len() != buffer.expected_len {
     let message = format!(
@@ -221,7 +144,7 @@ pub async fn pixelsort_preview_finish(
         message: Some(message.clone())
       }
     );
-    return Err(message);
+    return Err(BitrotError::invalid(message));
   }
   emit_preview_debug(
     &app,
@@ -238,11 +161,10 @@ pub async fn pixelsort_preview_finish(
     }
   );
   let result =
-    render_pixelsort_preview(&app, buffer.width, buffer.height, &buffer.data, &config)
-      .await;
+    render_effect_preview(&app, buffer.width, buffer.height, &buffer.data, &config).await;
   let render_message = match &result {
     Ok(response) => Some(format!("ok path={}", response.path)),
-    Err(error) => Some(error.clone())
+    Err(error) => Some(error.message.clone())
   };
   emit_preview_debug(
     &app,
@@ -266,7 +188,7 @@ pub fn pixelsort_preview_discard(
   app: AppHandle,
   preview_id: String,
   state: State<'_, PreviewBuffers>
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   state.discard(&preview_id);
   emit_preview_debug(
     &app,
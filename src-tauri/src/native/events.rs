@@ -0,0 +1,211 @@
+// Progress/log/preview events emitted by native jobs.
+// Every event goes out on the unified `job-event` channel; the per-mode names
+// ("<tag>-progress") are still emitted so existing listeners keep working.
+use std::{
+  path::{Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc
+  }
+};
+
+use bitrot_core::error::BitrotError;
+use bitrot_core::events::{JobEvent, JobEventPayload, JobStage, LogLevel, JOB_EVENT_CHANNEL};
+use bitrot_core::preview::{downscale_rgba_nearest, resolve_preview_size};
+use bitrot_core::progress::{JobReporter, ProgressSnapshot};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Window};
+
+use crate::native::preview::encode_preview_frame;
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct NativeProgress<'a> {
+  job_id: &'a str,
+  #[serde(flatten)]
+  progress: &'a ProgressSnapshot
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct NativeLog {
+  job_id: String,
+  message: String
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct NativePreviewEvent {
+  job_id: String,
+  frame: u64,
+  path: String
+}
+
+// Throttled live preview: at most one PNG encode in flight, roughly twice per second.
+struct LivePreview {
+  app: AppHandle,
+  path: PathBuf,
+  every: u64,
+  last_frame: AtomicU64,
+  inflight: Arc<AtomicBool>
+}
+
+impl LivePreview {
+  fn wants(&self, frame_index: u64) -> bool {
+    frame_index.saturating_sub(self.last_frame.load(Ordering::Relaxed)) >= self.every
+      && !self.inflight.load(Ordering::Relaxed)
+  }
+
+  fn maybe_emit(&self, events: &JobEvents, frame: &[u8], width: u32, height: u32, frame_index: u64) {
+    if !self.wants(frame_index) {
+      return;
+    }
+    self.last_frame.store(frame_index, Ordering::Relaxed);
+    self.inflight.store(true, Ordering::Relaxed);
+    let (preview_width, preview_height) = resolve_preview_size(width, height);
+    let preview_frame =
+      downscale_rgba_nearest(frame, width, height, preview_width, preview_height);
+    let preview_path = self.path.clone();
+    let preview_events = events.clone();
+    let preview_app = self.app.clone();
+    let preview_inflight = self.inflight.clone();
+    tauri::async_runtime::spawn(async move {
+      let result = encode_preview_frame(
+        &preview_app,
+        &preview_frame,
+        preview_width,
+        preview_height,
+        &preview_path
+      )
+      .await;
+      preview_inflight.store(false, Ordering::Relaxed);
+      if result.is_ok() {
+        preview_events.preview(frame_index, &preview_path);
+      }
+    });
+  }
+}
+
+// Commands emit through their calling window; queued jobs have no window and use the app.
+#[derive(Clone)]
+enum EventTarget {
+  Window(Window),
+  App(AppHandle)
+}
+
+impl EventTarget {
+  fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+    let _ = match self {
+      EventTarget::Window(window) => window.emit(event, payload),
+      EventTarget::App(app) => app.emit(event, payload)
+    };
+  }
+}
+
+#[derive(Clone)]
+pub struct JobEvents {
+  target: EventTarget,
+  job_id: String,
+  tag: &'static str,
+  live_preview: Option<Arc<LivePreview>>
+}
+
+impl JobEvents {
+  pub fn new(window: Window, job_id: &str, tag: &'static str) -> Self {
+    Self {
+      target: EventTarget::Window(window),
+      job_id: job_id.to_string(),
+      tag,
+      live_preview: None
+    }
+  }
+
+  pub fn for_app(app: AppHandle, job_id: &str, tag: &'static str) -> Self {
+    Self {
+      target: EventTarget::App(app),
+      job_id: job_id.to_string(),
+      tag,
+      live_preview: None
+    }
+  }
+
+  // Samples processed frames into `<tag>-preview` events written to `path`.
+  pub fn with_live_preview(mut self, app: AppHandle, path: PathBuf, fps: f64) -> Self {
+    let interval = (fps / 2.0).round() as u64;
+    self.live_preview = Some(Arc::new(LivePreview {
+      app,
+      path,
+      every: interval.clamp(5, 60),
+      last_frame: AtomicU64::new(0),
+      inflight: Arc::new(AtomicBool::new(false))
+    }));
+    self
+  }
+
+  fn emit_job_event(&self, payload: JobEventPayload) {
+    self
+      .target
+      .emit(JOB_EVENT_CHANNEL, JobEvent::new(&self.job_id, self.tag, payload));
+  }
+
+  pub fn started(&self) {
+    self.emit_job_event(JobEventPayload::Started);
+  }
+
+  // Terminal event; worker panics surface here as failures too.
+  pub fn finished(&self, result: &Result<(), BitrotError>) {
+    self.emit_job_event(JobEventPayload::from_result(result));
+  }
+
+  pub fn preview(&self, frame: u64, path: &Path) {
+    let path = path.to_string_lossy().into_owned();
+    let payload = NativePreviewEvent {
+      job_id: self.job_id.clone(),
+      frame,
+      path: path.clone()
+    };
+    self.target.emit(&format!("{}-preview", self.tag), payload);
+    self.emit_job_event(JobEventPayload::Preview { frame, path });
+  }
+}
+
+impl JobReporter for JobEvents {
+  fn log(&self, message: &str) {
+    self.log_at(LogLevel::Info, message);
+  }
+
+  fn log_at(&self, level: LogLevel, message: &str) {
+    let payload = NativeLog {
+      job_id: self.job_id.clone(),
+      message: message.to_string()
+    };
+    self.target.emit(&format!("{}-log", self.tag), payload);
+    self.emit_job_event(JobEventPayload::log(level, message));
+  }
+
+  fn progress(&self, progress: &ProgressSnapshot) {
+    let payload = NativeProgress {
+      job_id: &self.job_id,
+      progress
+    };
+    self.target.emit(&format!("{}-progress", self.tag), payload);
+    self.emit_job_event(JobEventPayload::Progress(progress.clone()));
+  }
+
+  fn stage(&self, stage: JobStage) {
+    self.emit_job_event(JobEventPayload::Stage { stage });
+  }
+
+  fn wants_frame(&self, frame_index: u64) -> bool {
+    self
+      .live_preview
+      .as_ref()
+      .is_some_and(|live_preview| live_preview.wants(frame_index))
+  }
+
+  fn frame(&self, frame_index: u64, rgba: &[u8], width: u32, height: u32) {
+    if let Some(live_preview) = self.live_preview.as_ref() {
+      live_preview.maybe_emit(self, rgba, width, height, frame_index);
+    }
+  }
+}
@@ -1,4 +1,4 @@
-// Job registry + cancellation for modulo mapping work.
+// Job registry + cancellation shared by every native effect pipeline.
 use std::{
   collections::HashMap,
   sync::{
@@ -7,12 +7,12 @@ use std::{
   }
 };
 
-use tauri::State;
+use bitrot_core::error::BitrotError;
 
 #[derive(Default)]
-pub struct ModuloMappingJobs(Mutex<HashMap<String, Arc<AtomicBool>>>);
+pub struct NativeJobs(Mutex<HashMap<String, Arc<AtomicBool>>>);
 
-impl ModuloMappingJobs {
+impl NativeJobs {
   pub fn register(&self, job_id: &str) -> Arc<AtomicBool> {
     let mut lock = self
       .0
@@ -44,13 +44,15 @@ impl ModuloMappingJobs {
   }
 }
 
-pub(super) async fn modulo_mapping_cancel(
-  job_id: String,
-  state: State<'_, ModuloMappingJobs>
-) -> Result<(), String> {
-  if state.cancel(&job_id) {
+// Shared body for the per-mode cancel commands.
+pub fn cancel_native_job(
+  jobs: &NativeJobs,
+  job_id: &str,
+  mode_name: &str
+) -> Result<(), BitrotError> {
+  if jobs.cancel(job_id) {
     Ok(())
   } else {
-    Err("Unknown modulo mapping job".into())
+    Err(BitrotError::not_found(format!("Unknown {mode_name} job")))
   }
 }
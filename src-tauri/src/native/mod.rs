@@ -1,4 +1,8 @@
-// Shared native helpers used by Rust pipelines (decode/encode, preview buffers).
+// Tauri glue for the bitrot-core native host (job registry, window events, preview buffers,
+// render queue).
 
+pub mod events;
+pub mod jobs;
 pub mod pipeline;
 pub mod preview;
+pub mod queue;
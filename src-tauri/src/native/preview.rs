@@ -6,11 +6,15 @@ use std::{
   time::{Duration, Instant, SystemTime, UNIX_EPOCH}
 };
 
+use bitrot_core::effect::NativeEffect;
+use bitrot_core::error::{BitrotError, ErrorCode};
+use bitrot_core::pipeline::cleanup_file;
+use bitrot_core::preview::{downscale_rgba_nearest, preview_expected_len, resolve_preview_size};
+use serde::Serialize;
 use tauri::AppHandle;
 use tauri_plugin_shell::process::CommandEvent;
 
 use crate::ffmpeg::resolve_ffmpeg_command;
-use crate::native::pipeline::cleanup_file;
 
 // Holds chunked preview buffers so large RGBA payloads can arrive safely over IPC.
 #[derive(Default)]
@@ -34,14 +38,14 @@ impl PreviewBuffers {
     width: u32,
     height: u32,
     expected_len: usize
-  ) -> Result<(), String> {
+  ) -> Result<(), BitrotError> {
     let mut lock = self
       .0
       .lock()
       .unwrap_or_else(|error| error.into_inner());
     Self::prune_stale(&mut lock);
     if lock.contains_key(preview_id) {
-      return Err("Preview upload already exists.".into());
+      return Err(BitrotError::invalid("Preview upload already exists."));
     }
     lock.insert(
       preview_id.to_string(),
@@ -56,31 +60,31 @@ impl PreviewBuffers {
     Ok(())
   }
 
-  pub(crate) fn append(&self, preview_id: &str, chunk: Vec<u8>) -> Result<(), String> {
+  pub(crate) fn append(&self, preview_id: &str, chunk: Vec<u8>) -> Result<(), BitrotError> {
     let mut lock = self
       .0
       .lock()
       .unwrap_or_else(|error| error.into_inner());
     let buffer = lock
       .get_mut(preview_id)
-      .ok_or_else(|| "Preview upload not found.".to_string())?;
+      .ok_or_else(|| BitrotError::not_found("Preview upload not found."))?;
     if buffer.data.len() + chunk.len() > buffer.expected_len {
       lock.remove(preview_id);
-      return Err("Preview buffer overflow.".into());
+      return Err(BitrotError::invalid("Preview buffer overflow."));
     }
     buffer.data.extend_from_slice(&chunk);
     buffer.last_updated = Instant::now();
     Ok(())
   }
 
-  pub(crate) fn finish(&self, preview_id: &str) -> Result<PreviewBuffer, String> {
+  pub(crate) fn finish(&self, preview_id: &str) -> Result<PreviewBuffer, BitrotError> {
     let mut lock = self
       .0
       .lock()
       .unwrap_or_else(|error| error.into_inner());
     lock
       .remove(preview_id)
-      .ok_or_else(|| "Preview upload not found.".to_string())
+      .ok_or_else(|| BitrotError::not_found("Preview upload not found."))
   }
 
   pub(crate) fn discard(&self, preview_id: &str) {
@@ -97,56 +101,6 @@ impl PreviewBuffers {
   }
 }
 
-const MAX_PREVIEW_DIMENSION: u32 = 1280;
-
-// Validates preview dimensions and returns the expected RGBA byte length.
-pub fn preview_expected_len(width: u32, height: u32) -> Result<usize, String> {
-  if width < 2 || height < 2 {
-    return Err("Preview dimensions are invalid.".into());
-  }
-  (width as usize)
-    .checked_mul(height as usize)
-    .and_then(|value| value.checked_mul(4))
-    .ok_or_else(|| "Preview dimensions are too large.".to_string())
-}
-
-pub fn resolve_preview_size(width: u32, height: u32) -> (u32, u32) {
-  let max_dim = width.max(height);
-  if max_dim <= MAX_PREVIEW_DIMENSION {
-    return (width, height);
-  }
-
-  let scale = MAX_PREVIEW_DIMENSION as f64 / max_dim as f64;
-  let scaled_width = ((width as f64) * scale).round().max(1.0) as u32;
-  let scaled_height = ((height as f64) * scale).round().max(1.0) as u32;
-  (scaled_width, scaled_height)
-}
-
-// Simple nearest-neighbor resize for preview buffers.
-pub fn downscale_rgba_nearest(
-  src: &[u8],
-  src_width: u32,
-  src_height: u32,
-  dst_width: u32,
-  dst_height: u32
-) -> Vec<u8> {
-  if src_width == dst_width && src_height == dst_height {
-    return src.to_vec();
-  }
-
-  let mut dst = vec![0u8; (dst_width as usize) * (dst_height as usize) * 4];
-  for y in 0..dst_height {
-    let src_y = (y as u64 * src_height as u64 / dst_height as u64) as u32;
-    for x in 0..dst_width {
-      let src_x = (x as u64 * src_width as u64 / dst_width as u64) as u32;
-      let src_idx = ((src_y * src_width + src_x) * 4) as usize;
-      let dst_idx = ((y * dst_width + x) * 4) as usize;
-      dst[dst_idx..dst_idx + 4].copy_from_slice(&src[src_idx..src_idx + 4]);
-    }
-  }
-  dst
-}
-
 pub fn build_preview_path(tag: &str) -> PathBuf {
   let safe_tag = tag.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
   let file_name = format!("bitrot-preview-{safe_tag}.png");
@@ -191,7 +145,7 @@ fn build_preview_encode_args(width: u32, height: u32, output_path: &PathBuf) ->
 
 async fn wait_for_exit(
   mut rx: tauri::async_runtime::Receiver<CommandEvent>
-) -> Result<(i32, Vec<String>), String> {
+) -> Result<(i32, Vec<String>), BitrotError> {
   let mut code = None;
   let mut errors = Vec::new();
   while let Some(event) = rx.recv().await {
@@ -220,26 +174,120 @@ pub async fn encode_preview_frame(
   width: u32,
   height: u32,
   output_path: &PathBuf
-) -> Result<(), String> {
+) -> Result<(), BitrotError> {
   let args = build_preview_encode_args(width, height, output_path);
   let encode_cmd = resolve_ffmpeg_command(app, "ffmpeg")?.args(args);
   let (encode_rx, mut encode_child) = encode_cmd
     .spawn()
-    .map_err(|error| format!("Failed to spawn preview encoder: {error}"))?;
-  encode_child
-    .write(frame)
-    .map_err(|error| format!("Failed to write preview frame: {error}"))?;
+    .map_err(|error| {
+      BitrotError::new(ErrorCode::SpawnFailed, format!("Failed to spawn preview encoder: {error}"))
+    })?;
+  encode_child.write(frame).map_err(|error| {
+    BitrotError::new(ErrorCode::PreviewFailed, format!("Failed to write preview frame: {error}"))
+  })?;
   drop(encode_child);
 
   let (code, errors) = wait_for_exit(encode_rx).await?;
   if code != 0 {
-    let message = errors.join("\n");
     cleanup_file(output_path);
-    return Err(if message.is_empty() {
-      format!("Preview encoder failed with exit code {code}")
-    } else {
-      message
-    });
+    return Err(
+      BitrotError::new(
+        ErrorCode::PreviewFailed,
+        format!("Preview encoder failed with exit code {code}")
+      )
+      .with_stderr(&errors.join("\n"))
+    );
   }
   Ok(())
 }
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewResponse {
+  pub path: String
+}
+
+// Chunked manual preview uploads keep IPC payloads well under size limits.
+pub fn start_preview_upload(
+  state: &PreviewBuffers,
+  preview_id: &str,
+  width: u32,
+  height: u32
+) -> Result<(), BitrotError> {
+  let expected_len = preview_expected_len(width, height)?;
+  state.start(preview_id, width, height, expected_len)
+}
+
+pub fn append_preview_upload(
+  state: &PreviewBuffers,
+  preview_id: &str,
+  chunk: Vec<u8>
+) -> Result<(), BitrotError> {
+  if chunk.is_empty() {
+    return Ok(());
+  }
+  state.append(preview_id, chunk)
+}
+
+// Takes a finished upload and renders it through the effect.
+pub async fn finish_effect_preview<E: NativeEffect>(
+  app: &AppHandle,
+  state: &PreviewBuffers,
+  preview_id: &str,
+  effect: &E
+) -> Result<PreviewResponse, BitrotError> {
+  let buffer = state.finish(preview_id)?;
+  if buffer.data.len() != buffer.expected_len {
+    return Err(BitrotError::invalid(format!(
+      "Preview buffer size mismatch (expected {} bytes, got {}).",
+      buffer.expected_len,
+      buffer.data.len()
+    )));
+  }
+  render_effect_preview(app, buffer.width, buffer.height, &buffer.data, effect).await
+}
+
+// Processes RGBA bytes through an effect into a PNG and returns the output path.
+pub async fn render_effect_preview<E: NativeEffect>(
+  app: &AppHandle,
+  width: u32,
+  height: u32,
+  frame: &[u8],
+  effect: &E
+) -> Result<PreviewResponse, BitrotError> {
+  let expected = preview_expected_len(width, height)?;
+  if frame.len() < expected {
+    return Err(BitrotError::invalid(format!(
+      "Preview buffer size mismatch (expected {expected} bytes, got {}).",
+      frame.len()
+    )));
+  }
+
+  let mut workspace = effect.workspace(width as usize, height as usize);
+  let processed = effect.process_frame(&frame[..expected], &mut workspace, 0);
+  let (preview_width, preview_height) = resolve_preview_size(width, height);
+
+  let preview_path = build_preview_unique_path("manual");
+  let encode_result = if preview_width == width && preview_height == height {
+    encode_preview_frame(app, processed, width, height, &preview_path).await
+  } else {
+    let preview_frame =
+      downscale_rgba_nearest(processed, width, height, preview_width, preview_height);
+    encode_preview_frame(
+      app,
+      &preview_frame,
+      preview_width,
+      preview_height,
+      &preview_path
+    )
+    .await
+  };
+  if let Err(error) = encode_result {
+    cleanup_file(&preview_path);
+    return Err(error);
+  }
+
+  Ok(PreviewResponse {
+    path: preview_path.to_string_lossy().into_owned()
+  })
+}
@@ -0,0 +1,258 @@
+// Tauri side of the render queue: persisted state, the scheduler and the queue commands.
+// Entries run through the same core job runner as the CLI; job events are keyed by the
+// entry id, and queue changes go out as `render-queue-changed`.
+use std::{
+  fs,
+  path::PathBuf,
+  process::Command,
+  sync::{atomic::AtomicBool, Arc, Mutex}
+};
+
+use bitrot_core::checkpoint::resumable_checkpoint;
+use bitrot_core::error::BitrotError;
+use bitrot_core::job::{JobRunner, JobSpec};
+use bitrot_core::queue::{QueueEntry, RenderQueue};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::ffmpeg::resolve_ffmpeg_command;
+use crate::native::events::JobEvents;
+use crate::native::jobs::NativeJobs;
+
+const QUEUE_FILE_NAME: &str = "render-queue.json";
+const QUEUE_CHANGED_EVENT: &str = "render-queue-changed";
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderQueueSnapshot {
+  concurrency: usize,
+  entries: Vec<QueueEntry>,
+  // Why the saved queue could not be loaded, and where it was moved. Only the first
+  // `render_queue_list` call carries it.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  load_error: Option<BitrotError>
+}
+
+pub struct RenderQueueState {
+  queue: Mutex<RenderQueue>,
+  path: PathBuf,
+  load_error: Mutex<Option<BitrotError>>,
+  jobs: NativeJobs
+}
+
+impl RenderQueueState {
+  // A queue file that can't be read is moved aside as `render-queue.json.bad`, so the next
+  // save doesn't overwrite it, and the app starts with an empty queue instead of failing.
+  pub fn load(app: &AppHandle) -> Result<Self, BitrotError> {
+    let path = app
+      .path()
+      .app_data_dir()
+      .map_err(|error| BitrotError::internal(format!("Failed to resolve app data dir: {error}")))?
+      .join(QUEUE_FILE_NAME);
+    let (queue, load_error) = match RenderQueue::load(&path) {
+      Ok(queue) => (queue, None),
+      Err(mut error) => {
+        let bad_path = path.with_extension("json.bad");
+        error.message = match fs::rename(&path, &bad_path) {
+          Ok(()) => format!(
+            "{} The saved queue was moved to {}.",
+            error.message,
+            bad_path.display()
+          ),
+          Err(rename_error) => format!(
+            "{} The saved queue could not be moved aside either ({rename_error}).",
+            error.message
+          )
+        };
+        (RenderQueue::default(), Some(error))
+      }
+    };
+    Ok(Self {
+      queue: Mutex::new(queue),
+      path,
+      load_error: Mutex::new(load_error),
+      jobs: NativeJobs::default()
+    })
+  }
+
+  // Applies `change`, persists the queue and tells listeners about the new state.
+  fn update<T>(
+    &self,
+    app: &AppHandle,
+    change: impl FnOnce(&mut RenderQueue) -> Result<T, BitrotError>
+  ) -> Result<T, BitrotError> {
+    let mut queue = self
+      .queue
+      .lock()
+      .unwrap_or_else(|error| error.into_inner());
+    let value = change(&mut queue)?;
+    let saved = queue.save(&self.path);
+    let _ = app.emit(QUEUE_CHANGED_EVENT, self.snapshot_of(&queue));
+    saved.map(|()| value)
+  }
+
+  fn snapshot(&self) -> RenderQueueSnapshot {
+    let queue = self
+      .queue
+      .lock()
+      .unwrap_or_else(|error| error.into_inner());
+    self.snapshot_of(&queue)
+  }
+
+  fn snapshot_of(&self, queue: &RenderQueue) -> RenderQueueSnapshot {
+    RenderQueueSnapshot {
+      concurrency: queue.concurrency,
+      entries: queue.entries.clone(),
+      load_error: None
+    }
+  }
+}
+
+// Starts queued entries until the concurrency limit is reached.
+pub fn pump_render_queue(app: &AppHandle) {
+  let state = app.state::<RenderQueueState>();
+  loop {
+    // A failed save still starts the entry; the next successful save catches up.
+    let mut claimed = None;
+    let _ = state.update(app, |queue| {
+      claimed = queue.start_next();
+      Ok(())
+    });
+    let Some(entry) = claimed else {
+      break;
+    };
+    // Registered before the task starts so a cancel right after the claim isn't lost.
+    let cancel_flag = state.jobs.register(&entry.id);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      run_queue_entry(app, entry, cancel_flag).await;
+    });
+  }
+}
+
+async fn run_queue_entry(app: AppHandle, entry: QueueEntry, cancel_flag: Arc<AtomicBool>) {
+  let state = app.state::<RenderQueueState>();
+  let job_id = entry.id.clone();
+  let events = JobEvents::for_app(app.clone(), &job_id, entry.spec.mode.tag());
+  let worker_app = app.clone();
+  events.started();
+  let worker_events = events.clone();
+
+  let result = tauri::async_runtime::spawn_blocking(move || {
+    let ffmpeg = || resolve_ffmpeg_command(&worker_app, "ffmpeg").map(Command::from);
+    let ffprobe = || resolve_ffmpeg_command(&worker_app, "ffprobe").map(Command::from);
+    let runner = JobRunner {
+      ffmpeg: &ffmpeg,
+      ffprobe: &ffprobe,
+      cancel_flag: &cancel_flag,
+      reporter: &worker_events
+    };
+    // Entries requeued after a crash or retried after a failure pick up their segments.
+    match resumable_checkpoint(&entry.spec) {
+      Some(checkpoint_dir) => runner.resume(&entry.id, &checkpoint_dir),
+      None => runner.run(&entry.id, &entry.spec)
+    }
+  })
+  .await
+  .map_err(|error| BitrotError::internal(format!("Queue worker failed: {error}")))
+  .and_then(|result| result);
+  events.finished(&result);
+
+  state.jobs.finish(&job_id);
+  let _ = state.update(&app, |queue| {
+    queue.finish(&job_id, &result);
+    Ok(())
+  });
+  pump_render_queue(&app);
+}
+
+#[tauri::command]
+pub fn render_queue_list(state: State<'_, RenderQueueState>) -> RenderQueueSnapshot {
+  let mut snapshot = state.snapshot();
+  snapshot.load_error = state
+    .load_error
+    .lock()
+    .unwrap_or_else(|error| error.into_inner())
+    .take();
+  snapshot
+}
+
+// Adds a batch of jobs in order and returns their queue ids.
+#[tauri::command]
+pub fn render_queue_add(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  jobs: Vec<JobSpec>
+) -> Result<Vec<String>, BitrotError> {
+  for (index, spec) in jobs.iter().enumerate() {
+    spec.validate().map_err(|mut error| {
+      error.message = format!("Job {}: {}", index + 1, error.message);
+      error
+    })?;
+  }
+  let ids = state.update(&app, |queue| {
+    Ok(jobs.into_iter().map(|spec| queue.add(spec)).collect())
+  })?;
+  pump_render_queue(&app);
+  Ok(ids)
+}
+
+#[tauri::command]
+pub fn render_queue_move(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  id: String,
+  index: usize
+) -> Result<(), BitrotError> {
+  state.update(&app, |queue| queue.move_entry(&id, index))
+}
+
+#[tauri::command]
+pub fn render_queue_retry(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  id: String
+) -> Result<(), BitrotError> {
+  state.update(&app, |queue| queue.retry(&id))?;
+  pump_render_queue(&app);
+  Ok(())
+}
+
+// Stops a running entry, which stays in the queue as canceled once its job has shut down
+// and can be retried; the next entry starts from `run_queue_entry` after that. Queued
+// entries are canceled right away.
+#[tauri::command]
+pub fn render_queue_cancel(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  id: String
+) -> Result<(), BitrotError> {
+  if state.update(&app, |queue| queue.cancel(&id))? {
+    state.jobs.cancel(&id);
+  }
+  Ok(())
+}
+
+// Running entries have to be canceled first.
+#[tauri::command]
+pub fn render_queue_remove(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  id: String
+) -> Result<(), BitrotError> {
+  state.update(&app, |queue| queue.remove(&id).map(|_| ()))
+}
+
+#[tauri::command]
+pub fn render_queue_set_concurrency(
+  app: AppHandle,
+  state: State<'_, RenderQueueState>,
+  concurrency: usize
+) -> Result<(), BitrotError> {
+  state.update(&app, |queue| {
+    queue.set_concurrency(concurrency);
+    Ok(())
+  })?;
+  pump_render_queue(&app);
+  Ok(())
+}
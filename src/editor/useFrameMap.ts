@@ -2,6 +2,7 @@ import { useEffect, useState } from "react";
 import type { VideoAsset } from "@/domain/video";
 import type { FrameMap } from "@/analysis/frameMap";
 import probeFrameMap from "@/analysis/probeFrameMap";
+import { errorMessage } from "@/system/commandError";
 
 export type FrameMapState = {
   status: "idle" | "loading" | "ready" | "error";
@@ -51,7 +52,7 @@ const useFrameMap = (
         if (!isMounted) {
           return;
         }
-        const message = error instanceof Error ? error.message : "Unknown error";
+        const message = errorMessage(error, "Unknown error");
         setState({ status: "error", error: message });
       });
 
@@ -21,6 +21,7 @@ import type { BlockShiftConfig } from "@/modes/blockShift";
 import type { VaporwaveConfig } from "@/modes/vaporwave";
 import type { KaleidoscopeConfig } from "@/modes/kaleidoscope";
 import { cleanupPreviewFile, registerPreviewFile } from "@/system/previewFiles";
+import { errorMessage } from "@/system/commandError";
 import makeDebug from "@/utils/debug";
 
 type FramePreviewOptions = {
@@ -155,8 +156,7 @@ const useFramePreview = ({
         if (!isMountedRef.current || requestId !== requestIdRef.current) {
           return;
         }
-        const message =
-          error instanceof Error ? error.message : "Preview capture failed.";
+        const message = errorMessage(error, "Preview capture failed.");
         debug("preview capture failed: %O", error);
         setManualError(message);
         return;
@@ -253,8 +253,7 @@ const useFramePreview = ({
         if (!isMountedRef.current || requestId !== requestIdRef.current) {
           return;
         }
-        const message =
-          error instanceof Error ? error.message : "Preview render failed.";
+        const message = errorMessage(error, "Preview render failed.");
         debug("preview failed: %O", error);
         setManualError(message);
       }
@@ -1,6 +1,7 @@
 import { useEffect, useState } from "react";
 import type { VideoAsset } from "@/domain/video";
 import { probeVideo, type VideoMetadata } from "@/system/ffprobe";
+import { errorMessage } from "@/system/commandError";
 
 export type MetadataState = {
   status: "idle" | "loading" | "ready" | "error";
@@ -44,7 +45,7 @@ const useVideoMetadata = (asset: VideoAsset | null) => {
         if (!isMounted) {
           return;
         }
-        const message = error instanceof Error ? error.message : "Unknown error";
+        const message = errorMessage(error, "Unknown error");
         setState({ status: "error", error: message });
       });
 
@@ -12,6 +12,7 @@ import {
 import { DEFAULT_EXPORT_PROFILE, type ExportProfile } from "@/jobs/exportProfile";
 import type { BlockShiftConfig } from "@/modes/blockShift";
 import { sanitizePath } from "@/system/path";
+import { errorMessage, isCanceledError } from "@/system/commandError";
 import { buildNativeEncoding } from "@/jobs/nativeEncoding";
 import { attachNativeJobListeners } from "@/jobs/nativeJobEvents";
 import { resolveNativeFps, resolveEvenDimensions } from "@/jobs/nativeVideo";
@@ -40,9 +41,6 @@ const debug = makeDebug("jobs:block-shift");
 const createJobId = () =>
   `block-shift-${Date.now()}-${Math.random().toString(16).slice(2, 8)}`;
 
-const isCancelError = (message: string) =>
-  message.toLowerCase().includes("canceled");
-
 // Runs the native block shift pipeline for macroblock displacement.
 export const runBlockShiftJob = async (
   asset: VideoAsset,
@@ -132,10 +130,9 @@ export const runBlockShiftJob = async (
       callbacks.onClose(0, null);
     })
     .catch((error) => {
-      const message =
-        error instanceof Error ? error.message : String(error ?? "Block shift failed");
+      const message = errorMessage(error, "Block shift failed");
       debug("block-shift failed: %O", error);
-      if (!canceled && !isCancelError(message)) {
+      if (!canceled && !isCanceledError(error)) {
         callbacks.onError(message);
       }
       callbacks.onClose(1, null);
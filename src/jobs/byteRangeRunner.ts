@@ -13,6 +13,7 @@ import {
 import { DEFAULT_EXPORT_PROFILE, type ExportProfile } from "@/jobs/exportProfile";
 import type { ModuloMappingConfig } from "@/modes/moduloMapping";
 import { sanitizePath } from "@/system/path";
+import { errorMessage, isCanceledError } from "@/system/commandError";
 import { buildNativeEncoding } from "@/jobs/nativeEncoding";
 import { attachNativeJobListeners } from "@/jobs/nativeJobEvents";
 import { resolveNativeFps, resolveEvenDimensions } from "@/jobs/nativeVideo";
@@ -41,9 +42,6 @@ const debug = makeDebug("jobs:modulo-mapping");
 const createJobId = () =>
   `modulo-mapping-${Date.now()}-${Math.random().toString(16).slice(2, 8)}`;
 
-const isCancelError = (message: string) =>
-  message.toLowerCase().includes("canceled");
-
 // Runs the native modulo mapping pipeline for frame-level corruption.
 export const runModuloMappingJob = async (
   asset: VideoAsset,
@@ -133,10 +131,9 @@ export const runModuloMappingJob = async (
       callbacks.onClose(0, null);
     })
     .catch((error) => {
-      const message =
-        error instanceof Error ? error.message : String(error ?? "Modulo mapping failed");
+      const message = errorMessage(error, "Modulo mapping failed");
       debug("modulo-mapping failed: %O", error);
-      if (!canceled && !isCancelError(message)) {
+      if (!canceled && !isCanceledError(error)) {
         callbacks.onError(message);
       }
       callbacks.onClose(1, null);
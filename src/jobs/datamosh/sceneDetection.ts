@@ -2,6 +2,7 @@
 import { spawnWithFallback } from "@/system/shellCommand";
 import type { TrimRange } from "@/jobs/trim";
 import { buildTrimArgs } from "@/jobs/datamosh/normalizeArgs";
+import { errorMessage } from "@/system/commandError";
 import makeDebug from "@/utils/debug";
 
 type SceneWindow = {
@@ -148,8 +149,7 @@ const detectSceneCuts = async (
         });
       });
     } catch (error) {
-      const message =
-        error instanceof Error ? error.message : String(error ?? "Scene detection failed");
+      const message = errorMessage(error, "Scene detection failed");
       finalize(new Error(message));
     }
   });
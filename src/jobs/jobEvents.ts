@@ -0,0 +1,56 @@
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { CommandError } from "@/system/commandError";
+
+// Unified job events (Rust: bitrot_core::events). Every native mode, queued render,
+// resume and raw ffmpeg/ffprobe job reports here, keyed by jobId and kind.
+export const JOB_EVENT_VERSION = 1;
+
+export type JobLogLevel = "debug" | "info" | "warn" | "error";
+
+export type JobStage = "render" | "concat" | "secondPass" | "mux";
+
+type JobEventBase = {
+  version: number;
+  jobId: string;
+  // Mode tag ("block-shift", "datamosh") or "ffmpeg"/"ffprobe" for raw jobs.
+  kind: string;
+};
+
+export type JobEventPayload =
+  | { type: "started" }
+  | {
+      type: "progress";
+      frame: number;
+      totalFrames?: number | null;
+      percent: number;
+      fps?: number | null;
+      speed?: number | null;
+      outTimeSeconds?: number | null;
+      elapsedSeconds?: number | null;
+      etaSeconds?: number | null;
+      stageWaits?: {
+        decodeSeconds: number;
+        processInputSeconds: number;
+        processOutputSeconds: number;
+        encodeSeconds: number;
+      };
+    }
+  | {
+      type: "log";
+      level: JobLogLevel;
+      message: string;
+      stream?: "stdout" | "stderr";
+    }
+  | { type: "preview"; frame: number; path: string }
+  | { type: "stage"; stage: JobStage }
+  | { type: "finished" }
+  | { type: "failed"; error: CommandError }
+  | { type: "canceled" };
+
+export type JobEvent = JobEventBase & JobEventPayload;
+
+// Listens to every job; filter on jobId/kind in the callback.
+export const onJobEvent = (callback: (event: JobEvent) => void) =>
+  getCurrentWindow().listen<JobEvent>("job-event", (event) =>
+    callback(event.payload)
+  );
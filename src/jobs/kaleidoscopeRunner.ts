@@ -12,6 +12,7 @@ import {
 import { DEFAULT_EXPORT_PROFILE, type ExportProfile } from "@/jobs/exportProfile";
 import type { KaleidoscopeConfig } from "@/modes/kaleidoscope";
 import { sanitizePath } from "@/system/path";
+import { errorMessage, isCanceledError } from "@/system/commandError";
 import { buildNativeEncoding } from "@/jobs/nativeEncoding";
 import { attachNativeJobListeners } from "@/jobs/nativeJobEvents";
 import { resolveNativeFps, resolveEvenDimensions } from "@/jobs/nativeVideo";
@@ -40,9 +41,6 @@ const debug = makeDebug("jobs:kaleidoscope");
 const createJobId = () =>
   `kaleidoscope-${Date.now()}-${Math.random().toString(16).slice(2, 8)}`;
 
-const isCancelError = (message: string) =>
-  message.toLowerCase().includes("canceled");
-
 // Runs the native kaleidoscope pipeline for mirrored symmetry effects.
 export const runKaleidoscopeJob = async (
   asset: VideoAsset,
@@ -132,12 +130,9 @@ export const runKaleidoscopeJob = async (
       callbacks.onClose(0, null);
     })
     .catch((error) => {
-      const message =
-        error instanceof Error
-          ? error.message
-          : String(error ?? "Kaleidoscope failed");
+      const message = errorMessage(error, "Kaleidoscope failed");
       debug("kaleidoscope failed: %O", error);
-      if (!canceled && !isCancelError(message)) {
+      if (!canceled && !isCanceledError(error)) {
         callbacks.onError(message);
       }
       callbacks.onClose(1, null);
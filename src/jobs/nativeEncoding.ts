@@ -9,8 +9,34 @@ import {
 } from "@/jobs/exportProfile";
 import { parseExtraArgs } from "@/jobs/ffmpegArgs";
 
+// Palette, caps and loop settings for animated GIF/WebP output.
+export type AnimationOptions = {
+  palette?: "global" | "perScene";
+  dither?: "bayer" | "heckbert" | "floydSteinberg" | "sierra2" | "sierra2_4a" | "none";
+  bayerScale?: number;
+  sceneThreshold?: number;
+  maxFps?: number;
+  maxWidth?: number;
+  maxHeight?: number;
+  loopCount?: number;
+  quality?: number;
+  lossless?: boolean;
+};
+
+// Encoders the native path accepts beyond the export profile's choices.
+export type NativeVideoEncoder =
+  | VideoEncoder
+  | "libx265"
+  | "libsvtav1"
+  | "libaom-av1"
+  | "prores_ks"
+  | "qtrle"
+  | "png"
+  | "ffv1"
+  | "utvideo";
+
 export type NativeEncoding = {
-  encoder: VideoEncoder;
+  encoder: NativeVideoEncoder;
   preset: string;
   crf?: number;
   cq?: number;
@@ -18,13 +44,26 @@ export type NativeEncoding = {
   targetBitrateKbps?: number;
   vp9Deadline?: string;
   vp9CpuUsed?: number;
-  format: ExportFormat;
+  format: ExportFormat | "avi" | "gif" | "webp";
   audioEnabled: boolean;
+  // "aac", "opus", "pcm" or "copy"; intra-only encoders default to PCM.
   audioCodec?: string;
   audioBitrateKbps?: number;
   // Safe extra args split for native encode + mux steps.
   extraEncodeArgs: string[];
   extraMuxArgs: string[];
+  // 16-bit per channel processing (rgba64le) and the output pixel format
+  // (yuv420p by default; yuv422p/yuv444p keep chroma detail, gbrp is planar RGB).
+  highBitDepth?: boolean;
+  pixelFormat?: string;
+  // Keep alpha (ProRes 4444, VP9, QuickTime Animation, PNG, FFV1 or UT Video only).
+  alpha?: boolean;
+  animation?: AnimationOptions;
+  // SVT-AV1 preset (0-13) or libaom cpu-used (0-8), and the AV1 tile grid.
+  av1Speed?: number;
+  tiles?: { columns: number; rows: number };
+  // Whole-file budget in decimal MB; renders two-pass at the bitrate that fits.
+  targetSizeMb?: number;
 };
 
 const resolveAudioCodec = (format: ExportFormat) =>
@@ -0,0 +1,10 @@
+import { invoke } from "@tauri-apps/api/core";
+
+// Continues an interrupted native render from its last finished segment.
+// Follow it on `onJobEvent` ("job-event"), filtered by the new jobId and the mode as
+// kind; the mode's cancel command stops it like a fresh render.
+export const resumeNativeRender = (
+  jobId: string,
+  outputPath: string,
+  mode: string
+) => invoke<void>("native_resume", { jobId, outputPath, mode });
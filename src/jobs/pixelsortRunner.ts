@@ -12,6 +12,7 @@ import {
 import { DEFAULT_EXPORT_PROFILE, type ExportProfile } from "@/jobs/exportProfile";
 import type { PixelsortConfig } from "@/modes/pixelsort";
 import { sanitizePath } from "@/system/path";
+import { errorMessage, isCanceledError } from "@/system/commandError";
 import { buildNativeEncoding } from "@/jobs/nativeEncoding";
 import { attachNativeJobListeners } from "@/jobs/nativeJobEvents";
 import { resolveNativeFps, resolveEvenDimensions } from "@/jobs/nativeVideo";
@@ -40,9 +41,6 @@ const PIXELSORT_EVENTS = {
   log: "pixelsort-log"
 };
 
-const isCancelError = (message: string) =>
-  message.toLowerCase().includes("canceled");
-
 // Runs the Rust-powered pixel sort pipeline for full per-pixel control.
 export const runPixelsortJob = async (
   asset: VideoAsset,
@@ -132,10 +130,9 @@ export const runPixelsortJob = async (
       callbacks.onClose(0, null);
     })
     .catch((error) => {
-      const message =
-        error instanceof Error ? error.message : String(error ?? "Pixel sort failed");
+      const message = errorMessage(error, "Pixel sort failed");
       debug("pixelsort failed: %O", error);
-      if (!canceled && !isCancelError(message)) {
+      if (!canceled && !isCanceledError(error)) {
         callbacks.onError(message);
       }
       callbacks.onClose(1, null);
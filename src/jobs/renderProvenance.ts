@@ -0,0 +1,27 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { CommandSource } from "@/system/shellCommand";
+import type { RenderQueueJobSpec } from "@/jobs/renderQueue";
+
+// Finished native renders write "<output>.bitrot.json" next to the output
+// (Rust: bitrot_core::provenance) with the job spec that produced them.
+export type RenderProvenance = {
+  version: number;
+  bitrotVersion: string;
+  renderedAt: number;
+  spec: RenderQueueJobSpec;
+  ffmpegVersion?: string | null;
+  ffmpegSource: CommandSource;
+  input: {
+    sizeBytes: number;
+    modifiedUnix?: number | null;
+    sampleHash: string;
+  };
+};
+
+export const renderSidecarPath = (outputPath: string) =>
+  `${outputPath.trim()}.bitrot.json`;
+
+// Renders the sidecar's spec again to the same output. Events arrive like a fresh
+// render of that mode, keyed by jobId; a changed input is reported as a warning log.
+export const replayRender = (jobId: string, sidecarPath: string) =>
+  invoke<void>("render_replay", { jobId, sidecarPath });
@@ -0,0 +1,82 @@
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { CommandError } from "@/system/commandError";
+
+// Typed wrappers for the Rust-side render queue (persisted across restarts).
+export type RenderQueueStatus =
+  | "queued"
+  | "running"
+  | "completed"
+  | "failed"
+  | "canceled";
+
+// Same shape as a bitrot-cli job spec: { mode, config, input, output, encoding, ... }.
+export type RenderQueueJobSpec = {
+  mode: string;
+  config: unknown;
+  input: string;
+  output: string;
+  encoding?: unknown;
+  automation?: unknown;
+  mask?: unknown;
+  activeWindows?: unknown;
+  width?: number;
+  height?: number;
+  fps?: number;
+  durationSeconds?: number;
+  trimStartSeconds?: number;
+  trimEndSeconds?: number;
+  // Still image inputs: frame index, JPEG/WebP quality and 16-bit processing.
+  still?: { frameIndex?: number; quality?: number; highBitDepth?: boolean };
+};
+
+export type RenderQueueEntry = {
+  id: string;
+  spec: RenderQueueJobSpec;
+  status: RenderQueueStatus;
+  error?: CommandError | null;
+  attempts: number;
+  addedAt: number;
+  finishedAt?: number | null;
+};
+
+export type RenderQueueSnapshot = {
+  concurrency: number;
+  entries: RenderQueueEntry[];
+  // Set once, on the first list after the saved queue was unreadable and moved to
+  // render-queue.json.bad.
+  loadError?: CommandError;
+};
+
+export const listRenderQueue = () =>
+  invoke<RenderQueueSnapshot>("render_queue_list");
+
+// Returns the new entry ids; follow them on `onJobEvent` ("job-event"), filtered by
+// entry id as jobId and the mode as kind.
+export const addToRenderQueue = (jobs: RenderQueueJobSpec[]) =>
+  invoke<string[]>("render_queue_add", { jobs });
+
+export const moveRenderQueueEntry = (id: string, index: number) =>
+  invoke<void>("render_queue_move", { id, index });
+
+export const retryRenderQueueEntry = (id: string) =>
+  invoke<void>("render_queue_retry", { id });
+
+// A running entry turns "canceled" once its job has stopped; it can be retried.
+export const cancelRenderQueueEntry = (id: string) =>
+  invoke<void>("render_queue_cancel", { id });
+
+// Running entries must be canceled first.
+export const removeRenderQueueEntry = (id: string) =>
+  invoke<void>("render_queue_remove", { id });
+
+export const setRenderQueueConcurrency = (concurrency: number) =>
+  invoke<void>("render_queue_set_concurrency", { concurrency });
+
+export const onRenderQueueChanged = (
+  callback: (snapshot: RenderQueueSnapshot) => void
+) =>
+  getCurrentWindow().listen<RenderQueueSnapshot>(
+    "render-queue-changed",
+    (event) => callback(event.payload)
+  );
@@ -0,0 +1,8 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { RenderQueueJobSpec } from "@/jobs/renderQueue";
+
+// Runs any native mode on a single PNG/JPEG/WebP/TIFF image at full resolution and
+// writes the format named by the output extension. Events arrive like a render of the
+// spec's mode, keyed by jobId.
+export const renderStill = (jobId: string, spec: RenderQueueJobSpec) =>
+  invoke<void>("render_still", { jobId, spec });
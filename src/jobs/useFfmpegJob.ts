@@ -8,6 +8,7 @@ import type { JobProgress, JobState } from "@/jobs/types";
 import { buildDefaultOutputPath } from "@/jobs/output";
 import type { ExportProfile } from "@/jobs/exportProfile";
 import { cleanupJob, registerJobCleanup } from "@/jobs/jobCleanup";
+import { errorMessage } from "@/system/commandError";
 import makeDebug from "@/utils/debug";
 
 const initialProgress: JobProgress = {
@@ -202,7 +203,7 @@ const useFfmpegJob = () => {
           void cleanupJob(cleanupPathRef.current, { keepOutput: false });
         }
         await setTaskbarProgress(ProgressBarStatus.None, 0);
-        const message = error instanceof Error ? error.message : "Failed to start ffmpeg";
+        const message = errorMessage(error, "Failed to start ffmpeg");
         debug("run exception: %O", error);
         setJob((prev) => ({
           ...prev,
@@ -0,0 +1,46 @@
+// Structured errors rejected by Tauri commands (Rust: bitrot_core::error::BitrotError).
+// Match on `code`; `message` is meant for people.
+export type CommandErrorCode =
+  | "canceled"
+  | "invalid-request"
+  | "output-conflict"
+  | "not-found"
+  | "ffmpeg-unavailable"
+  | "ffmpeg-unsupported"
+  | "spawn-failed"
+  | "decode-failed"
+  | "encode-failed"
+  | "mux-failed"
+  | "ffmpeg-failed"
+  | "datamosh-failed"
+  | "preview-failed"
+  | "io"
+  | "internal";
+
+export type CommandError = {
+  code: CommandErrorCode;
+  message: string;
+  // Last lines of ffmpeg's stderr when a process failed.
+  stderrTail?: string;
+  retryable: boolean;
+};
+
+export const isCommandError = (error: unknown): error is CommandError =>
+  typeof error === "object" &&
+  error !== null &&
+  typeof (error as CommandError).code === "string" &&
+  typeof (error as CommandError).message === "string";
+
+// Human-readable message for anything a command or promise rejected with.
+export const errorMessage = (error: unknown, fallback: string) => {
+  if (isCommandError(error) || error instanceof Error) {
+    return error.message || fallback;
+  }
+  if (typeof error === "string" && error.trim()) {
+    return error;
+  }
+  return fallback;
+};
+
+export const isCanceledError = (error: unknown) =>
+  isCommandError(error) && error.code === "canceled";
@@ -1,4 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
 import { executeWithFallback, type CommandSource } from "@/system/shellCommand";
+import { errorMessage } from "@/system/commandError";
 import makeDebug from "@/utils/debug";
 
 export type FfmpegStatus = {
@@ -10,22 +12,24 @@ export type FfmpegStatus = {
   source?: CommandSource;
 };
 
+// What the resolved ffmpeg build can encode (Rust: bitrot_core::toolchain::ToolchainReport).
+export type FfmpegCapabilities = {
+  version: string;
+  hasLibx265: boolean;
+  hasLibsvtav1: boolean;
+  hasLibvpx: boolean;
+  alphaEncoders: string[];
+  encoders: string[];
+  filters: string[];
+  pixelFormats: string[];
+};
+
 const debug = makeDebug("system:ffmpeg");
 
 const getFirstLine = (value: string) =>
   value.split(/\r?\n/).map((line) => line.trim()).find(Boolean) ?? "";
 
-const formatError = (error: unknown) => {
-  if (error instanceof Error) {
-    return error.message;
-  }
-
-  if (typeof error === "string") {
-    return error;
-  }
-
-  return "Unknown error";
-};
+const formatError = (error: unknown) => errorMessage(error, "Unknown error");
 
 const findVersionLine = (output: string, prefix: string) =>
   output
@@ -89,3 +93,9 @@ export const checkFfmpegSidecars = async (): Promise<FfmpegStatus> => {
     source
   };
 };
+
+// Cached on the Rust side; `refresh` probes the binary again.
+export const getFfmpegCapabilities = (refresh = false) =>
+  invoke<{ report: FfmpegCapabilities; source: CommandSource }>("ffmpeg_capabilities", {
+    refresh
+  });
@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
+import type { NativeEncoding } from "@/jobs/nativeEncoding";
 import makeDebug from "@/utils/debug";
 
 type ProgramId = "ffmpeg" | "ffprobe";
@@ -8,6 +9,8 @@ export type CommandSource = "local" | "sidecar" | "path";
 export type SpawnOptions = {
   cwd?: string;
   env?: Record<string, string>;
+  // Checked against the ffmpeg capability report before the process starts.
+  encoding?: NativeEncoding;
 };
 
 type StreamHandler = (data: string) => void;
@@ -171,7 +174,7 @@ export const spawnWithFallback = async (
   program: ProgramId,
   args: string[],
   bind: CommandBinder,
-  _options?: SpawnOptions
+  options?: SpawnOptions
 ) => {
   const jobId = createJobId();
   const command = createCommand();
@@ -179,7 +182,12 @@ export const spawnWithFallback = async (
   let response: SpawnResponse;
 
   try {
-    response = await invoke<SpawnResponse>("ffmpeg_spawn", { program, args, jobId });
+    response = await invoke<SpawnResponse>("ffmpeg_spawn", {
+      program,
+      args,
+      jobId,
+      encoding: options?.encoding
+    });
   } catch (error) {
     cleanup();
     debug("%s spawn failed: %O", program, error);